@@ -0,0 +1,22 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use web_browser_core::clock::Clock;
+
+// std::time::SystemTimeを使った時計
+#[derive(Debug, Clone, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        // システムの時刻が1970年より前になっている場合は0にする
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
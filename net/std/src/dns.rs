@@ -0,0 +1,36 @@
+use std::net::{SocketAddr, ToSocketAddrs};
+use web_browser_core::dns::{IpAddr, Resolver};
+use web_browser_core::http::transport::NetworkError;
+
+// std::net::ToSocketAddrsを使ってOSのリゾルバで名前解決する
+#[derive(Debug, Clone, Default)]
+pub struct SystemResolver;
+
+impl SystemResolver {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Resolver for SystemResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetworkError> {
+        let addrs = match (host, 0).to_socket_addrs() {
+            Ok(addrs) => addrs,
+            Err(_) => return Err(NetworkError::Dns(host.to_string())),
+        };
+        Ok(addrs
+            .map(|addr| match addr.ip() {
+                std::net::IpAddr::V4(ip) => IpAddr::V4(ip.octets()),
+                std::net::IpAddr::V6(ip) => IpAddr::V6(ip.segments()),
+            })
+            .collect())
+    }
+}
+
+// 解決したアドレスとポート番号から接続先のアドレスを作る
+pub fn socket_addr(address: IpAddr, port: u16) -> SocketAddr {
+    match address {
+        IpAddr::V4(octets) => SocketAddr::from((octets, port)),
+        IpAddr::V6(segments) => SocketAddr::from((segments, port)),
+    }
+}
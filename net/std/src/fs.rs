@@ -0,0 +1,22 @@
+use std::io::ErrorKind;
+use web_browser_core::loader::{FileSystem, LoadError};
+
+// std::fsを使ってOSのファイルシステムからfile: URLのファイルを読み込む
+#[derive(Debug, Clone, Default)]
+pub struct StdFileSystem;
+
+impl StdFileSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl FileSystem for StdFileSystem {
+    fn read(&self, path: &str) -> Result<Vec<u8>, LoadError> {
+        std::fs::read(path).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => LoadError::NotFound(path.to_string()),
+            ErrorKind::PermissionDenied => LoadError::PermissionDenied(path.to_string()),
+            _ => LoadError::Read(format!("{}: {}", path, e)),
+        })
+    }
+}
@@ -0,0 +1,299 @@
+use crate::dns::{socket_addr, SystemResolver};
+use crate::tls::{tls_error, TlsConfig, TlsStream};
+use std::cell::RefCell;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::rc::Rc;
+use std::time::Duration;
+use web_browser_core::dns::{IpAddr, Resolver};
+use web_browser_core::error::Error;
+use web_browser_core::http::connection::{exchange, Connection, ExchangeError};
+use web_browser_core::http::parser::ResponseParser;
+use web_browser_core::http::pool::ConnectionPool;
+use web_browser_core::http::proxy::ProxyConfig;
+use web_browser_core::http::request::{HttpRequest, Method};
+use web_browser_core::http::transport::{HttpTransport, NetworkError};
+use web_browser_core::http::HttpResponse;
+use web_browser_core::url::Url;
+
+// 接続とレスポンスの受信を待つ時間のデフォルト値
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 平文のTCPの接続か、その上のTLSの接続
+#[derive(Debug)]
+enum Stream {
+    Tcp(TcpStream),
+    Tls(Box<TlsStream>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+impl Connection for Stream {
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), NetworkError> {
+        Write::write_all(self, bytes)
+            .and_then(|_| self.flush())
+            .map_err(|e| network_error(e, NetworkError::Send))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, NetworkError> {
+        loop {
+            match Read::read(self, buf) {
+                Ok(bytes) => return Ok(bytes),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(network_error(e, NetworkError::Receive)),
+            }
+        }
+    }
+}
+
+// std::net::TcpStreamを使ったHTTPクライアント。Linuxなどのホスト上でブラウザを動かすときに使う
+// クローンしたクライアントは接続プールを共有する
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    resolver: Rc<dyn Resolver>,
+    tls: TlsConfig,
+    pool: Rc<RefCell<ConnectionPool<Stream>>>,
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        Self {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            resolver: Rc::new(SystemResolver::new()),
+            tls: TlsConfig::new(),
+            pool: Rc::new(RefCell::new(ConnectionPool::new())),
+        }
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.read_timeout = timeout;
+    }
+
+    // 名前解決に使うリゾルバ。デフォルトではOSのリゾルバを使う
+    // Browser::resolver()を渡すと、ブラウザに設定したホスト名の表とキャッシュが使われる
+    pub fn set_resolver(&mut self, resolver: Rc<dyn Resolver>) {
+        self.resolver = resolver;
+    }
+
+    // httpsのURLに接続するときに使う、信頼する認証局などの設定
+    // 変更するとプールしている接続は閉じる
+    pub fn set_tls_config(&mut self, tls: TlsConfig) {
+        self.tls = tls;
+        self.pool.borrow_mut().clear();
+    }
+
+    // アイドル状態で保持しておく接続の数の上限。0の場合は接続を再利用しない
+    pub fn set_max_idle_connections(&self, max_idle: usize) {
+        self.pool.borrow_mut().set_max_idle(max_idle);
+    }
+
+    pub fn idle_connections(&self) -> usize {
+        self.pool.borrow().len()
+    }
+
+    pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
+        // パスは / から始まっていてもいなくてもよい
+        let url =
+            Url::parse(&format!("http://{}:{}/", host, port)).and_then(|base| base.join(&path))?;
+        let request = HttpRequest::builder(Method::Get, url)
+            .header("Accept", "text/html")
+            .build()?;
+        self.send(request)
+    }
+
+    // リクエストのURLのホストに接続し、リクエストを送ってレスポンスを受け取る
+    pub fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.send_streaming(request, &mut |_, _| {})
+    }
+
+    // ボディを受信するたびにon_bodyを呼び出す
+    pub fn send_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let url = request.url();
+        let port = match url.port_or_default() {
+            Some(port) => port,
+            None => return Err(Error::UnexpectedInput("Failed to find a port".to_string())),
+        };
+
+        // 同じオリジンへのアイドル状態の接続があれば再利用する
+        // サーバーがすでに閉じていた場合、冪等なリクエストは新しい接続で1回だけ送り直す
+        // https://www.rfc-editor.org/rfc/rfc9112#section-9.3.1
+        // プロキシを通すかどうかが変わった接続は再利用しない
+        let proxy = request.proxy();
+        let pooled = self.pool.borrow_mut().take(&url, proxy.as_ref());
+        if let Some(stream) = pooled {
+            match self.exchange(stream, &request, on_body) {
+                Err(ExchangeError::Closed(_)) if request.method().is_idempotent() => {}
+                result => return result.map_err(Error::from),
+            }
+        }
+
+        // プロキシを通す場合はプロキシに接続する。httpsのURLはCONNECTで作ったトンネルの中でTLSを使う
+        let mut stream = match &proxy {
+            Some(proxy) => self.connect(&proxy.host(), proxy.port())?,
+            None => self.connect(&url.hostname(), port)?,
+        };
+        let stream = if url.scheme() == "https" {
+            if let Some(proxy) = &proxy {
+                self.tunnel(&mut stream, proxy, &url)?;
+            }
+            match self.tls.connect(&url.hostname(), stream) {
+                Ok(stream) => Stream::Tls(Box::new(stream)),
+                Err(e) => return Err(network_error(e, NetworkError::Tls).into()),
+            }
+        } else {
+            Stream::Tcp(stream)
+        };
+        Ok(self.exchange(stream, &request, on_body)?)
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-9.3.6
+    // プロキシにURLのホストとポートへのトンネルを作ってもらう
+    fn tunnel(&self, stream: &mut TcpStream, proxy: &ProxyConfig, url: &Url) -> Result<(), Error> {
+        let request = proxy.connect_request(url);
+        if let Err(e) = stream.write_all(&request.to_bytes()) {
+            return Err(network_error(e, NetworkError::Send).into());
+        }
+
+        let mut parser = ResponseParser::for_method(Method::Connect);
+        while !parser.is_complete() {
+            let mut buf = [0u8; 4096];
+            let bytes_read = match stream.read(&mut buf) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(network_error(e, NetworkError::Receive).into()),
+            };
+            if bytes_read == 0 {
+                break;
+            }
+            parser.feed(&buf[..bytes_read])?;
+        }
+
+        let response = parser.finish()?;
+        if !(200..300).contains(&response.status_code()) {
+            let reason = format!("{} {}", response.status_code(), response.reason());
+            return Err(NetworkError::Proxy(reason).into());
+        }
+        Ok(())
+    }
+
+    // 接続にリクエストを送ってレスポンスを受け取る。接続を維持できる場合はプールに戻す
+    fn exchange(
+        &self,
+        stream: Stream,
+        request: &HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, ExchangeError> {
+        let (response, stream) = exchange(stream, request, on_body)?;
+        if let Some(stream) = stream {
+            self.pool
+                .borrow_mut()
+                .put(&request.url(), request.proxy().as_ref(), stream);
+        }
+        Ok(response)
+    }
+
+    // リゾルバでホスト名を解決し、見つかったアドレスに順番に接続を試みる
+    // [::1] のようなIPアドレスの場合はリゾルバを使わない
+    fn connect(&self, host: &str, port: u16) -> Result<TcpStream, Error> {
+        let addrs = match IpAddr::parse(host) {
+            Some(address) => vec![address],
+            None => self.resolver.resolve(host)?,
+        };
+
+        let mut last_error = NetworkError::Dns(host.to_string());
+        for addr in addrs.into_iter().map(|addr| socket_addr(addr, port)) {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => {
+                    if let Err(e) = stream
+                        .set_read_timeout(Some(self.read_timeout))
+                        .and_then(|_| stream.set_write_timeout(Some(self.read_timeout)))
+                    {
+                        let reason = format!("failed to set timeouts: {}", e);
+                        return Err(NetworkError::Connect(reason).into());
+                    }
+                    return Ok(stream);
+                }
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    last_error = NetworkError::ConnectionRefused(addr.to_string())
+                }
+                Err(e) => {
+                    last_error = network_error(e, |reason| {
+                        NetworkError::Connect(format!("{}: {}", addr, reason))
+                    })
+                }
+            }
+        }
+        Err(last_error.into())
+    }
+}
+
+// タイムアウトと接続の切断、TLSのエラーは種類で区別し、それ以外はotherでエラーにする
+fn network_error(e: io::Error, other: impl FnOnce(String) -> NetworkError) -> NetworkError {
+    if let Some(e) = e.get_ref().and_then(|e| e.downcast_ref::<rustls::Error>()) {
+        return tls_error(e);
+    }
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => NetworkError::Timeout,
+        // TLSの接続はclose_notifyを送らずに閉じられるとUnexpectedEofになる
+        ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::BrokenPipe
+        | ErrorKind::UnexpectedEof => NetworkError::ConnectionReset,
+        _ => other(e.to_string()),
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpTransport for HttpClient {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        HttpClient::send(self, request)
+    }
+
+    fn send_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        HttpClient::send_streaming(self, request, on_body)
+    }
+}
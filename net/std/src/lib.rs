@@ -0,0 +1,6 @@
+pub mod clock;
+pub mod dns;
+pub mod fs;
+pub mod http;
+pub mod random;
+pub mod tls;
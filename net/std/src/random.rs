@@ -0,0 +1,32 @@
+use std::fs::File;
+use std::io::Read;
+use web_browser_core::random::RandomSource;
+
+// OSが用意している暗号論的に安全な乱数の読み出し口
+// https://man7.org/linux/man-pages/man4/urandom.4.html
+const URANDOM: &str = "/dev/urandom";
+
+// /dev/urandomから読み出す乱数
+// /dev/urandomがない環境（Windowsなど）では使えない
+#[derive(Debug, Clone, Default)]
+pub struct SystemRandom;
+
+impl SystemRandom {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl RandomSource for SystemRandom {
+    fn fill(&self, buf: &mut [u8]) {
+        // 読み出せなかった場合は、乱数を得られない環境と同じく0で埋める
+        let read = File::open(URANDOM).and_then(|mut file| file.read_exact(buf));
+        if read.is_err() {
+            buf.fill(0);
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        File::open(URANDOM).is_ok()
+    }
+}
@@ -0,0 +1,96 @@
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::io::{self, ErrorKind};
+use std::net::TcpStream;
+use std::sync::Arc;
+use web_browser_core::http::transport::NetworkError;
+
+// TCPの接続の上でハンドシェイクを終えたTLSの接続
+pub type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+// https://www.rfc-editor.org/rfc/rfc8446
+// httpsのURLに接続するときの設定。サーバーの証明書はroot storeに含まれる認証局で検証する
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    roots: RootCertStore,
+    config: Arc<ClientConfig>,
+}
+
+impl TlsConfig {
+    // webpki-rootsに含まれる、Mozillaが信頼している認証局を使う
+    pub fn new() -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        Self::with_roots(roots)
+    }
+
+    // 信頼する認証局を持たない設定。add_ca_pemで追加した認証局だけを信頼する
+    pub fn empty() -> Self {
+        Self::with_roots(RootCertStore::empty())
+    }
+
+    fn with_roots(roots: RootCertStore) -> Self {
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots.clone())
+            .with_no_client_auth();
+        Self {
+            roots,
+            config: Arc::new(config),
+        }
+    }
+
+    // PEM形式の証明書を信頼する認証局として追加し、追加した数を返す
+    // 自己署名の証明書を使うローカルのテスト用サーバーに接続するときに、そのテスト用の認証局を指定する
+    pub fn add_ca_pem(&mut self, pem: &[u8]) -> Result<usize, NetworkError> {
+        let mut added = 0;
+        for cert in rustls_pemfile::certs(&mut &pem[..]) {
+            let cert = cert.map_err(|e| NetworkError::Certificate(e.to_string()))?;
+            self.roots
+                .add(cert)
+                .map_err(|e| NetworkError::Certificate(e.to_string()))?;
+            added += 1;
+        }
+        if added == 0 {
+            return Err(NetworkError::Certificate(
+                "no certificate found in PEM".to_string(),
+            ));
+        }
+        *self = Self::with_roots(self.roots.clone());
+        Ok(added)
+    }
+
+    // 信頼している認証局の数
+    pub fn root_count(&self) -> usize {
+        self.roots.len()
+    }
+
+    // ホスト名を指定してハンドシェイクを行う。証明書はこのホスト名に対して発行されている必要がある
+    pub fn connect(&self, host: &str, mut stream: TcpStream) -> io::Result<TlsStream> {
+        // IPv6アドレスは [ ] を取り除く
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "invalid server name"))?;
+        let mut connection = ClientConnection::new(self.config.clone(), name)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        while connection.is_handshaking() {
+            connection.complete_io(&mut stream)?;
+        }
+        Ok(StreamOwned::new(connection, stream))
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// rustlsのエラーを、証明書の検証に失敗したかどうかで区別する
+pub fn tls_error(e: &rustls::Error) -> NetworkError {
+    match e {
+        rustls::Error::InvalidCertificate(reason) => {
+            NetworkError::Certificate(format!("{:?}", reason))
+        }
+        _ => NetworkError::Tls(e.to_string()),
+    }
+}
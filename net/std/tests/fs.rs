@@ -0,0 +1,50 @@
+use net_std::fs::StdFileSystem;
+use std::rc::Rc;
+use web_browser_core::browser::Browser;
+use web_browser_core::error::Error;
+use web_browser_core::loader::{FileSystem, LoadError};
+
+// リポジトリのルートにあるテスト用のページ
+fn test_html_url() -> String {
+    format!("file://{}/../../test.html", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn test_load_test_html() {
+    let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_file_system(Rc::new(StdFileSystem::new()));
+    let page = browser.borrow().current_page();
+    let dom = page
+        .borrow_mut()
+        .load(&test_html_url())
+        .expect("failed to load page");
+    assert!(dom.contains("kind: H1,"));
+    assert!(dom.contains("kind: P,"));
+    assert!(page
+        .borrow()
+        .url()
+        .expect("no url")
+        .to_string()
+        .ends_with("/test.html"));
+}
+
+#[test]
+fn test_missing_file() {
+    let path = format!("{}/missing.html", env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(
+        StdFileSystem::new().read(&path),
+        Err(LoadError::NotFound(path.clone()))
+    );
+
+    let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_file_system(Rc::new(StdFileSystem::new()));
+    let page = browser.borrow().current_page();
+    assert_eq!(
+        page.borrow_mut().load(&format!("file://{}", path)).err(),
+        Some(Error::Load(LoadError::NotFound(path)))
+    );
+}
@@ -0,0 +1,499 @@
+use net_std::clock::SystemClock;
+use net_std::dns::SystemResolver;
+use net_std::http::HttpClient;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+use web_browser_core::browser::Browser;
+use web_browser_core::dns::IpAddr;
+use web_browser_core::error::Error;
+use web_browser_core::http::auth::{Challenge, Credentials, CredentialsProvider};
+use web_browser_core::http::proxy::ProxyConfig;
+use web_browser_core::http::request::{HttpRequest, Method};
+use web_browser_core::http::transport::NetworkError;
+use web_browser_core::url::Url;
+
+// 1つの接続だけを受け付け、受け取ったリクエストを返すテスト用のサーバー
+fn serve_once(response: &'static [u8]) -> (SocketAddr, thread::JoinHandle<Vec<u8>>) {
+    serve_once_on(
+        TcpListener::bind("127.0.0.1:0").expect("failed to bind"),
+        response,
+    )
+}
+
+fn serve_once_on(
+    listener: TcpListener,
+    response: &'static [u8],
+) -> (SocketAddr, thread::JoinHandle<Vec<u8>>) {
+    let addr = listener.local_addr().expect("failed to get local address");
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("failed to accept");
+        let request = read_request(&mut stream);
+        stream.write_all(response).expect("failed to write");
+        request
+    });
+    (addr, handle)
+}
+
+// ヘッダーの終わりまでと、Content-Lengthの分のボディを読む
+fn read_request(stream: &mut impl Read) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut buf).expect("failed to read");
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text
+                .lines()
+                .find_map(|l| l.strip_prefix("Content-Length: "))
+                .map(|l| l.parse::<usize>().expect("failed to parse length"))
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                return request;
+            }
+        }
+        if n == 0 {
+            return request;
+        }
+    }
+}
+
+#[test]
+fn test_get() {
+    let (addr, server) = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    let response = HttpClient::new()
+        .get(
+            "127.0.0.1".to_string(),
+            addr.port(),
+            "index.html".to_string(),
+        )
+        .expect("failed to get");
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.body_text(), "hello");
+
+    let request = String::from_utf8(server.join().expect("server panicked")).expect("not utf-8");
+    assert!(request.starts_with("GET /index.html HTTP/1.1\r\n"));
+    assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", addr.port())));
+    assert!(!request.contains("Connection:"));
+}
+
+#[test]
+fn test_chunked_response() {
+    let (addr, server) = serve_once(
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n",
+    );
+    let url = Url::parse(&format!("http://127.0.0.1:{}/", addr.port())).expect("invalid url");
+    let response = HttpClient::new()
+        .send(HttpRequest::new(Method::Get, url))
+        .expect("failed to send");
+    assert_eq!(response.body_text(), "abcde");
+    server.join().expect("server panicked");
+}
+
+#[test]
+fn test_streaming() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let port = listener
+        .local_addr()
+        .expect("failed to get local address")
+        .port();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("failed to accept");
+        read_request(&mut stream);
+        // ボディを2回に分けて送り、その間に少し待つ
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\n<html>\r\n")
+            .expect("failed to write");
+        stream.flush().expect("failed to flush");
+        thread::sleep(Duration::from_millis(100));
+        stream
+            .write_all(b"7\r\n</html>\r\n0\r\n\r\n")
+            .expect("failed to write");
+    });
+
+    let url = Url::parse(&format!("http://127.0.0.1:{}/", port)).expect("invalid url");
+    let mut chunks = Vec::new();
+    let response = HttpClient::new()
+        .send_streaming(HttpRequest::new(Method::Get, url), &mut |head, chunk| {
+            assert_eq!(head.status_code(), 200);
+            chunks.push(String::from_utf8_lossy(chunk).to_string());
+        })
+        .expect("failed to send");
+    assert_eq!(chunks, vec!["<html>", "</html>"]);
+    assert_eq!(response.body_text(), "<html></html>");
+    server.join().expect("server panicked");
+}
+
+#[test]
+fn test_post() {
+    let (addr, server) = serve_once(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n");
+    let url = Url::parse(&format!("http://localhost:{}/form", addr.port())).expect("invalid url");
+    let request = HttpRequest::builder(Method::Post, url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(b"a=1&b=2".to_vec())
+        .build()
+        .expect("failed to build request");
+    let response = HttpClient::new().send(request).expect("failed to send");
+    assert_eq!(response.status_code(), 201);
+
+    let request = String::from_utf8(server.join().expect("server panicked")).expect("not utf-8");
+    assert!(request.starts_with("POST /form HTTP/1.1\r\n"));
+    assert!(request.ends_with("Content-Length: 7\r\n\r\na=1&b=2"));
+}
+
+#[test]
+fn test_read_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let port = listener
+        .local_addr()
+        .expect("failed to get local address")
+        .port();
+    let server = thread::spawn(move || {
+        // 接続を受け付けるだけで何も返さない
+        let (stream, _) = listener.accept().expect("failed to accept");
+        thread::sleep(Duration::from_millis(500));
+        drop(stream);
+    });
+
+    let mut client = HttpClient::new();
+    client.set_read_timeout(Duration::from_millis(100));
+    let result = client.get("127.0.0.1".to_string(), port, "/".to_string());
+    assert_eq!(result.err(), Some(Error::Network(NetworkError::Timeout)));
+    server.join().expect("server panicked");
+}
+
+#[test]
+fn test_connection_refused() {
+    // 一度使ったポートを閉じておく
+    let port = TcpListener::bind("127.0.0.1:0")
+        .expect("failed to bind")
+        .local_addr()
+        .expect("failed to get local address")
+        .port();
+    let result = HttpClient::new().get("127.0.0.1".to_string(), port, "/".to_string());
+    assert_eq!(
+        result.err(),
+        Some(Error::Network(NetworkError::ConnectionRefused(format!(
+            "127.0.0.1:{}",
+            port
+        ))))
+    );
+}
+
+#[test]
+fn test_load_page() {
+    let (addr, server) = serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 48\r\n\r\n<html><head></head><body><p>hi</p></body></html>",
+    );
+    let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_transport(Rc::new(HttpClient::new()));
+    let page = browser.borrow().current_page();
+    let dom = page
+        .borrow_mut()
+        .load(&format!("http://127.0.0.1:{}/", addr.port()))
+        .expect("failed to load page");
+    assert!(dom.contains("kind: P,"));
+    server.join().expect("server panicked");
+}
+
+#[test]
+fn test_cookies() {
+    let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_transport(Rc::new(HttpClient::new()));
+    browser.borrow_mut().set_clock(Rc::new(SystemClock::new()));
+    let fetcher = browser.borrow().fetcher().expect("no transport");
+
+    let (addr, server) = serve_once(
+        b"HTTP/1.1 200 OK\r\nSet-Cookie: sid=abc; Max-Age=3600; HttpOnly\r\nContent-Length: 0\r\n\r\n",
+    );
+    let url = Url::parse(&format!("http://127.0.0.1:{}/login", addr.port())).expect("invalid url");
+    fetcher
+        .fetch(HttpRequest::new(Method::Post, url))
+        .expect("failed to fetch");
+    server.join().expect("server panicked");
+
+    // クッキーはポート番号を区別しない
+    let (addr, server) = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+    let url = Url::parse(&format!("http://127.0.0.1:{}/home", addr.port())).expect("invalid url");
+    fetcher
+        .fetch(HttpRequest::new(Method::Get, url))
+        .expect("failed to fetch");
+    let request = String::from_utf8(server.join().expect("server panicked")).expect("not utf-8");
+    assert!(request.contains("Cookie: sid=abc\r\n"));
+}
+
+#[test]
+fn test_hosts_override() {
+    let (addr, server) = serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 48\r\n\r\n<html><head></head><body><p>hi</p></body></html>",
+    );
+    let browser = Browser::new();
+    let resolver = browser.borrow().resolver();
+    resolver.set_platform(Rc::new(SystemResolver::new()));
+    resolver.add_host("www.example.com", vec![IpAddr::V4([127, 0, 0, 1])]);
+    let mut client = HttpClient::new();
+    client.set_resolver(resolver);
+    browser.borrow_mut().set_transport(Rc::new(client));
+
+    let page = browser.borrow().current_page();
+    page.borrow_mut()
+        .load(&format!("http://www.example.com:{}/", addr.port()))
+        .expect("failed to load page");
+    let request = String::from_utf8(server.join().expect("server panicked")).expect("not utf-8");
+    assert!(request.contains(&format!("Host: www.example.com:{}\r\n", addr.port())));
+}
+
+#[test]
+fn test_ipv6_literal() {
+    // IPv6が使えない環境では確かめられない
+    let listener = match TcpListener::bind("[::1]:0") {
+        Ok(listener) => listener,
+        Err(_) => return,
+    };
+    let (addr, server) = serve_once_on(listener, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    // ブラウザのリゾルバを通しても、IPアドレスは名前解決せずに接続する
+    let browser = Browser::new();
+    let mut client = HttpClient::new();
+    client.set_resolver(browser.borrow().resolver());
+    let url = Url::parse(&format!("http://[::1]:{}/", addr.port())).expect("invalid url");
+    let response = client
+        .send(HttpRequest::new(Method::Get, url))
+        .expect("failed to send");
+    assert_eq!(response.body_text(), "ok");
+    let request = String::from_utf8(server.join().expect("server panicked")).expect("not utf-8");
+    assert!(request.contains(&format!("Host: [::1]:{}\r\n", addr.port())));
+}
+
+// 1つの接続で2つのリクエストに答え、接続を受け付けた回数と受け取ったリクエストを返すサーバー
+fn serve_keep_alive(
+    responses: &'static [&'static [u8]],
+) -> (SocketAddr, thread::JoinHandle<(usize, Vec<String>)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let addr = listener.local_addr().expect("failed to get local address");
+    let handle = thread::spawn(move || {
+        let mut accepted = 0;
+        let mut requests = Vec::new();
+        let mut responses = responses.iter();
+        while let Some(response) = responses.next() {
+            let (mut stream, _) = listener.accept().expect("failed to accept");
+            accepted += 1;
+            let mut response = *response;
+            loop {
+                let request = read_request(&mut stream);
+                requests.push(String::from_utf8(request).expect("not utf-8"));
+                stream.write_all(response).expect("failed to write");
+                // Connection: closeを返した場合は接続を閉じる
+                if String::from_utf8_lossy(response).contains("Connection: close") {
+                    break;
+                }
+                response = match responses.next() {
+                    Some(response) => response,
+                    None => break,
+                };
+            }
+        }
+        (accepted, requests)
+    });
+    (addr, handle)
+}
+
+#[test]
+fn test_keep_alive() {
+    let (addr, server) = serve_keep_alive(&[
+        b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nfirst",
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nsecond\r\n0\r\n\r\n",
+    ]);
+    let client = HttpClient::new();
+    let url = format!("http://127.0.0.1:{}/", addr.port());
+    for expected in ["first", "second"] {
+        let url = Url::parse(&url).expect("invalid url");
+        let response = client
+            .send(HttpRequest::new(Method::Get, url))
+            .expect("failed to send");
+        assert_eq!(response.body_text(), expected);
+        assert_eq!(client.idle_connections(), 1);
+    }
+
+    let (accepted, requests) = server.join().expect("server panicked");
+    assert_eq!(accepted, 1);
+    assert_eq!(requests.len(), 2);
+}
+
+#[test]
+fn test_connection_close_is_not_reused() {
+    let (addr, server) = serve_keep_alive(&[
+        b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+    ]);
+    let client = HttpClient::new();
+    for _ in 0..2 {
+        client
+            .get("127.0.0.1".to_string(), addr.port(), "/".to_string())
+            .expect("failed to get");
+    }
+    let (accepted, _) = server.join().expect("server panicked");
+    assert_eq!(accepted, 2);
+}
+
+// レスポンスを返した後、次のリクエストを読まずに接続を閉じるサーバー
+fn serve_then_close(count: usize) -> (SocketAddr, thread::JoinHandle<usize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let addr = listener.local_addr().expect("failed to get local address");
+    let handle = thread::spawn(move || {
+        for _ in 0..count {
+            let (mut stream, _) = listener.accept().expect("failed to accept");
+            read_request(&mut stream);
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .expect("failed to write");
+        }
+        count
+    });
+    (addr, handle)
+}
+
+#[test]
+fn test_retry_on_closed_connection() {
+    let (addr, server) = serve_then_close(2);
+    let client = HttpClient::new();
+    for _ in 0..2 {
+        let response = client
+            .get("127.0.0.1".to_string(), addr.port(), "/".to_string())
+            .expect("failed to get");
+        assert_eq!(response.body_text(), "ok");
+        // サーバーが接続を閉じるのを待つ
+        thread::sleep(Duration::from_millis(50));
+    }
+    server.join().expect("server panicked");
+}
+
+#[test]
+fn test_no_retry_for_post() {
+    let (addr, server) = serve_then_close(1);
+    let client = HttpClient::new();
+    let url = format!("http://127.0.0.1:{}/form", addr.port());
+    client
+        .send(HttpRequest::new(
+            Method::Get,
+            Url::parse(&url).expect("invalid url"),
+        ))
+        .expect("failed to send");
+    server.join().expect("server panicked");
+
+    // POSTは冪等ではないので、閉じられた接続で失敗しても送り直さない
+    let result = client.send(HttpRequest::new(
+        Method::Post,
+        Url::parse(&url).expect("invalid url"),
+    ));
+    assert_eq!(
+        result.err(),
+        Some(Error::Network(NetworkError::ConnectionReset))
+    );
+}
+
+#[test]
+fn test_proxy() {
+    // プロキシの代わりに、受け取ったリクエストをそのまま返すサーバーを使う
+    let (addr, proxy) = serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 48\r\n\r\n<html><head></head><body><p>hi</p></body></html>",
+    );
+    let mut config = ProxyConfig::new("127.0.0.1", addr.port());
+    config.set_credentials("user", "pass");
+    let browser = Browser::new();
+    browser.borrow_mut().set_proxy(Some(config));
+    browser
+        .borrow_mut()
+        .set_transport(Rc::new(HttpClient::new()));
+
+    // ホスト名はプロキシが解決するので、存在しないドメインでもよい
+    let page = browser.borrow().current_page();
+    let dom = page
+        .borrow_mut()
+        .load("http://www.example.invalid/index.html?q=1")
+        .expect("failed to load page");
+    assert!(dom.contains("kind: P,"));
+
+    let request = String::from_utf8(proxy.join().expect("server panicked")).expect("not utf-8");
+    assert!(request.starts_with("GET http://www.example.invalid/index.html?q=1 HTTP/1.1\r\n"));
+    assert!(request.contains("Host: www.example.invalid\r\n"));
+    assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+}
+
+#[test]
+fn test_direct_connection_is_not_reused_for_proxy() {
+    // 1つ目のレスポンスの後も接続を閉じず、次に受け取ったリクエストを返すサーバー
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let addr = listener.local_addr().expect("failed to get local address");
+    let origin = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("failed to accept");
+        read_request(&mut stream);
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\ndirect")
+            .expect("failed to write");
+        read_request(&mut stream)
+    });
+    let (proxy_addr, proxy) = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\nproxied");
+
+    let client = HttpClient::new();
+    let url = Url::parse(&format!("http://127.0.0.1:{}/", addr.port())).expect("invalid url");
+    let response = client
+        .send(HttpRequest::new(Method::Get, url.clone()))
+        .expect("failed to send");
+    assert_eq!(response.body_text(), "direct");
+    assert_eq!(client.idle_connections(), 1);
+
+    // プロキシを設定した後は、プールにある直接の接続を使わずにプロキシに接続する
+    let mut request = HttpRequest::new(Method::Get, url);
+    request.set_proxy(Some(ProxyConfig::new("127.0.0.1", proxy_addr.port())));
+    let response = client.send(request).expect("failed to send");
+    assert_eq!(response.body_text(), "proxied");
+    proxy.join().expect("server panicked");
+
+    drop(client);
+    assert!(origin.join().expect("server panicked").is_empty());
+}
+
+#[derive(Debug)]
+struct FixedCredentials;
+
+impl CredentialsProvider for FixedCredentials {
+    fn credentials(&self, _url: &Url, challenge: &Challenge) -> Option<Credentials> {
+        assert_eq!(challenge.realm(), "members");
+        Some(Credentials::new("user", "pass"))
+    }
+}
+
+#[test]
+fn test_basic_auth() {
+    let (addr, server) = serve_keep_alive(&[
+        b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"members\"\r\nContent-Length: 0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 48\r\n\r\n<html><head></head><body><p>hi</p></body></html>",
+    ]);
+    let browser = Browser::new();
+    browser
+        .borrow_mut()
+        .set_credentials_provider(Some(Rc::new(FixedCredentials)));
+    browser
+        .borrow_mut()
+        .set_transport(Rc::new(HttpClient::new()));
+
+    let page = browser.borrow().current_page();
+    let dom = page
+        .borrow_mut()
+        .load(&format!("http://127.0.0.1:{}/private/", addr.port()))
+        .expect("failed to load page");
+    assert!(dom.contains("kind: P,"));
+    assert_eq!(browser.borrow().auth_cache().borrow().len(), 1);
+
+    let (accepted, requests) = server.join().expect("server panicked");
+    assert_eq!(accepted, 1);
+    assert!(!requests[0].contains("Authorization"));
+    assert!(requests[1].contains("Authorization: Basic dXNlcjpwYXNz\r\n"));
+}
@@ -0,0 +1,209 @@
+use net_std::http::HttpClient;
+use net_std::tls::TlsConfig;
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use web_browser_core::error::Error;
+use web_browser_core::http::proxy::ProxyConfig;
+use web_browser_core::http::request::{HttpRequest, Method};
+use web_browser_core::http::transport::NetworkError;
+use web_browser_core::url::Url;
+
+// テスト用の認証局と、それが localhost と 127.0.0.1 に発行したサーバー証明書
+const CA: &[u8] = include_bytes!("certs/ca.pem");
+const SERVER_CERT: &[u8] = include_bytes!("certs/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("certs/server.key");
+
+fn server_config() -> Arc<ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut &SERVER_CERT[..])
+        .collect::<Result<Vec<_>, _>>()
+        .expect("failed to read certificate");
+    let key = rustls_pemfile::private_key(&mut &SERVER_KEY[..])
+        .expect("failed to read private key")
+        .expect("no private key");
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .expect("failed to build server config");
+    Arc::new(config)
+}
+
+// 1つの接続を受け付けてTLSで通信し、count個のリクエストに同じレスポンスを返すサーバー
+fn serve_tls(count: usize, response: &'static [u8]) -> (u16, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let port = listener
+        .local_addr()
+        .expect("failed to get local address")
+        .port();
+    let config = server_config();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("failed to accept");
+        serve_tls_stream(stream, config, count, response)
+    });
+    (port, handle)
+}
+
+// TCPの接続の上でTLSのサーバーとして振る舞い、受け取ったリクエストを返す
+fn serve_tls_stream(
+    stream: TcpStream,
+    config: Arc<ServerConfig>,
+    count: usize,
+    response: &[u8],
+) -> Vec<String> {
+    let connection = ServerConnection::new(config).expect("failed to create connection");
+    let mut stream = StreamOwned::new(connection, stream);
+    let mut requests = Vec::new();
+    for _ in 0..count {
+        // ハンドシェイクに失敗した場合は、受け取ったリクエストだけを返す
+        let request = match read_head(&mut stream) {
+            Some(request) => request,
+            None => break,
+        };
+        requests.push(request);
+        stream.write_all(response).expect("failed to write");
+        stream.flush().expect("failed to flush");
+    }
+    requests
+}
+
+// CONNECTを受け取ってconnect_responseを返すプロキシ
+// トンネルを作った場合は、そのままTLSのサーバーとして1つのリクエストに答える
+fn serve_proxy(connect_response: &'static [u8]) -> (u16, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let port = listener
+        .local_addr()
+        .expect("failed to get local address")
+        .port();
+    let config = server_config();
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("failed to accept");
+        let connect = read_head(&mut stream).expect("no CONNECT request");
+        stream.write_all(connect_response).expect("failed to write");
+        let mut requests = vec![connect];
+        if connect_response.starts_with(b"HTTP/1.1 200") {
+            requests.extend(serve_tls_stream(
+                stream,
+                config,
+                1,
+                b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\ntunneled",
+            ));
+        }
+        requests
+    });
+    (port, handle)
+}
+
+// ヘッダーの終わりまで読む
+fn read_head(stream: &mut impl Read) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+    Some(String::from_utf8(request).expect("not utf-8"))
+}
+
+fn client() -> HttpClient {
+    let mut tls = TlsConfig::empty();
+    assert_eq!(tls.add_ca_pem(CA), Ok(1));
+    let mut client = HttpClient::new();
+    client.set_tls_config(tls);
+    client
+}
+
+#[test]
+fn test_https_get() {
+    let (port, server) = serve_tls(1, b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    let url = Url::parse(&format!("https://localhost:{}/index.html", port)).expect("invalid url");
+    let response = client()
+        .send(HttpRequest::new(Method::Get, url))
+        .expect("failed to send");
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.body_text(), "hello");
+
+    let requests = server.join().expect("server panicked");
+    assert!(requests[0].starts_with("GET /index.html HTTP/1.1\r\n"));
+    assert!(requests[0].contains(&format!("Host: localhost:{}\r\n", port)));
+}
+
+#[test]
+fn test_https_keep_alive() {
+    let (port, server) = serve_tls(2, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    let client = client();
+    for _ in 0..2 {
+        let url = Url::parse(&format!("https://127.0.0.1:{}/", port)).expect("invalid url");
+        let response = client
+            .send(HttpRequest::new(Method::Get, url))
+            .expect("failed to send");
+        assert_eq!(response.body_text(), "ok");
+    }
+    assert_eq!(server.join().expect("server panicked").len(), 2);
+}
+
+#[test]
+fn test_untrusted_certificate() {
+    // テスト用の認証局を信頼しない場合は、証明書の検証に失敗する
+    let (port, server) = serve_tls(1, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+    let url = Url::parse(&format!("https://localhost:{}/", port)).expect("invalid url");
+    let result = HttpClient::new().send(HttpRequest::new(Method::Get, url));
+    assert!(matches!(
+        result,
+        Err(Error::Network(NetworkError::Certificate(_)))
+    ));
+    assert!(server.join().expect("server panicked").is_empty());
+}
+
+#[test]
+fn test_add_ca_pem() {
+    let mut tls = TlsConfig::empty();
+    assert_eq!(tls.root_count(), 0);
+    assert!(matches!(
+        tls.add_ca_pem(b"not a certificate"),
+        Err(NetworkError::Certificate(_))
+    ));
+    assert_eq!(tls.add_ca_pem(CA), Ok(1));
+    assert_eq!(tls.root_count(), 1);
+    assert!(TlsConfig::new().root_count() > 100);
+}
+
+#[test]
+fn test_connect_tunnel() {
+    let (port, proxy) = serve_proxy(b"HTTP/1.1 200 Connection established\r\n\r\n");
+    let mut config = ProxyConfig::new("127.0.0.1", port);
+    config.set_credentials("user", "pass");
+    let url = Url::parse("https://localhost/secure").expect("invalid url");
+    let mut request = HttpRequest::new(Method::Get, url);
+    request.set_proxy(Some(config));
+    let response = client().send(request).expect("failed to send");
+    assert_eq!(response.body_text(), "tunneled");
+
+    let requests = proxy.join().expect("server panicked");
+    assert_eq!(
+        requests[0],
+        "CONNECT localhost:443 HTTP/1.1\r\nHost: localhost:443\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"
+    );
+    // トンネルの中ではorigin-formで送り、プロキシの認証情報は送らない
+    assert!(requests[1].starts_with("GET /secure HTTP/1.1\r\n"));
+    assert!(!requests[1].contains("Proxy-Authorization"));
+}
+
+#[test]
+fn test_connect_rejected() {
+    let (port, proxy) =
+        serve_proxy(b"HTTP/1.1 407 Proxy Authentication Required\r\nContent-Length: 0\r\n\r\n");
+    let url = Url::parse("https://localhost/").expect("invalid url");
+    let mut request = HttpRequest::new(Method::Get, url);
+    request.set_proxy(Some(ProxyConfig::new("127.0.0.1", port)));
+    assert_eq!(
+        client().send(request).err(),
+        Some(Error::Network(NetworkError::Proxy(
+            "407 Proxy Authentication Required".to_string()
+        )))
+    );
+    proxy.join().expect("server panicked");
+}
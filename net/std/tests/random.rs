@@ -0,0 +1,16 @@
+use net_std::random::SystemRandom;
+use web_browser_core::random::RandomSource;
+
+#[test]
+fn test_system_random() {
+    let random = SystemRandom::new();
+    assert!(random.is_available());
+
+    // 32バイトの乱数が2回続けて同じになったり、すべて0になったりすることはまずない
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+    random.fill(&mut first);
+    random.fill(&mut second);
+    assert_ne!(first, second);
+    assert_ne!([0u8; 32], first);
+}
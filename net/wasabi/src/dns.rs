@@ -0,0 +1,26 @@
+extern crate alloc;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use noli::net::lookup_host;
+use web_browser_core::dns::{IpAddr, Resolver};
+use web_browser_core::http::transport::NetworkError;
+
+// noliのlookup_hostを使ってOSのリゾルバで名前解決する
+#[derive(Debug, Clone, Default)]
+pub struct NoliResolver;
+
+impl NoliResolver {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Resolver for NoliResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetworkError> {
+        match lookup_host(host) {
+            Ok(ips) => Ok(ips.iter().map(|ip| IpAddr::V4(ip.bytes())).collect()),
+            Err(_) => Err(NetworkError::Dns(host.to_string())),
+        }
+    }
+}
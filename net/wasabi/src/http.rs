@@ -1,89 +1,195 @@
 extern crate alloc;
 
+use crate::dns::NoliResolver;
 use alloc::format;
+use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
-use alloc::vec::Vec;
-use noli::net::{SocketAddr, TcpStream, lookup_host};
+use alloc::vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use noli::net::{IpV4Addr, SocketAddr, TcpStream};
+use web_browser_core::dns::{IpAddr, Resolver};
 use web_browser_core::error::Error;
-use web_browser_core::http::HttpResponse;
+use web_browser_core::http::connection::{exchange, Connection, ExchangeError};
+use web_browser_core::http::pool::ConnectionPool;
+use web_browser_core::http::request::{HttpRequest, Method};
+use web_browser_core::http::transport::{HttpTransport, NetworkError};
+use web_browser_core::http::{HttpError, HttpResponse};
+use web_browser_core::url::Url;
 
-pub struct HttpClient {}
+// noliのTcpStreamに、web_browser_coreのConnectionを実装するためのラッパー
+struct Stream(TcpStream);
+
+impl Connection for Stream {
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), NetworkError> {
+        let mut written = 0;
+        while written < bytes.len() {
+            match self.0.write(&bytes[written..]) {
+                Ok(0) | Err(_) => {
+                    return Err(NetworkError::Send(
+                        "Failed to send a request to TCP".to_string(),
+                    ))
+                }
+                Ok(bytes) => written += bytes,
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, NetworkError> {
+        self.0.read(buf).map_err(|_| {
+            NetworkError::Receive("Failed to receive a request from TCP stream".to_string())
+        })
+    }
+}
+
+pub struct HttpClient {
+    resolver: Rc<dyn Resolver>,
+    pool: RefCell<ConnectionPool<Stream>>,
+}
 
 impl HttpClient {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            resolver: Rc::new(NoliResolver::new()),
+            pool: RefCell::new(ConnectionPool::new()),
+        }
+    }
+
+    // アイドル状態で保持しておく接続の数の上限。0の場合は接続を再利用しない
+    pub fn set_max_idle_connections(&self, max_idle: usize) {
+        self.pool.borrow_mut().set_max_idle(max_idle);
+    }
+
+    // 名前解決に使うリゾルバ。デフォルトではnoliのリゾルバを使う
+    // Browser::resolver()を渡すと、ブラウザに設定したホスト名の表とキャッシュが使われる
+    pub fn set_resolver(&mut self, resolver: Rc<dyn Resolver>) {
+        self.resolver = resolver;
     }
 
     pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
-        // URLからホストを探す
-        let ips = match lookup_host(&"example.com") {
-            Ok(ips) => ips,
-            Err(_) => return Err(Error::Network("Failed to find IP addresses".to_string())),
+        // パスは / から始まっていてもいなくてもよい
+        let url = Url::parse(&format!("http://{}:{}/", host, port))
+            .and_then(|base| base.join(&path))?;
+        let request = HttpRequest::builder(Method::Get, url)
+            .header("Accept", "text/html")
+            .build()?;
+        self.send(request)
+    }
+
+    // リクエストのURLのホストに接続し、リクエストを送ってレスポンスを受け取る
+    pub fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.send_streaming(request, &mut |_, _| {})
+    }
+
+    // ボディを受信するたびにon_bodyを呼び出す
+    pub fn send_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let port = match request.url().port_or_default() {
+            Some(port) => port,
+            None => return Err(Error::UnexpectedInput("Failed to find a port".to_string())),
         };
 
-        if ips.len() < 1 {
-            return Err(Error::Network("Failed to find IP addresses".to_string()));
+        // noliにはTLSの実装がないので、httpsのURLには平文で接続せずにエラーにする
+        if request.url().scheme() != "http" {
+            return Err(HttpError::UnsupportedScheme(request.url().scheme()).into());
+        }
+
+        // 同じオリジンへのアイドル状態の接続があれば再利用する
+        // サーバーがすでに閉じていた場合、冪等なリクエストは新しい接続で1回だけ送り直す
+        // https://www.rfc-editor.org/rfc/rfc9112#section-9.3.1
+        // プロキシを通すかどうかが変わった接続は再利用しない
+        let proxy = request.proxy();
+        let pooled = self.pool.borrow_mut().take(&request.url(), proxy.as_ref());
+        if let Some(stream) = pooled {
+            match self.exchange(stream, &request, on_body) {
+                Err(ExchangeError::Closed(_)) if request.method().is_idempotent() => {}
+                result => return result.map_err(Error::from),
+            }
         }
 
+        // プロキシを通す場合は、URLのホストの代わりにプロキシに接続する
+        let (host, port) = match proxy {
+            Some(proxy) => (proxy.host(), proxy.port()),
+            None => (request.url().hostname(), port),
+        };
+
+        // ホストをIPアドレスに解決する。IPアドレスの場合はリゾルバを使わない。noliはIPv4にのみ対応している
+        let addresses = match IpAddr::parse(&host) {
+            Some(address) => vec![address],
+            None => self.resolver.resolve(&host)?,
+        };
+        let ip = match addresses.into_iter().find_map(|ip| match ip {
+            IpAddr::V4(octets) => Some(IpV4Addr::new(octets)),
+            IpAddr::V6(_) => None,
+        }) {
+            Some(ip) => ip,
+            None => return Err(NetworkError::Dns(host).into()),
+        };
+
         // intoメソッドでtupleからSocketAddrに変換
-        let socket_addr: SocketAddr = (ips[0], port).into();
+        let socket_addr: SocketAddr = (ip, port).into();
 
         // ホスト名、ポート番号をもとに接続（ストリーム）を作成
-        let mut stream = match TcpStream::connect(socket_addr) {
+        let stream = match TcpStream::connect(socket_addr) {
             Ok(stream) => stream,
             Err(_) => {
-                return Err(Error::Network(
+                return Err(NetworkError::Connect(
                     "Failed to connect to TCP stream".to_string(),
-                ))
+                ).into())
             }
         };
 
-        // ホストに送るリクエストを構築
-        // リクエストラインを追加
-        let mut request = String::from("GET /");
-        request.push_str(&path);
-        request.push_str(" HTTP/1.1\n");
-
-        // ヘッダを追加
-        request.push_str("Host: ");
-        request.push_str(&host);
-        request.push('\n');
-        request.push_str("Accept: text/html\n");
-        request.push_str("Connection: close\n");
-        request.push('\n');
-
-        // リクエストの送信
-        let _bytes_written = match stream.write(request.as_bytes()) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return Err(Error::Network(
-                    "Failed to send a request to TCP".to_string(),
-                ))
-            }
-        };
+        Ok(self.exchange(Stream(stream), &request, on_body)?)
+    }
 
-        // レスポンスの受信
-        let mut received = Vec::new();
-        loop {
-            let mut buf = [0u8; 4096];
-            let bytes_read = match stream.read(&mut buf) {
-                Ok(bytes) => bytes,
-                Err(_) => {
-                    return Err(Error::Network(
-                        "Failed to receive a request from TCP stream".to_string(),
-                    ));
-                }
-            };
-            if bytes_read == 0 {
-                break;
-            }
-            received.extend_from_slice(&buf[..bytes_read]);
+    // 接続にリクエストを送ってレスポンスを受け取る。接続を維持できる場合はプールに戻す
+    fn exchange(
+        &self,
+        stream: Stream,
+        request: &HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, ExchangeError> {
+        let (response, stream) = exchange(stream, request, on_body)?;
+        if let Some(stream) = stream {
+            self.pool
+                .borrow_mut()
+                .put(&request.url(), request.proxy().as_ref(), stream);
         }
+        Ok(response)
+    }
+}
 
-        match core::str::from_utf8(&received) {
-            Ok(response) => HttpResponse::new(response.to_string()),
-            Err(e) => Err(Error::Network(format!("Invalid received response: {}", e))),
-        }
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// noliのTcpStreamはDebugを実装していないので、保持している接続の数だけを表示する
+impl Debug for HttpClient {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("resolver", &self.resolver)
+            .field("idle_connections", &self.pool.borrow().len())
+            .finish()
+    }
+}
+
+impl HttpTransport for HttpClient {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        HttpClient::send(self, request)
+    }
+
+    fn send_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        HttpClient::send_streaming(self, request, on_body)
     }
 }
@@ -1,3 +1,4 @@
 #![no_std]
 
+pub mod dns;
 pub mod http;
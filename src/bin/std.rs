@@ -0,0 +1,44 @@
+use net_std::clock::SystemClock;
+use net_std::dns::SystemResolver;
+use net_std::fs::StdFileSystem;
+use net_std::http::HttpClient;
+use net_std::random::SystemRandom;
+use std::env;
+use std::process::ExitCode;
+use std::rc::Rc;
+use web_browser_core::browser::Browser;
+
+// ホストのOSの上で動かすブラウザ。引数のURLを読み込み、DOMツリーを表示する
+// 引数がない場合はリポジトリのテスト用のページを読み込む
+fn main() -> ExitCode {
+    let url = env::args()
+        .nth(1)
+        .unwrap_or_else(|| format!("file://{}/test.html", env!("CARGO_MANIFEST_DIR")));
+
+    let browser = Browser::new();
+    browser.borrow_mut().set_clock(Rc::new(SystemClock::new()));
+    browser.borrow_mut().set_random(Rc::new(SystemRandom::new()));
+    // ブラウザのリゾルバを通して、ホスト名の表とキャッシュを使う
+    let resolver = browser.borrow().resolver();
+    resolver.set_platform(Rc::new(SystemResolver::new()));
+    let mut client = HttpClient::new();
+    client.set_resolver(resolver);
+    browser.borrow_mut().set_transport(Rc::new(client));
+    browser
+        .borrow_mut()
+        .set_file_system(Rc::new(StdFileSystem::new()));
+    let page = browser.borrow().current_page();
+    let dom_string = match page.borrow_mut().load(&url) {
+        Ok(dom_string) => dom_string,
+        Err(e) => {
+            eprintln!("failed to load {}: {}", url, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for log in dom_string.lines() {
+        println!("{}", log);
+    }
+
+    ExitCode::SUCCESS
+}
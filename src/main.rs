@@ -3,34 +3,31 @@
 
 extern crate alloc;
 
-use crate::alloc::string::ToString;
+use alloc::rc::Rc;
+use net_wasabi::dns::NoliResolver;
+use net_wasabi::http::HttpClient;
 use noli::*;
 use web_browser_core::browser::Browser;
-use web_browser_core::http::HttpResponse;
+use web_browser_core::loader::MemoryFileSystem;
 
-static TEST_HTTP_RESPONSE: &str = r#"HTTP/1.1 200 OK
-Data: xx xx xx
-
-
-<html>
-<head></head>
-<body>
-  <h1 id="title">H1 title</h1>
-  <h2 class="class">H2 title</h2>
-  <p>Test text.</p>
-  <p>
-    <a href="example.com">Link1</a>
-    <a href="example.com">Link2</a>
-  </p>
-</body>
-</html>
-"#;
+// OSのファイルシステムがないので、リポジトリのテスト用のページを埋め込んで file:///test.html として読み込む
+static TEST_HTML: &[u8] = include_bytes!("../test.html");
 
 fn main() -> u64 {
+    // 時計は設定しないので、キャッシュしたレスポンスは使う前に毎回検証する
+    // noliには乱数もないので、Digest認証のcnonceはサーバーが予測できる値になる
     let browser = Browser::new();
-    let response = HttpResponse::new(TEST_HTTP_RESPONSE.to_string()).expect("failed to parse http response");
+    // ブラウザのリゾルバを通して、ホスト名の表とキャッシュを使う
+    let resolver = browser.borrow().resolver();
+    resolver.set_platform(Rc::new(NoliResolver::new()));
+    let mut client = HttpClient::new();
+    client.set_resolver(resolver);
+    browser.borrow_mut().set_transport(Rc::new(client));
+    let mut file_system = MemoryFileSystem::new();
+    file_system.add_file("/test.html", TEST_HTML);
+    browser.borrow_mut().set_file_system(Rc::new(file_system));
     let page = browser.borrow().current_page();
-    let dom_string = page.borrow_mut().receive_response(response);
+    let dom_string = page.borrow_mut().load("file:///test.html").expect("failed to load test.html");
 
     for log in dom_string.lines() {
         println!("{}", log);
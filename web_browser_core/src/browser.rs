@@ -1,25 +1,64 @@
-use crate::renderer::page::Page;
-use alloc::rc::Rc;
+use crate::clock::{Clock, NoClock};
+use crate::dns::HostResolver;
+use crate::download::Download;
+use crate::error::Error;
+use crate::http::auth::{AuthCache, CredentialsProvider};
+use crate::http::cache::HttpCache;
+use crate::http::cookie::CookieJar;
+use crate::http::fetch::{Fetcher, DEFAULT_MAX_REDIRECTS};
+use crate::http::proxy::ProxyConfig;
+use crate::http::transport::HttpTransport;
+use crate::loader::{FileSystem, Loader};
+use crate::random::RandomSource;
+use crate::renderer::page::{LoadState, Page};
+use crate::url::Url;
+use alloc::rc::{Rc, Weak};
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::fmt::Display;
 
 #[derive(Debug, Clone)]
 pub struct Browser {
     active_page_index: usize,
     pages: Vec<Rc<RefCell<Page>>>,
+    transport: Option<Rc<dyn HttpTransport>>, // ページを読み込むときに使うネットワークの実装
+    max_redirects: usize,
+    clock: Rc<dyn Clock>,
+    cookie_jar: Rc<RefCell<CookieJar>>,
+    cache: Rc<RefCell<HttpCache>>,
+    resolver: Rc<HostResolver>, // トランスポートに渡して名前解決に使う
+    proxy: Option<ProxyConfig>,
+    auth_cache: Rc<RefCell<AuthCache>>,
+    credentials_provider: Option<Rc<dyn CredentialsProvider>>, // 401を受け取ったときに認証情報を尋ねる相手
+    file_system: Option<Rc<dyn FileSystem>>,                   // file: URLを読み込むときに使う
+    downloads: Vec<Download>,
 }
 
 impl Browser {
     pub fn new() -> Rc<RefCell<Self>> {
-        let mut page = Page::new();
+        // 時刻を取得できない環境もあるので、デフォルトでは時計を使わない
+        // 時刻を取得できる環境ではset_clockで設定する
+        let clock: Rc<dyn Clock> = Rc::new(NoClock::new());
 
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            transport: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            clock: clock.clone(),
+            cookie_jar: Rc::new(RefCell::new(CookieJar::new(clock.clone()))),
+            cache: Rc::new(RefCell::new(HttpCache::new(clock.clone()))),
+            resolver: Rc::new(HostResolver::new(clock.clone())),
+            proxy: None,
+            auth_cache: Rc::new(RefCell::new(AuthCache::new(clock))),
+            credentials_provider: None,
+            file_system: None,
+            downloads: Vec::new(),
         }));
 
-        page.set_browser(Rc::downgrade(&browser));
-        browser.borrow_mut().pages.push(Rc::new(RefCell::new(page)));
+        let page = Self::new_page(&browser);
+        browser.borrow_mut().pages.push(page);
 
         browser
     }
@@ -27,4 +66,409 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         self.pages[self.active_page_index].clone()
     }
+
+    // 新しいタブを末尾に開いてアクティブにし、URLを読み込む。開いたタブの位置を返す
+    // ページの読み込み中にブラウザを参照するので、ブラウザを借用していない状態で呼び出す
+    // 読み込みに失敗してもタブは閉じない。失敗したことはtabs()の読み込みの状態でわかる
+    pub fn open_tab(browser: &Rc<RefCell<Self>>, url: &str) -> usize {
+        let page = Self::new_page(browser);
+        let index = {
+            let mut b = browser.borrow_mut();
+            b.pages.push(page.clone());
+            b.active_page_index = b.pages.len() - 1;
+            b.active_page_index
+        };
+        // 結果はページの読み込みの状態に残る
+        let _ = page.borrow_mut().load(url);
+        index
+    }
+
+    // indexのタブを、そのすぐ右の新しいタブに複製してアクティブにする。開いたタブの位置を返す
+    // 履歴の項目と今の位置は引き継ぐが、ドキュメントは共有せずに今の項目のURLを読み込み直す
+    // 何も読み込んでいないタブの場合は空のタブになる
+    pub fn duplicate_tab(browser: &Rc<RefCell<Self>>, index: usize) -> Result<usize, TabError> {
+        let page = Self::new_page(browser);
+        let history = {
+            let mut b = browser.borrow_mut();
+            let history = b.page(index)?.borrow().history();
+            b.pages.insert(index + 1, page.clone());
+            b.active_page_index = index + 1;
+            history
+        };
+        // 結果はページの読み込みの状態に残る
+        let _ = page.borrow_mut().restore_history(history);
+        Ok(index + 1)
+    }
+
+    // indexのタブを閉じる。アクティブなタブを閉じた場合は右隣、右端の場合は左隣のタブをアクティブにする
+    // ブラウザには常に1つ以上のタブがあるので、最後のタブは閉じられない
+    pub fn close_tab(&mut self, index: usize) -> Result<(), TabError> {
+        self.page(index)?;
+        if self.pages.len() == 1 {
+            return Err(TabError::LastTab);
+        }
+
+        let page = self.pages.remove(index);
+        // 閉じたページを持ち続けている場合に、このブラウザの設定で読み込まないようにする
+        page.borrow_mut().set_browser(Weak::new());
+        if index < self.active_page_index || self.active_page_index == self.pages.len() {
+            self.active_page_index -= 1;
+        }
+        Ok(())
+    }
+
+    pub fn switch_to(&mut self, index: usize) -> Result<(), TabError> {
+        self.page(index)?;
+        self.active_page_index = index;
+        Ok(())
+    }
+
+    // 開いているタブを左から順に返す
+    pub fn tabs(&self) -> Vec<Tab> {
+        self.pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                let page = page.borrow();
+                Tab {
+                    title: page.title(),
+                    url: page.url(),
+                    load_state: page.load_state(),
+                    active: i == self.active_page_index,
+                }
+            })
+            .collect()
+    }
+
+    pub fn active_tab_index(&self) -> usize {
+        self.active_page_index
+    }
+
+    pub fn set_transport(&mut self, transport: Rc<dyn HttpTransport>) {
+        self.transport = Some(transport);
+    }
+
+    pub fn transport(&self) -> Option<Rc<dyn HttpTransport>> {
+        self.transport.clone()
+    }
+
+    // リダイレクトをたどる回数の上限
+    pub fn set_max_redirects(&mut self, max_redirects: usize) {
+        self.max_redirects = max_redirects;
+    }
+
+    // クッキーやキャッシュ、名前解決のキャッシュの有効期限の判定と、Digest認証のcnonceの生成に使う時計
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock.clone();
+        self.cookie_jar.borrow_mut().set_clock(clock.clone());
+        self.cache.borrow_mut().set_clock(clock.clone());
+        self.resolver.set_clock(clock.clone());
+        self.auth_cache.borrow_mut().set_clock(clock);
+    }
+
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    // Digest認証のcnonceに混ぜる乱数。設定しない場合、cnonceはサーバーが予測できる値になる
+    pub fn set_random(&mut self, random: Rc<dyn RandomSource>) {
+        self.auth_cache.borrow_mut().set_random(random);
+    }
+
+    pub fn cookie_jar(&self) -> Rc<RefCell<CookieJar>> {
+        self.cookie_jar.clone()
+    }
+
+    pub fn cache(&self) -> Rc<RefCell<HttpCache>> {
+        self.cache.clone()
+    }
+
+    // 固定のアドレスを割り当てるホスト名の表とOSのリゾルバを設定し、トランスポートに渡す
+    pub fn resolver(&self) -> Rc<HostResolver> {
+        self.resolver.clone()
+    }
+
+    // ページの読み込みに使うHTTPプロキシ。Noneの場合は直接接続する
+    pub fn set_proxy(&mut self, proxy: Option<ProxyConfig>) {
+        self.proxy = proxy;
+    }
+
+    pub fn proxy(&self) -> Option<ProxyConfig> {
+        self.proxy.clone()
+    }
+
+    // 認証を求められたときにユーザー名とパスワードを用意する。Noneの場合は401をそのままページに渡す
+    pub fn set_credentials_provider(&mut self, provider: Option<Rc<dyn CredentialsProvider>>) {
+        self.credentials_provider = provider;
+    }
+
+    // 保護空間ごとに保存した認証情報
+    pub fn auth_cache(&self) -> Rc<RefCell<AuthCache>> {
+        self.auth_cache.clone()
+    }
+
+    // ブラウザの設定をもとにFetcherを作る。トランスポートが設定されていない場合はNoneを返す
+    pub fn fetcher(&self) -> Option<Fetcher> {
+        let mut fetcher = Fetcher::new(self.transport.clone()?);
+        fetcher.set_max_redirects(self.max_redirects);
+        fetcher.set_cookie_jar(self.cookie_jar.clone());
+        fetcher.set_cache(self.cache.clone());
+        fetcher.set_proxy(self.proxy.clone());
+        fetcher.set_auth_cache(self.auth_cache.clone());
+        if let Some(provider) = &self.credentials_provider {
+            fetcher.set_credentials_provider(provider.clone());
+        }
+        Some(fetcher)
+    }
+
+    pub fn set_file_system(&mut self, file_system: Rc<dyn FileSystem>) {
+        self.file_system = Some(file_system);
+    }
+
+    // 表示せずに保存したレスポンスを、受け取った順番に返す
+    pub fn downloads(&self) -> Vec<Download> {
+        self.downloads.clone()
+    }
+
+    pub fn add_download(&mut self, download: Download) {
+        self.downloads.push(download);
+    }
+
+    // このブラウザを参照するページを作る
+    fn new_page(browser: &Rc<RefCell<Self>>) -> Rc<RefCell<Page>> {
+        let mut page = Page::new();
+        page.set_browser(Rc::downgrade(browser));
+        Rc::new(RefCell::new(page))
+    }
+
+    fn page(&self, index: usize) -> Result<Rc<RefCell<Page>>, TabError> {
+        self.pages
+            .get(index)
+            .cloned()
+            .ok_or(TabError::OutOfRange(index))
+    }
+
+    // ブラウザの設定をもとに、URLのスキームに応じてドキュメントを読み込むLoaderを作る
+    pub fn loader(&self) -> Loader {
+        let mut loader = Loader::new();
+        if let Some(fetcher) = self.fetcher() {
+            loader.set_fetcher(fetcher);
+        }
+        if let Some(file_system) = &self.file_system {
+            loader.set_file_system(file_system.clone());
+        }
+        loader
+    }
+}
+
+// タブの一覧に表示する情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tab {
+    title: String, // <title>のテキスト。<title>がない場合は空文字列
+    url: Option<Url>,
+    load_state: LoadState,
+    active: bool,
+}
+
+impl Tab {
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn url(&self) -> Option<Url> {
+        self.url.clone()
+    }
+
+    pub fn load_state(&self) -> LoadState {
+        self.load_state.clone()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+// タブを操作するときのエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabError {
+    OutOfRange(usize), // その位置にタブがない
+    LastTab,           // 最後のタブは閉じられない
+}
+
+impl Display for TabError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TabError::OutOfRange(index) => write!(f, "no tab at index {}", index),
+            TabError::LastTab => write!(f, "cannot close the last tab"),
+        }
+    }
+}
+
+impl From<TabError> for Error {
+    fn from(e: TabError) -> Self {
+        Error::Tab(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::transport::{MockTransport, NetworkError};
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn browser() -> Rc<RefCell<Browser>> {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/a",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 43\r\n\r\n<html><head><title> Page\n A </title></head>",
+        );
+        transport.add_response(
+            "http://example.com/b",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\r\n<html></html>",
+        );
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(Rc::new(transport));
+        browser
+    }
+
+    fn titles(browser: &Rc<RefCell<Browser>>) -> Vec<String> {
+        browser
+            .borrow()
+            .tabs()
+            .iter()
+            .map(|tab| tab.title())
+            .collect()
+    }
+
+    #[test]
+    fn test_open_and_switch_tabs() {
+        let browser = browser();
+        assert_eq!(1, Browser::open_tab(&browser, "http://example.com/a"));
+        assert_eq!(2, Browser::open_tab(&browser, "http://example.com/missing"));
+
+        let tabs = browser.borrow().tabs();
+        assert_eq!(3, tabs.len());
+        assert_eq!(LoadState::Initial, tabs[0].load_state());
+        assert_eq!("Page A", tabs[1].title());
+        assert_eq!(LoadState::Complete, tabs[1].load_state());
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            tabs[1].url().map(|url| url.to_string())
+        );
+        // 読み込みに失敗してもタブは残る
+        assert!(matches!(tabs[2].load_state(), LoadState::Failed(_)));
+        assert!(tabs[2].is_active());
+
+        browser
+            .borrow_mut()
+            .switch_to(1)
+            .expect("failed to switch tab");
+        assert_eq!(1, browser.borrow().active_tab_index());
+        assert_eq!("Page A", browser.borrow().current_page().borrow().title());
+        assert_eq!(
+            Err(TabError::OutOfRange(3)),
+            browser.borrow_mut().switch_to(3)
+        );
+    }
+
+    #[test]
+    fn test_close_tab() {
+        let browser = browser();
+        Browser::open_tab(&browser, "http://example.com/a");
+        Browser::open_tab(&browser, "http://example.com/b");
+        browser
+            .borrow_mut()
+            .switch_to(1)
+            .expect("failed to switch tab");
+
+        // アクティブなタブを閉じると右隣のタブがアクティブになる
+        let closed = browser.borrow().current_page();
+        browser
+            .borrow_mut()
+            .close_tab(1)
+            .expect("failed to close tab");
+        assert_eq!(1, browser.borrow().active_tab_index());
+        assert_eq!(
+            Some("http://example.com/b".to_string()),
+            browser.borrow().tabs()[1].url().map(|url| url.to_string())
+        );
+        // 閉じたページはブラウザのトランスポートを使えない
+        assert_eq!(
+            Err(Error::Network(NetworkError::NoTransport)),
+            closed.borrow_mut().load("http://example.com/b")
+        );
+
+        // 右端のタブを閉じると左隣のタブがアクティブになる
+        browser
+            .borrow_mut()
+            .close_tab(1)
+            .expect("failed to close tab");
+        assert_eq!(0, browser.borrow().active_tab_index());
+        assert_eq!(Err(TabError::LastTab), browser.borrow_mut().close_tab(0));
+        assert_eq!(
+            Err(TabError::OutOfRange(1)),
+            browser.borrow_mut().close_tab(1)
+        );
+
+        // アクティブなタブより左のタブを閉じても、アクティブなタブは変わらない
+        Browser::open_tab(&browser, "http://example.com/a");
+        browser
+            .borrow_mut()
+            .close_tab(0)
+            .expect("failed to close tab");
+        assert_eq!(0, browser.borrow().active_tab_index());
+        assert_eq!(vec!["Page A".to_string()], titles(&browser));
+    }
+
+    #[test]
+    fn test_duplicate_tab() {
+        let browser = browser();
+        Browser::open_tab(&browser, "http://example.com/a");
+        Browser::open_tab(&browser, "http://example.com/b");
+        let source = browser.borrow().page(1).expect("no tab");
+        source.borrow_mut().load("b").expect("failed to load page");
+        source.borrow_mut().back().expect("failed to go back");
+
+        assert_eq!(Ok(2), Browser::duplicate_tab(&browser, 1));
+        assert_eq!(2, browser.borrow().active_tab_index());
+        assert_eq!(
+            vec![
+                String::new(),
+                "Page A".to_string(),
+                "Page A".to_string(),
+                String::new()
+            ],
+            titles(&browser)
+        );
+
+        // 履歴の項目と今の位置を引き継ぎ、今の項目のドキュメントだけを読み込み直す
+        let history = browser.borrow().current_page().borrow().history();
+        let urls: Vec<String> = history
+            .entries()
+            .iter()
+            .map(|entry| entry.url().to_string())
+            .collect();
+        assert_eq!(vec!["http://example.com/a", "http://example.com/b"], urls);
+        assert_eq!(Some(0), history.current_index());
+        let copied = history.get(0).and_then(|entry| entry.document());
+        let original = source
+            .borrow()
+            .history()
+            .get(0)
+            .and_then(|entry| entry.document());
+        match (copied, original) {
+            (Some(copied), Some(original)) => {
+                assert!(!Rc::ptr_eq(&copied.frame(), &original.frame()))
+            }
+            _ => panic!("documents should be loaded"),
+        }
+        assert!(history.get(1).and_then(|entry| entry.document()).is_none());
+        assert!(browser.borrow().current_page().borrow().can_go_forward());
+
+        assert_eq!(
+            Err(TabError::OutOfRange(4)),
+            Browser::duplicate_tab(&browser, 4)
+        );
+    }
 }
@@ -0,0 +1,62 @@
+use core::cell::Cell;
+use core::fmt::Debug;
+
+// 現在時刻を返す。no_stdの環境では時刻を取得する方法が決まっていないので、外から差し替えられるようにする
+pub trait Clock: Debug {
+    // 1970-01-01T00:00:00Zからの経過秒数
+    fn now(&self) -> u64;
+
+    // 実際の時刻を返せない場合はfalseを返す
+    // その場合、キャッシュの有効期限のような時刻をもとにした判断は、常に期限切れとして扱う
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+// 時刻を取得できない環境で使う時計。nowは常に0を返す
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoClock;
+
+impl NoClock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Clock for NoClock {
+    fn now(&self) -> u64 {
+        0
+    }
+
+    fn is_available(&self) -> bool {
+        false
+    }
+}
+
+// 手動で進める時計。時刻を取得できない環境やテストで使う
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> Self {
+        Self {
+            now: Cell::new(now),
+        }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, seconds: u64) {
+        self.now.set(self.now.get().saturating_add(seconds));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+}
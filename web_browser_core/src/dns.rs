@@ -0,0 +1,287 @@
+use crate::clock::Clock;
+use crate::http::transport::NetworkError;
+use crate::url::host::Host;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt::{Debug, Display};
+
+// 解決したアドレスをキャッシュしておく秒数のデフォルト値
+// OSのリゾルバはTTLを返さないことが多いので、固定の値を使う
+pub const DEFAULT_TTL: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpAddr {
+    V4([u8; 4]),  // https://url.spec.whatwg.org/#concept-ipv4
+    V6([u16; 8]), // https://url.spec.whatwg.org/#concept-ipv6
+}
+
+impl IpAddr {
+    // 127.0.0.1 や ::1、[::1] のようなIPアドレスの表記を解釈する。ドメインの場合はNoneを返す
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(input);
+        if input.contains(':') {
+            return match Host::parse(&format!("[{}]", input), false) {
+                Ok(Host::Ipv6(address)) => Some(IpAddr::V6(address)),
+                _ => None,
+            };
+        }
+        match Host::parse(input, false) {
+            Ok(Host::Ipv4(address)) => Some(IpAddr::V4(address)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for IpAddr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IpAddr::V4(address) => write!(f, "{}", Host::Ipv4(*address)),
+            IpAddr::V6(address) => {
+                let host = Host::Ipv6(*address).to_string();
+                write!(f, "{}", host.trim_start_matches('[').trim_end_matches(']'))
+            }
+        }
+    }
+}
+
+// ホスト名からIPアドレスを解決する手段
+// OSごとのネットワークの実装（net_wasabiなど）がOSのリゾルバを使って実装する
+pub trait Resolver: Debug {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetworkError>;
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    host: String,
+    addresses: Vec<IpAddr>,
+    expires: u64, // この時刻を過ぎたら解決し直す
+}
+
+// ブラウザが使うリゾルバ
+// /etc/hostsのように登録したホスト名は固定のアドレスを返し、それ以外はOSのリゾルバで解決してTTLの間キャッシュする
+// 複数のトランスポートから共有できるように、設定は&selfで変更できるようにする
+#[derive(Debug)]
+pub struct HostResolver {
+    hosts: RefCell<Vec<(String, Vec<IpAddr>)>>,
+    platform: RefCell<Option<Rc<dyn Resolver>>>,
+    cache: RefCell<Vec<CacheEntry>>,
+    clock: RefCell<Rc<dyn Clock>>,
+    ttl: Cell<u64>,
+}
+
+impl HostResolver {
+    pub fn new(clock: Rc<dyn Clock>) -> Self {
+        Self {
+            hosts: RefCell::new(Vec::new()),
+            platform: RefCell::new(None),
+            cache: RefCell::new(Vec::new()),
+            clock: RefCell::new(clock),
+            ttl: Cell::new(DEFAULT_TTL),
+        }
+    }
+
+    // 登録されていないホスト名を解決するOSのリゾルバ
+    pub fn set_platform(&self, platform: Rc<dyn Resolver>) {
+        *self.platform.borrow_mut() = Some(platform);
+        self.clear_cache();
+    }
+
+    pub fn set_clock(&self, clock: Rc<dyn Clock>) {
+        *self.clock.borrow_mut() = clock;
+    }
+
+    // 0の場合はキャッシュしない
+    pub fn set_ttl(&self, ttl: u64) {
+        self.ttl.set(ttl);
+    }
+
+    // ホスト名に固定のアドレスを割り当てる。同じホスト名の場合は上書きする
+    pub fn add_host(&self, host: &str, addresses: Vec<IpAddr>) {
+        let host = normalize_host(host);
+        let mut hosts = self.hosts.borrow_mut();
+        hosts.retain(|(h, _)| *h != host);
+        hosts.push((host, addresses));
+    }
+
+    pub fn remove_host(&self, host: &str) {
+        let host = normalize_host(host);
+        self.hosts.borrow_mut().retain(|(h, _)| *h != host);
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    // キャッシュしているホスト名の数。有効期限が切れたものも含む
+    pub fn cache_len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+}
+
+impl Resolver for HostResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetworkError> {
+        if let Some(address) = IpAddr::parse(host) {
+            return Ok(vec![address]);
+        }
+
+        let host = normalize_host(host);
+        if let Some((_, addresses)) = self.hosts.borrow().iter().find(|(h, _)| *h == host) {
+            return Ok(addresses.clone());
+        }
+
+        // 時刻がわからない場合は有効期限を判断できないので、キャッシュを使わずに毎回解決する
+        let clock = self.clock.borrow().clone();
+        let now = clock.now();
+        let mut cache = self.cache.borrow_mut();
+        cache.retain(|entry| entry.expires > now);
+        if let Some(entry) = cache.iter().find(|entry| entry.host == host) {
+            return Ok(entry.addresses.clone());
+        }
+
+        let platform = match self.platform.borrow().clone() {
+            Some(platform) => platform,
+            None => return Err(NetworkError::Dns(host)),
+        };
+        let addresses = platform.resolve(&host)?;
+        if addresses.is_empty() {
+            return Err(NetworkError::Dns(host));
+        }
+        let ttl = self.ttl.get();
+        if ttl > 0 && clock.is_available() {
+            cache.push(CacheEntry {
+                host,
+                addresses: addresses.clone(),
+                expires: now.saturating_add(ttl),
+            });
+        }
+        Ok(addresses)
+    }
+}
+
+// ホスト名の大文字小文字と、末尾の . は区別しない
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ManualClock, NoClock};
+
+    // 問い合わせの回数を数えて、いつも同じアドレスを返すリゾルバ
+    #[derive(Debug)]
+    struct CountingResolver {
+        address: IpAddr,
+        count: Cell<usize>,
+    }
+
+    impl Resolver for CountingResolver {
+        fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetworkError> {
+            self.count.set(self.count.get() + 1);
+            if host == "missing.example" {
+                return Err(NetworkError::Dns(host.to_string()));
+            }
+            Ok(vec![self.address])
+        }
+    }
+
+    fn setup() -> (Rc<ManualClock>, Rc<CountingResolver>, HostResolver) {
+        let clock = Rc::new(ManualClock::new(1000));
+        let platform = Rc::new(CountingResolver {
+            address: IpAddr::V4([93, 184, 216, 34]),
+            count: Cell::new(0),
+        });
+        let resolver = HostResolver::new(clock.clone());
+        resolver.set_platform(platform.clone());
+        (clock, platform, resolver)
+    }
+
+    #[test]
+    fn test_ip_addr() {
+        assert_eq!(Some(IpAddr::V4([127, 0, 0, 1])), IpAddr::parse("127.0.0.1"));
+        assert_eq!(
+            Some(IpAddr::V6([0, 0, 0, 0, 0, 0, 0, 1])),
+            IpAddr::parse("[::1]")
+        );
+        assert_eq!(None, IpAddr::parse("example.com"));
+        assert_eq!("::1", IpAddr::V6([0, 0, 0, 0, 0, 0, 0, 1]).to_string());
+        assert_eq!("10.0.0.1", IpAddr::V4([10, 0, 0, 1]).to_string());
+    }
+
+    #[test]
+    fn test_hosts() {
+        let (_, platform, resolver) = setup();
+        resolver.add_host("Example.COM", vec![IpAddr::V4([127, 0, 0, 1])]);
+        assert_eq!(
+            Ok(vec![IpAddr::V4([127, 0, 0, 1])]),
+            resolver.resolve("example.com.")
+        );
+        assert_eq!(0, platform.count.get());
+
+        resolver.remove_host("example.com");
+        assert_eq!(
+            Ok(vec![IpAddr::V4([93, 184, 216, 34])]),
+            resolver.resolve("example.com")
+        );
+        assert_eq!(1, platform.count.get());
+
+        // IPアドレスはそのまま返す
+        assert_eq!(
+            Ok(vec![IpAddr::V4([10, 0, 0, 1])]),
+            resolver.resolve("10.0.0.1")
+        );
+        assert_eq!(1, platform.count.get());
+    }
+
+    #[test]
+    fn test_cache_ttl() {
+        let (clock, platform, resolver) = setup();
+        resolver.set_ttl(30);
+        assert!(resolver.resolve("example.com").is_ok());
+        assert!(resolver.resolve("example.com").is_ok());
+        assert_eq!(1, platform.count.get());
+        assert_eq!(1, resolver.cache_len());
+
+        clock.advance(29);
+        assert!(resolver.resolve("example.com").is_ok());
+        assert_eq!(1, platform.count.get());
+
+        clock.advance(1);
+        assert!(resolver.resolve("example.com").is_ok());
+        assert_eq!(2, platform.count.get());
+
+        // 解決できなかったホスト名はキャッシュしない
+        assert_eq!(
+            Err(NetworkError::Dns("missing.example".to_string())),
+            resolver.resolve("missing.example")
+        );
+        assert!(resolver.resolve("missing.example").is_err());
+        assert_eq!(4, platform.count.get());
+    }
+
+    #[test]
+    fn test_cache_without_clock() {
+        let (_, platform, resolver) = setup();
+        resolver.set_clock(Rc::new(NoClock::new()));
+        assert!(resolver.resolve("example.com").is_ok());
+        assert!(resolver.resolve("example.com").is_ok());
+        assert_eq!(2, platform.count.get());
+        assert_eq!(0, resolver.cache_len());
+    }
+
+    #[test]
+    fn test_without_platform() {
+        let resolver = HostResolver::new(Rc::new(ManualClock::new(0)));
+        assert_eq!(
+            Err(NetworkError::Dns("example.com".to_string())),
+            resolver.resolve("example.com")
+        );
+    }
+}
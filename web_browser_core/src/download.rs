@@ -0,0 +1,98 @@
+use crate::http::HttpResponse;
+use crate::mime::MimeType;
+use crate::url::percent_encoding::percent_decode;
+use crate::url::Url;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// ファイル名を決められない場合に使う名前
+pub const DEFAULT_FILE_NAME: &str = "download";
+
+// 表示せずに保存するレスポンス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Download {
+    url: Option<Url>,
+    mime_type: MimeType,
+    file_name: String, // ディレクトリを含まない、保存するときのファイル名
+    body: Vec<u8>,
+}
+
+impl Download {
+    pub fn new(response: &HttpResponse, mime_type: MimeType) -> Self {
+        let url = response.url();
+        Self {
+            file_name: file_name(response, url.as_ref()),
+            url,
+            mime_type,
+            body: response.body(),
+        }
+    }
+
+    pub fn url(&self) -> Option<Url> {
+        self.url.clone()
+    }
+
+    pub fn mime_type(&self) -> MimeType {
+        self.mime_type.clone()
+    }
+
+    pub fn file_name(&self) -> String {
+        self.file_name.clone()
+    }
+
+    pub fn body(&self) -> Vec<u8> {
+        self.body.clone()
+    }
+}
+
+// Content-Dispositionのfilename、URLのパスの最後のセグメントの順に探す
+// 保存先のディレクトリの外に書き込まないように、/ や \ より前は取り除く
+fn file_name(response: &HttpResponse, url: Option<&Url>) -> String {
+    let from_header = response
+        .headers()
+        .content_disposition()
+        .and_then(|disposition| disposition.filename());
+    let from_url = url
+        .and_then(|url| url.path_segments())
+        .and_then(|segments| segments.last().cloned())
+        .map(|segment| String::from_utf8_lossy(&percent_decode(segment.as_bytes())).to_string());
+
+    for name in [from_header, from_url].into_iter().flatten() {
+        let name = name.rsplit(['/', '\\']).next().unwrap_or("").trim();
+        if !name.is_empty() && name != "." && name != ".." {
+            return name.to_string();
+        }
+    }
+    DEFAULT_FILE_NAME.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn download(url: &str, raw: &[u8]) -> Download {
+        let mut response = HttpResponse::new(raw).expect("failed to parse http response");
+        response.set_url_list(vec![Url::parse(url).expect("failed to parse url")]);
+        Download::new(&response, MimeType::new("application", "octet-stream"))
+    }
+
+    #[test]
+    fn test_file_name() {
+        let d = download(
+            "http://example.com/files/report%202024.pdf?x=1",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc",
+        );
+        assert_eq!("report 2024.pdf", d.file_name());
+        assert_eq!(b"abc".to_vec(), d.body());
+
+        let d = download(
+            "http://example.com/get",
+            b"HTTP/1.1 200 OK\r\nContent-Disposition: attachment; filename=\"../../etc/passwd\"\r\n\r\n",
+        );
+        assert_eq!("passwd", d.file_name());
+
+        let d = download("http://example.com/", b"HTTP/1.1 200 OK\r\n\r\n");
+        assert_eq!(DEFAULT_FILE_NAME, d.file_name());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::encoding::jis0208::JIS0208;
+use crate::encoding::Encoding;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://encoding.spec.whatwg.org/#decoder
+// 受け取ったバイト列を文字列にデコードする
+// チャンクの境界で分かれた文字は、残りのバイトを受け取るまでためておく。不正なバイト列はU+FFFDに置き換える
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    encoding: Encoding,
+    pending: Vec<u8>,                // まだデコードしていないバイト列
+    bom_checked: bool,               // 先頭のBOMを確かめたかどうか
+    iso_2022_jp_mode: Iso2022JpMode, // ISO-2022-JPのエスケープシーケンスで切り替えた状態
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            pending: Vec::new(),
+            bom_checked: false,
+            iso_2022_jp_mode: Iso2022JpMode::Ascii,
+        }
+    }
+
+    // 先頭にBOMがあった場合は、BOMの文字エンコーディングになる
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    // https://encoding.spec.whatwg.org/#decode
+    // lastがtrueの場合は最後のチャンクとして扱い、文字の途中で終わっているバイト列もU+FFFDにする
+    pub fn decode(&mut self, chunk: &[u8], last: bool) -> String {
+        self.pending.extend_from_slice(chunk);
+        if !self.bom_checked {
+            match Encoding::for_bom(&self.pending) {
+                Some((encoding, len)) => {
+                    self.encoding = encoding;
+                    self.pending.drain(..len);
+                }
+                // BOMかどうかを決めるには足りない
+                None if !last && is_bom_prefix(&self.pending) => return String::new(),
+                None => {}
+            }
+            self.bom_checked = true;
+        }
+
+        let mut decoded = String::new();
+        let bytes = &self.pending;
+        let consumed = match self.encoding {
+            Encoding::Utf8 => decode_utf8(bytes, last, &mut decoded),
+            Encoding::Utf16Be => decode_utf16(bytes, last, true, &mut decoded),
+            Encoding::Utf16Le => decode_utf16(bytes, last, false, &mut decoded),
+            Encoding::ShiftJis => decode_shift_jis(bytes, last, &mut decoded),
+            Encoding::EucJp => decode_euc_jp(bytes, last, &mut decoded),
+            Encoding::Iso2022Jp => {
+                decode_iso_2022_jp(bytes, last, &mut self.iso_2022_jp_mode, &mut decoded)
+            }
+            Encoding::Windows1252 => decode_windows_1252(bytes, &mut decoded),
+        };
+        self.pending.drain(..consumed);
+        decoded
+    }
+}
+
+// https://encoding.spec.whatwg.org/#iso-2022-jp-decoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Iso2022JpMode {
+    Ascii,    // ESC ( B
+    Roman,    // ESC ( J。JIS X 0201のラテン文字
+    Katakana, // ESC ( I。JIS X 0201の片仮名
+    Jis0208,  // ESC $ @ または ESC $ B
+}
+
+fn is_bom_prefix(bytes: &[u8]) -> bool {
+    [&[0xef, 0xbb, 0xbf][..], &[0xfe, 0xff], &[0xff, 0xfe]]
+        .iter()
+        .any(|bom| bom.starts_with(bytes))
+}
+
+// 以下の関数は、デコードしたバイト数を返す。デコードしなかったバイトは次のチャンクと一緒にデコードする
+
+// https://encoding.spec.whatwg.org/#utf-8-decoder
+fn decode_utf8(bytes: &[u8], last: bool, decoded: &mut String) -> usize {
+    let mut i = 0;
+    loop {
+        match core::str::from_utf8(&bytes[i..]) {
+            Ok(s) => {
+                decoded.push_str(s);
+                return bytes.len();
+            }
+            Err(e) => {
+                let valid = i + e.valid_up_to();
+                decoded.push_str(&String::from_utf8_lossy(&bytes[i..valid]));
+                match e.error_len() {
+                    Some(len) => {
+                        decoded.push('\u{FFFD}');
+                        i = valid + len;
+                    }
+                    // 文字の途中で終わっている
+                    None if last => {
+                        decoded.push('\u{FFFD}');
+                        return bytes.len();
+                    }
+                    None => return valid,
+                }
+            }
+        }
+    }
+}
+
+// https://encoding.spec.whatwg.org/#shared-utf-16-decoder
+// 対になっていないサロゲートはU+FFFDにする
+fn decode_utf16(bytes: &[u8], last: bool, big_endian: bool, decoded: &mut String) -> usize {
+    let unit = |i: usize| {
+        let pair = [bytes[i], bytes[i + 1]];
+        if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    };
+
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        let code_unit = unit(i);
+        match code_unit {
+            0xd800..=0xdbff => {
+                if i + 3 >= bytes.len() {
+                    if !last {
+                        return i;
+                    }
+                    decoded.push('\u{FFFD}');
+                    i += 2;
+                    continue;
+                }
+                let trail = unit(i + 2);
+                if (0xdc00..=0xdfff).contains(&trail) {
+                    let c = 0x10000 + ((code_unit as u32 - 0xd800) << 10) + (trail as u32 - 0xdc00);
+                    decoded.push(char::from_u32(c).unwrap_or('\u{FFFD}'));
+                    i += 4;
+                } else {
+                    decoded.push('\u{FFFD}');
+                    i += 2;
+                }
+            }
+            0xdc00..=0xdfff => {
+                decoded.push('\u{FFFD}');
+                i += 2;
+            }
+            _ => {
+                decoded.push(char::from_u32(code_unit as u32).unwrap_or('\u{FFFD}'));
+                i += 2;
+            }
+        }
+    }
+
+    if i < bytes.len() && last {
+        decoded.push('\u{FFFD}');
+        return bytes.len();
+    }
+    i
+}
+
+// https://encoding.spec.whatwg.org/#shift_jis-decoder
+fn decode_shift_jis(bytes: &[u8], last: bool, decoded: &mut String) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = bytes[i];
+        match lead {
+            0x00..=0x80 => {
+                decoded.push(lead as char);
+                i += 1;
+            }
+            // 半角片仮名
+            0xa1..=0xdf => {
+                decoded.push(char::from_u32(0xff61 + (lead as u32 - 0xa1)).unwrap_or('\u{FFFD}'));
+                i += 1;
+            }
+            0x81..=0x9f | 0xe0..=0xfc => {
+                let trail = match bytes.get(i + 1) {
+                    Some(trail) => *trail,
+                    None if last => {
+                        decoded.push('\u{FFFD}');
+                        return bytes.len();
+                    }
+                    None => return i,
+                };
+                let lead_offset = if lead < 0xa0 { 0x81 } else { 0xc1 };
+                let trail_offset = if trail < 0x7f { 0x40 } else { 0x41 };
+                if matches!(trail, 0x40..=0x7e | 0x80..=0xfc) {
+                    let pointer =
+                        (lead - lead_offset) as usize * 188 + (trail - trail_offset) as usize;
+                    // ユーザー定義文字は私用領域に割り当てる
+                    let code_point = if (8836..=10715).contains(&pointer) {
+                        Some(0xe000 + (pointer - 8836) as u32)
+                    } else {
+                        jis0208(pointer)
+                    };
+                    if let Some(c) = code_point.and_then(char::from_u32) {
+                        decoded.push(c);
+                        i += 2;
+                        continue;
+                    }
+                }
+                // 2バイト目がASCIIの場合は、次の文字としてデコードし直す
+                decoded.push('\u{FFFD}');
+                i += if trail < 0x80 { 1 } else { 2 };
+            }
+            _ => {
+                decoded.push('\u{FFFD}');
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+// https://encoding.spec.whatwg.org/#euc-jp-decoder
+// JIS X 0212の補助漢字（0x8Fで始まる3バイトの文字）には対応していないので、U+FFFDにする
+fn decode_euc_jp(bytes: &[u8], last: bool, decoded: &mut String) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = bytes[i];
+        let len = match lead {
+            0x00..=0x7f => {
+                decoded.push(lead as char);
+                i += 1;
+                continue;
+            }
+            0x8e | 0xa1..=0xfe => 2,
+            0x8f => 3,
+            _ => {
+                decoded.push('\u{FFFD}');
+                i += 1;
+                continue;
+            }
+        };
+        if i + len > bytes.len() {
+            if !last {
+                return i;
+            }
+            // 足りない分のバイトが正しいかは確かめずにU+FFFDにする
+            decoded.push('\u{FFFD}');
+            return bytes.len();
+        }
+
+        let trail = bytes[i + 1];
+        let code_point = match lead {
+            // 半角片仮名
+            0x8e if (0xa1..=0xdf).contains(&trail) => Some(0xff61 + (trail as u32 - 0xa1)),
+            0x8f if (0xa1..=0xfe).contains(&trail) && (0xa1..=0xfe).contains(&bytes[i + 2]) => {
+                decoded.push('\u{FFFD}');
+                i += 3;
+                continue;
+            }
+            0xa1..=0xfe if (0xa1..=0xfe).contains(&trail) => {
+                jis0208((lead - 0xa1) as usize * 94 + (trail - 0xa1) as usize)
+            }
+            _ => None,
+        };
+        if let Some(c) = code_point.and_then(char::from_u32) {
+            decoded.push(c);
+            i += 2;
+            continue;
+        }
+        decoded.push('\u{FFFD}');
+        i += if trail < 0x80 { 1 } else { 2 };
+    }
+    i
+}
+
+// https://encoding.spec.whatwg.org/#iso-2022-jp-decoder
+// エスケープシーケンスで切り替えた状態はチャンクをまたいで引き継ぐ
+fn decode_iso_2022_jp(
+    bytes: &[u8],
+    last: bool,
+    mode: &mut Iso2022JpMode,
+    decoded: &mut String,
+) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == 0x1b {
+            if i + 2 >= bytes.len() && !last {
+                return i;
+            }
+            let next = match bytes.get(i + 1..i + 3) {
+                Some(b"(B") => Some(Iso2022JpMode::Ascii),
+                Some(b"(J") => Some(Iso2022JpMode::Roman),
+                Some(b"(I") => Some(Iso2022JpMode::Katakana),
+                Some(b"$@") | Some(b"$B") => Some(Iso2022JpMode::Jis0208),
+                _ => None,
+            };
+            match next {
+                Some(next) => {
+                    *mode = next;
+                    i += 3;
+                }
+                // 知らないエスケープシーケンスは、ESCの後ろを次の文字としてデコードし直す
+                None => {
+                    decoded.push('\u{FFFD}');
+                    i += 1;
+                }
+            }
+            continue;
+        }
+
+        let c = match *mode {
+            Iso2022JpMode::Ascii if b < 0x80 && b != 0x0e && b != 0x0f => Some(b as u32),
+            Iso2022JpMode::Roman if b == 0x5c => Some(0xa5),
+            Iso2022JpMode::Roman if b == 0x7e => Some(0x203e),
+            Iso2022JpMode::Roman if b < 0x80 && b != 0x0e && b != 0x0f => Some(b as u32),
+            Iso2022JpMode::Katakana if (0x21..=0x5f).contains(&b) => {
+                Some(0xff61 + (b as u32 - 0x21))
+            }
+            Iso2022JpMode::Jis0208 if (0x21..=0x7e).contains(&b) => {
+                let trail = match bytes.get(i + 1) {
+                    Some(trail) => *trail,
+                    None if last => {
+                        decoded.push('\u{FFFD}');
+                        return bytes.len();
+                    }
+                    None => return i,
+                };
+                if trail == 0x1b {
+                    // ESCは次のエスケープシーケンスとして処理する
+                    decoded.push('\u{FFFD}');
+                    i += 1;
+                    continue;
+                }
+                i += 1;
+                if (0x21..=0x7e).contains(&trail) {
+                    jis0208((b - 0x21) as usize * 94 + (trail - 0x21) as usize)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        decoded.push(c.and_then(char::from_u32).unwrap_or('\u{FFFD}'));
+        i += 1;
+    }
+    i
+}
+
+// https://encoding.spec.whatwg.org/#index-windows-1252
+// 0x80から0x9Fまでのコードポイント。それ以外のバイトはそのままコードポイントになる
+const WINDOWS_1252: [u16; 32] = [
+    0x20ac, 0x0081, 0x201a, 0x0192, 0x201e, 0x2026, 0x2020, 0x2021, 0x02c6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x008d, 0x017d, 0x008f, 0x0090, 0x2018, 0x2019, 0x201c, 0x201d, 0x2022, 0x2013, 0x2014,
+    0x02dc, 0x2122, 0x0161, 0x203a, 0x0153, 0x009d, 0x017e, 0x0178,
+];
+
+// https://encoding.spec.whatwg.org/#single-byte-decoder
+fn decode_windows_1252(bytes: &[u8], decoded: &mut String) -> usize {
+    for b in bytes {
+        let c = match b {
+            0x80..=0x9f => WINDOWS_1252[(b - 0x80) as usize] as u32,
+            _ => *b as u32,
+        };
+        decoded.push(char::from_u32(c).unwrap_or('\u{FFFD}'));
+    }
+    bytes.len()
+}
+
+// https://encoding.spec.whatwg.org/#index-code-point
+fn jis0208(pointer: usize) -> Option<u32> {
+    match JIS0208.get(pointer) {
+        Some(0) | None => None,
+        Some(c) => Some(*c as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoding: Encoding, bytes: &[u8]) -> String {
+        Decoder::new(encoding).decode(bytes, true)
+    }
+
+    #[test]
+    fn test_utf8() {
+        let mut decoder = Decoder::new(Encoding::Utf8);
+        let bytes = "あい".as_bytes();
+        assert_eq!("", decoder.decode(&bytes[..2], false));
+        assert_eq!("あ", decoder.decode(&bytes[2..4], false));
+        assert_eq!("い", decoder.decode(&bytes[4..], false));
+        assert_eq!("a\u{FFFD}b", decoder.decode(b"a\xffb", false));
+        assert_eq!("", decoder.decode(b"\xe3\x81", false));
+        assert_eq!("\u{FFFD}", decoder.decode(b"", true));
+    }
+
+    #[test]
+    fn test_bom() {
+        // BOMは取り除き、BOMの文字エンコーディングでデコードする
+        let mut decoder = Decoder::new(Encoding::ShiftJis);
+        assert_eq!("", decoder.decode(b"\xef\xbb", false));
+        assert_eq!("あ", decoder.decode(b"\xbf\xe3\x81\x82", true));
+        assert_eq!(Encoding::Utf8, decoder.encoding());
+
+        assert_eq!("hi", decode(Encoding::Utf8, b"\xff\xfeh\0i\0"));
+        assert_eq!("\u{FFFD}", decode(Encoding::Utf8, b"\xef\xbb"));
+    }
+
+    #[test]
+    fn test_utf16() {
+        assert_eq!("aあ", decode(Encoding::Utf16Be, b"\x00a\x30\x42"));
+        assert_eq!("aあ", decode(Encoding::Utf16Le, b"a\x00\x42\x30"));
+        assert_eq!("😀", decode(Encoding::Utf16Be, b"\xd8\x3d\xde\x00"));
+        assert_eq!("\u{FFFD}a", decode(Encoding::Utf16Be, b"\xd8\x3d\x00a"));
+        assert_eq!("a\u{FFFD}", decode(Encoding::Utf16Be, b"\x00a\x00"));
+
+        let mut decoder = Decoder::new(Encoding::Utf16Be);
+        assert_eq!("", decoder.decode(b"\xd8\x3d\xde", false));
+        assert_eq!("😀", decoder.decode(b"\x00", false));
+    }
+
+    #[test]
+    fn test_shift_jis() {
+        assert_eq!(
+            "あア漢字ｱ\u{301C}",
+            decode(
+                Encoding::ShiftJis,
+                b"\x82\xa0\x83\x41\x8a\xbf\x8e\x9a\xb1\x81\x60"
+            )
+        );
+        // 全角のマイナスやセント記号などは、Windowsと同じく全角形のコードポイントにする
+        assert_eq!(
+            "\u{FF0D}\u{FFE0}\u{FFE1}\u{FFE2}",
+            decode(Encoding::ShiftJis, b"\x81\x7c\x81\x91\x81\x92\x81\xca")
+        );
+        // NECの特殊文字とIBM拡張文字
+        assert_eq!("①髙", decode(Encoding::ShiftJis, b"\x87\x40\xfb\xfc"));
+        assert_eq!("\u{E000}", decode(Encoding::ShiftJis, b"\xf0\x40"));
+        // 2バイト目がASCIIの場合はASCIIとしてデコードし直す
+        assert_eq!("\u{FFFD}<", decode(Encoding::ShiftJis, b"\x82<"));
+        assert_eq!("\u{FFFD}\u{FFFD}", decode(Encoding::ShiftJis, b"\xa0\xfd"));
+
+        let mut decoder = Decoder::new(Encoding::ShiftJis);
+        assert_eq!("a", decoder.decode(b"a\x82", false));
+        assert_eq!("あ", decoder.decode(b"\xa0", false));
+        assert_eq!("\u{FFFD}", decoder.decode(b"\x82", true));
+    }
+
+    #[test]
+    fn test_euc_jp() {
+        assert_eq!(
+            "あア漢字ｱ",
+            decode(Encoding::EucJp, b"\xa4\xa2\xa5\xa2\xb4\xc1\xbb\xfa\x8e\xb1")
+        );
+        // JIS X 0212には対応していない
+        assert_eq!("\u{FFFD}a", decode(Encoding::EucJp, b"\x8f\xb0\xa1a"));
+        assert_eq!("\u{FFFD}<", decode(Encoding::EucJp, b"\xa4<"));
+
+        let mut decoder = Decoder::new(Encoding::EucJp);
+        assert_eq!("", decoder.decode(b"\xa4", false));
+        assert_eq!("あ", decoder.decode(b"\xa2", true));
+    }
+
+    #[test]
+    fn test_iso_2022_jp() {
+        assert_eq!(
+            "aあいb",
+            decode(Encoding::Iso2022Jp, b"a\x1b$B\x24\x22\x24\x24\x1b(Bb")
+        );
+        assert_eq!(
+            "\u{a5}\u{203e}ｱ",
+            decode(Encoding::Iso2022Jp, b"\x1b(J\\~\x1b(I1\x1b(B")
+        );
+        assert_eq!("\u{FFFD}(Xa", decode(Encoding::Iso2022Jp, b"\x1b(Xa"));
+
+        // エスケープシーケンスで切り替えた状態は次のチャンクに引き継ぐ
+        let mut decoder = Decoder::new(Encoding::Iso2022Jp);
+        assert_eq!("", decoder.decode(b"\x1b$", false));
+        assert_eq!("", decoder.decode(b"B\x24", false));
+        assert_eq!("あ", decoder.decode(b"\x22", false));
+        assert_eq!("い", decoder.decode(b"\x24\x24\x1b(B", true));
+    }
+
+    #[test]
+    fn test_windows_1252() {
+        assert_eq!("café €", decode(Encoding::Windows1252, b"caf\xe9 \x80"));
+        assert_eq!("\u{81}", decode(Encoding::Windows1252, b"\x81"));
+    }
+}
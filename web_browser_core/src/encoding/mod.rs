@@ -0,0 +1,235 @@
+pub mod decoder;
+pub mod jis0208;
+pub mod prescan;
+
+use crate::encoding::decoder::Decoder;
+use crate::encoding::prescan::prescan;
+use crate::mime::MimeType;
+use alloc::string::String;
+use core::fmt::Display;
+
+// https://encoding.spec.whatwg.org/#names-and-labels
+// デコードできる文字エンコーディング
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Be,
+    Utf16Le,
+    ShiftJis,
+    EucJp,
+    Iso2022Jp,
+    Windows1252, // ISO-8859-1やUS-ASCIIのラベルもこれになる
+}
+
+impl Encoding {
+    // https://encoding.spec.whatwg.org/#concept-encoding-get
+    // Content-Typeのcharsetや<meta charset>の値から文字エンコーディングを決める。大文字小文字は区別しない
+    pub fn for_label(label: &str) -> Option<Self> {
+        let label = label
+            .trim_matches(|c| matches!(c, '\t' | '\n' | '\x0c' | '\r' | ' '))
+            .to_ascii_lowercase();
+        let encoding = match label.as_str() {
+            "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+            | "x-unicode20utf8" => Encoding::Utf8,
+            "unicodefffe" | "utf-16be" => Encoding::Utf16Be,
+            "csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff" | "utf-16"
+            | "utf-16le" => Encoding::Utf16Le,
+            "csshiftjis" | "ms932" | "ms_kanji" | "shift-jis" | "shift_jis" | "sjis"
+            | "windows-31j" | "x-sjis" => Encoding::ShiftJis,
+            "cseucpkdfmtjapanese" | "euc-jp" | "x-euc-jp" => Encoding::EucJp,
+            "csiso2022jp" | "iso-2022-jp" => Encoding::Iso2022Jp,
+            "ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1" | "ibm819"
+            | "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591" | "iso_8859-1"
+            | "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii" | "windows-1252" | "x-cp1252" => {
+                Encoding::Windows1252
+            }
+            _ => return None,
+        };
+        Some(encoding)
+    }
+
+    // https://encoding.spec.whatwg.org/#bom-sniff
+    // 先頭のBOMから文字エンコーディングを決め、BOMのバイト数と一緒に返す
+    pub fn for_bom(bytes: &[u8]) -> Option<(Self, usize)> {
+        if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+            Some((Encoding::Utf8, 3))
+        } else if bytes.starts_with(&[0xfe, 0xff]) {
+            Some((Encoding::Utf16Be, 2))
+        } else if bytes.starts_with(&[0xff, 0xfe]) {
+            Some((Encoding::Utf16Le, 2))
+        } else {
+            None
+        }
+    }
+
+    // https://encoding.spec.whatwg.org/#concept-encoding-name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::ShiftJis => "Shift_JIS",
+            Encoding::EucJp => "EUC-JP",
+            Encoding::Iso2022Jp => "ISO-2022-JP",
+            Encoding::Windows1252 => "windows-1252",
+        }
+    }
+
+    pub fn new_decoder(&self) -> Decoder {
+        Decoder::new(*self)
+    }
+
+    // バイト列全体を一度にデコードする
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        self.new_decoder().decode(bytes, true)
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding
+// BOM、Content-Typeのcharset、HTMLの場合は<meta charset>、ボディの中身の順に文字エンコーディングを決める
+// bytesにはボディの先頭を渡す。<meta charset>は先頭の1024バイトから探す
+pub fn determine_encoding(mime_type: &MimeType, bytes: &[u8]) -> Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if let Some(encoding) = mime_type
+        .charset()
+        .and_then(|charset| Encoding::for_label(&charset))
+    {
+        return encoding;
+    }
+    if mime_type.is_html() {
+        if let Some(encoding) = prescan(bytes) {
+            return encoding;
+        }
+    }
+    guess_encoding(bytes)
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding
+// 文字エンコーディングが指定されていない場合に、バイト列から推測する
+// ISO-2022-JPのエスケープシーケンスがあればISO-2022-JP、UTF-8として正しければUTF-8にする
+// それ以外は、Shift_JISとEUC-JPのうち不正なバイト列なしにデコードできる方を選び、どちらも不正な場合はwindows-1252にする
+fn guess_encoding(bytes: &[u8]) -> Encoding {
+    // ISO-2022-JPは7ビットのバイト列なので、UTF-8より先に確かめる
+    if [&b"\x1b$B"[..], b"\x1b$@", b"\x1b(J", b"\x1b(I"]
+        .iter()
+        .any(|escape| bytes.windows(escape.len()).any(|w| w == *escape))
+    {
+        return Encoding::Iso2022Jp;
+    }
+    // 末尾で文字の途中まで読んでいる場合もUTF-8とみなす
+    let utf8 = match core::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if utf8 {
+        return Encoding::Utf8;
+    }
+
+    let errors = |encoding: Encoding| {
+        encoding
+            .new_decoder()
+            .decode(bytes, false)
+            .matches('\u{FFFD}')
+            .count()
+    };
+    let shift_jis_errors = errors(Encoding::ShiftJis);
+    let euc_jp_errors = errors(Encoding::EucJp);
+    if shift_jis_errors == 0 && euc_jp_errors == 0 {
+        // どちらでもデコードできる場合、Shift_JISで半角片仮名になるならEUC-JPの全角文字の可能性が高い
+        let half_width_katakana = Encoding::ShiftJis
+            .new_decoder()
+            .decode(bytes, false)
+            .chars()
+            .any(|c| ('\u{ff61}'..='\u{ff9f}').contains(&c));
+        if half_width_katakana {
+            return Encoding::EucJp;
+        }
+        return Encoding::ShiftJis;
+    }
+    if shift_jis_errors == 0 {
+        return Encoding::ShiftJis;
+    }
+    if euc_jp_errors == 0 {
+        return Encoding::EucJp;
+    }
+    Encoding::Windows1252
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn html(charset: Option<&str>) -> MimeType {
+        let mut input = String::from("text/html");
+        if let Some(charset) = charset {
+            input.push_str("; charset=");
+            input.push_str(charset);
+        }
+        MimeType::parse(&input).expect("failed to parse mime type")
+    }
+
+    #[test]
+    fn test_for_label() {
+        assert_eq!(Some(Encoding::ShiftJis), Encoding::for_label(" Shift_JIS "));
+        assert_eq!(Some(Encoding::ShiftJis), Encoding::for_label("windows-31j"));
+        assert_eq!(Some(Encoding::EucJp), Encoding::for_label("x-euc-jp"));
+        assert_eq!(Some(Encoding::Utf16Le), Encoding::for_label("utf-16"));
+        assert_eq!(
+            Some(Encoding::Windows1252),
+            Encoding::for_label("ISO-8859-1")
+        );
+        assert_eq!(None, Encoding::for_label("gbk"));
+        assert_eq!("Shift_JIS", Encoding::ShiftJis.to_string());
+    }
+
+    #[test]
+    fn test_determine_encoding() {
+        let sjis_meta = b"<meta charset=shift_jis>\x82\xa0";
+        // BOMを最優先する
+        assert_eq!(
+            Encoding::Utf16Be,
+            determine_encoding(&html(Some("euc-jp")), b"\xfe\xff\x00a")
+        );
+        // Content-Typeのcharsetは<meta>より優先する
+        assert_eq!(
+            Encoding::EucJp,
+            determine_encoding(&html(Some("EUC-JP")), sjis_meta)
+        );
+        assert_eq!(
+            Encoding::ShiftJis,
+            determine_encoding(&html(Some("unknown")), sjis_meta)
+        );
+        // HTML以外では<meta>を探さない
+        let text = MimeType::new("text", "plain");
+        assert_eq!(
+            Encoding::Windows1252,
+            determine_encoding(&text, b"<meta charset=shift_jis>caf\xe9!")
+        );
+    }
+
+    #[test]
+    fn test_guess_encoding() {
+        assert_eq!(Encoding::Utf8, guess_encoding(b"ascii only"));
+        assert_eq!(Encoding::Utf8, guess_encoding(&"あい".as_bytes()[..5]));
+        assert_eq!(Encoding::Iso2022Jp, guess_encoding(b"\x1b$B\x24\x22\x1b(B"));
+        // 「こんにちは」
+        assert_eq!(
+            Encoding::ShiftJis,
+            guess_encoding(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd")
+        );
+        assert_eq!(
+            Encoding::EucJp,
+            guess_encoding(b"\xa4\xb3\xa4\xf3\xa4\xcb\xa4\xc1\xa4\xcf")
+        );
+        assert_eq!(Encoding::Windows1252, guess_encoding(b"caf\xe9!"));
+    }
+}
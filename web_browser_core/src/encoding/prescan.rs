@@ -0,0 +1,285 @@
+use crate::encoding::Encoding;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+// <meta charset>を探すバイト数
+pub const PRESCAN_SIZE: usize = 1024;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+// HTMLをパースする前に、先頭のバイト列から<meta charset>や<meta http-equiv="Content-Type">を探す
+pub fn prescan(bytes: &[u8]) -> Option<Encoding> {
+    let mut scanner = Prescanner {
+        bytes: &bytes[..bytes.len().min(PRESCAN_SIZE)],
+        position: 0,
+    };
+    scanner.scan()
+}
+
+// 小文字にした属性の名前と値
+type Attribute = (Vec<u8>, Vec<u8>);
+
+struct Prescanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Prescanner<'_> {
+    fn scan(&mut self) -> Option<Encoding> {
+        while self.position < self.bytes.len() {
+            let rest = &self.bytes[self.position..];
+            if rest.starts_with(b"<!--") {
+                // <!--> のように、<!-- の - と重なる --> でもコメントは終わる
+                let end = find(&rest[2..], b"-->")?;
+                self.position += 2 + end + 3;
+                continue;
+            }
+            if starts_with_ignore_case(rest, b"<meta")
+                && rest.get(5).is_some_and(|b| is_whitespace(*b) || *b == b'/')
+            {
+                self.position += 5;
+                if let Some(encoding) = self.meta()? {
+                    return Some(encoding);
+                }
+            } else if rest[0] == b'<'
+                && rest.len() > 2
+                && (rest[1].is_ascii_alphabetic()
+                    || (rest[1] == b'/' && rest[2].is_ascii_alphabetic()))
+            {
+                // ほかのタグは属性ごと読み飛ばす。属性の値の中の<meta>は無視される
+                let name_end = rest.iter().position(|b| is_whitespace(*b) || *b == b'>')?;
+                self.position += name_end;
+                while self.attribute()?.is_some() {}
+            } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?")
+            {
+                self.position += find(rest, b">")?;
+            }
+            self.position += 1;
+        }
+        None
+    }
+
+    // <meta の後ろの属性から文字エンコーディングを決める
+    // 末尾まで読んでしまった場合はNone、このmetaから決まらなかった場合はSome(None)を返す
+    fn meta(&mut self) -> Option<Option<Encoding>> {
+        let mut names: Vec<Vec<u8>> = Vec::new();
+        let mut got_pragma = false;
+        let mut need_pragma: Option<bool> = None;
+        // Noneはまだ見つかっていない、Some(None)は知らないラベルだったことを表す
+        let mut charset: Option<Option<Encoding>> = None;
+
+        while let Some((name, value)) = self.attribute()? {
+            if names.contains(&name) {
+                continue;
+            }
+            match name.as_slice() {
+                b"http-equiv" => got_pragma |= value == b"content-type",
+                b"content" if charset.is_none() => {
+                    if let Some(label) = extract_charset(&value) {
+                        charset = Some(label_to_encoding(&label));
+                        need_pragma = Some(true);
+                    }
+                }
+                b"charset" if charset.is_none() => {
+                    charset = Some(label_to_encoding(&value));
+                    need_pragma = Some(false);
+                }
+                _ => {}
+            }
+            names.push(name);
+        }
+
+        let encoding = match (need_pragma, charset) {
+            (Some(need_pragma), Some(Some(encoding))) if got_pragma || !need_pragma => encoding,
+            _ => return Some(None),
+        };
+        // UTF-16の<meta>は、ASCIIと互換性のあるバイト列として読めているので正しくない
+        let encoding = match encoding {
+            Encoding::Utf16Be | Encoding::Utf16Le => Encoding::Utf8,
+            encoding => encoding,
+        };
+        Some(Some(encoding))
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#concept-get-attributes-when-sniffing
+    // 名前と値を小文字にして返す。タグの終わりの場合はSome(None)、末尾まで読んでしまった場合はNoneを返す
+    fn attribute(&mut self) -> Option<Option<Attribute>> {
+        while is_whitespace(self.peek()?) || self.peek()? == b'/' {
+            self.position += 1;
+        }
+        if self.peek()? == b'>' {
+            return Some(None);
+        }
+
+        let mut name = Vec::new();
+        let mut value = Vec::new();
+        loop {
+            let b = self.peek()?;
+            match b {
+                b'=' if !name.is_empty() => {
+                    self.position += 1;
+                    break;
+                }
+                b if is_whitespace(b) => {
+                    while is_whitespace(self.peek()?) {
+                        self.position += 1;
+                    }
+                    if self.peek()? != b'=' {
+                        return Some(Some((name, value)));
+                    }
+                    self.position += 1;
+                    break;
+                }
+                b'/' | b'>' => return Some(Some((name, value))),
+                b => name.push(b.to_ascii_lowercase()),
+            }
+            self.position += 1;
+        }
+
+        while is_whitespace(self.peek()?) {
+            self.position += 1;
+        }
+        let quote = self.peek()?;
+        if quote == b'"' || quote == b'\'' {
+            self.position += 1;
+            loop {
+                let b = self.peek()?;
+                self.position += 1;
+                if b == quote {
+                    return Some(Some((name, value)));
+                }
+                value.push(b.to_ascii_lowercase());
+            }
+        }
+        loop {
+            let b = self.peek()?;
+            if is_whitespace(b) || b == b'>' {
+                return Some(Some((name, value)));
+            }
+            value.push(b.to_ascii_lowercase());
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element
+// content属性の "text/html; charset=Shift_JIS" のような値からcharsetの値を取り出す
+fn extract_charset(content: &[u8]) -> Option<Vec<u8>> {
+    let mut position = 0;
+    loop {
+        position += find_ignore_case(&content[position..], b"charset")? + 7;
+        let rest = &content[position..];
+        let start = rest
+            .iter()
+            .position(|b| !is_whitespace(*b))
+            .unwrap_or(rest.len());
+        if rest.get(start) != Some(&b'=') {
+            position += start;
+            continue;
+        }
+
+        let rest = &rest[start + 1..];
+        let rest = &rest[rest
+            .iter()
+            .position(|b| !is_whitespace(*b))
+            .unwrap_or(rest.len())..];
+        return match rest.first() {
+            Some(quote @ (b'"' | b'\'')) => {
+                let end = rest[1..].iter().position(|b| b == quote)?;
+                Some(rest[1..1 + end].to_vec())
+            }
+            Some(_) => {
+                let end = rest
+                    .iter()
+                    .position(|b| is_whitespace(*b) || *b == b';')
+                    .unwrap_or(rest.len());
+                Some(rest[..end].to_vec())
+            }
+            None => None,
+        };
+    }
+}
+
+// x-user-definedには対応していないので、仕様のとおりwindows-1252にする
+fn label_to_encoding(label: &[u8]) -> Option<Encoding> {
+    let label = String::from_utf8_lossy(label);
+    if label.trim() == "x-user-defined" {
+        return Some(Encoding::Windows1252);
+    }
+    Encoding::for_label(&label)
+}
+
+// https://infra.spec.whatwg.org/#ascii-whitespace
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, 0x09 | 0x0a | 0x0c | 0x0d | 0x20)
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes.windows(pattern.len()).position(|w| w == pattern)
+}
+
+fn find_ignore_case(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes
+        .windows(pattern.len())
+        .position(|w| w.eq_ignore_ascii_case(pattern))
+}
+
+fn starts_with_ignore_case(bytes: &[u8], pattern: &[u8]) -> bool {
+    bytes.len() >= pattern.len() && bytes[..pattern.len()].eq_ignore_ascii_case(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_charset() {
+        assert_eq!(
+            Some(Encoding::ShiftJis),
+            prescan(b"<!doctype html><html><head><meta charset=\"Shift_JIS\">")
+        );
+        assert_eq!(Some(Encoding::EucJp), prescan(b"<META CHARSET=euc-jp>"));
+        assert_eq!(
+            Some(Encoding::EucJp),
+            prescan(b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset='EUC-JP'\">")
+        );
+        // http-equivがない場合はcontentを使わない
+        assert_eq!(
+            None,
+            prescan(b"<meta content=\"text/html; charset=EUC-JP\">")
+        );
+        // 知らないラベルの<meta>は無視して次を探す
+        assert_eq!(
+            Some(Encoding::Iso2022Jp),
+            prescan(b"<meta charset=unknown><meta charset=iso-2022-jp>")
+        );
+        // UTF-16はUTF-8、x-user-definedはwindows-1252にする
+        assert_eq!(Some(Encoding::Utf8), prescan(b"<meta charset=utf-16le>"));
+        assert_eq!(
+            Some(Encoding::Windows1252),
+            prescan(b"<meta charset=x-user-defined>")
+        );
+    }
+
+    #[test]
+    fn test_skip_comments_and_attributes() {
+        assert_eq!(
+            None,
+            prescan(b"<!-- <meta charset=shift_jis> --><p title=\"<meta charset=shift_jis>\">")
+        );
+        assert_eq!(
+            Some(Encoding::ShiftJis),
+            prescan(b"<!--><meta charset=shift_jis>")
+        );
+        // 先頭の1024バイトより後ろは探さない
+        let mut html = b"<p>".repeat(PRESCAN_SIZE / 3);
+        html.extend_from_slice(b"<meta charset=shift_jis>");
+        assert_eq!(None, prescan(&html));
+        // 末尾で終わっている<meta>は使わない
+        assert_eq!(None, prescan(b"<meta charset=shift_jis"));
+    }
+}
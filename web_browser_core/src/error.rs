@@ -1,9 +1,71 @@
+use crate::browser::TabError;
+use crate::http::transport::NetworkError;
+use crate::http::HttpError;
+use crate::loader::LoadError;
+use crate::renderer::css::CssError;
+use crate::renderer::html::HtmlError;
+use crate::renderer::layout::LayoutError;
+use crate::url::UrlError;
 use alloc::string::String;
+use core::fmt::Display;
 
+// 各モジュールのエラーをまとめたもの。どのモジュールで失敗したかを型で区別できる
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
-    Network(String),
+    Url(UrlError),
+    Http(HttpError),
+    Network(NetworkError),
+    Load(LoadError),
+    Tab(TabError),
+    Html(HtmlError),
+    Css(CssError),
+    Layout(LayoutError),
     UnexpectedInput(String),
     InvalidUI(String),
     Other(String),
 }
+
+// エラーページにそのまま表示できるメッセージにする
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Url(e) => write!(f, "invalid url: {}", e),
+            Error::Http(e) => write!(f, "http error: {}", e),
+            Error::Network(e) => write!(f, "network error: {}", e),
+            Error::Load(e) => write!(f, "load error: {}", e),
+            Error::Tab(e) => write!(f, "tab error: {}", e),
+            Error::Html(e) => write!(f, "html parse error: {}", e),
+            Error::Css(e) => write!(f, "css parse error: {}", e),
+            Error::Layout(e) => write!(f, "layout error: {}", e),
+            Error::UnexpectedInput(s) => write!(f, "unexpected input: {}", s),
+            Error::InvalidUI(s) => write!(f, "invalid ui: {}", s),
+            Error::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpParseError;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "invalid url: missing host at offset 7",
+            Error::from(UrlError::MissingHost { offset: 7 }).to_string()
+        );
+        assert_eq!(
+            "http error: invalid status code: OK",
+            Error::from(HttpParseError::InvalidStatusCode("OK".to_string())).to_string()
+        );
+        assert_eq!(
+            "network error: connection refused: 127.0.0.1:8000",
+            Error::from(NetworkError::ConnectionRefused(
+                "127.0.0.1:8000".to_string()
+            ))
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,649 @@
+use crate::clock::Clock;
+use crate::hash::{md5, sha256, to_hex};
+use crate::http::pool::origin_key;
+use crate::http::request::HttpRequest;
+use crate::random::{NoRandom, RandomSource};
+use crate::url::Url;
+use crate::utils::encode_base64;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display};
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-11.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthScheme {
+    Basic,  // https://www.rfc-editor.org/rfc/rfc7617
+    Digest, // https://www.rfc-editor.org/rfc/rfc7616
+}
+
+// https://www.rfc-editor.org/rfc/rfc7616#section-3.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn hash(&self, input: &str) -> String {
+        match self {
+            DigestAlgorithm::Md5 => to_hex(&md5(input.as_bytes())),
+            DigestAlgorithm::Sha256 => to_hex(&sha256(input.as_bytes())),
+        }
+    }
+}
+
+impl Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DigestAlgorithm::Md5 => write!(f, "MD5"),
+            DigestAlgorithm::Sha256 => write!(f, "SHA-256"),
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-11.3
+// WWW-Authenticateで送られてくる、サーバーが受け付ける認証方式とそのパラメーター
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    scheme: AuthScheme,
+    params: Vec<(String, String)>, // パラメーターの名前は小文字にする
+}
+
+impl Challenge {
+    pub fn scheme(&self) -> AuthScheme {
+        self.scheme
+    }
+
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-11.5
+    // 保護空間を区別する名前
+    pub fn realm(&self) -> String {
+        self.param("realm").unwrap_or_default()
+    }
+
+    // Digest認証のハッシュ関数。指定がない場合はMD5を使う。対応していない場合はNoneを返す
+    pub fn algorithm(&self) -> Option<DigestAlgorithm> {
+        match self.param("algorithm") {
+            None => Some(DigestAlgorithm::Md5),
+            Some(a) if a.eq_ignore_ascii_case("MD5") => Some(DigestAlgorithm::Md5),
+            Some(a) if a.eq_ignore_ascii_case("SHA-256") => Some(DigestAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc7616#section-3.3
+    // 前回のnonceが期限切れになっただけで、認証情報自体は正しかった
+    pub fn is_stale(&self) -> bool {
+        self.param("stale")
+            .is_some_and(|s| s.eq_ignore_ascii_case("true"))
+    }
+
+    // qop=auth-intやuserhashには対応していない
+    fn is_supported(&self) -> bool {
+        match self.scheme {
+            AuthScheme::Basic => true,
+            AuthScheme::Digest => {
+                self.algorithm().is_some() && self.param("nonce").is_some() && self.qop().is_some()
+            }
+        }
+    }
+
+    // qopの指定がない場合はSome(None)、authを含む場合はSome(Some("auth"))、含まない場合はNone
+    fn qop(&self) -> Option<Option<&'static str>> {
+        match self.param("qop") {
+            None => Some(None),
+            Some(qop)
+                if qop
+                    .split(',')
+                    .any(|q| q.trim().eq_ignore_ascii_case("auth")) =>
+            {
+                Some(Some("auth"))
+            }
+            Some(_) => None,
+        }
+    }
+
+    // 対応している方式のうち、最も安全なものを選ぶ
+    fn strength(&self) -> u8 {
+        match (self.scheme, self.algorithm()) {
+            (AuthScheme::Digest, Some(DigestAlgorithm::Sha256)) => 3,
+            (AuthScheme::Digest, _) => 2,
+            (AuthScheme::Basic, _) => 1,
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-11.6.1
+// WWW-Authenticate = #challenge
+// challenge = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+// 1つの値に複数のチャレンジが含まれることがある。対応していない方式は無視する
+pub fn parse_challenges(value: &str) -> Vec<Challenge> {
+    let mut parser = ChallengeParser {
+        input: value.chars().collect(),
+        pos: 0,
+    };
+    let mut challenges = Vec::new();
+    loop {
+        parser.skip(|c| c == ' ' || c == '\t' || c == ',');
+        if parser.pos >= parser.input.len() {
+            break;
+        }
+        let scheme = parser.token();
+        if scheme.is_empty() {
+            parser.pos += 1;
+            continue;
+        }
+
+        let mut params = Vec::new();
+        loop {
+            let start = parser.pos;
+            parser.skip(|c| c == ' ' || c == '\t' || c == ',');
+            let name = parser.token();
+            parser.skip(|c| c == ' ' || c == '\t');
+            // = が続かない場合は次のチャレンジの方式名
+            if name.is_empty() || parser.peek() != Some('=') {
+                parser.pos = start;
+                break;
+            }
+            parser.pos += 1;
+            parser.skip(|c| c == ' ' || c == '\t');
+            let value = if parser.peek() == Some('"') {
+                parser.quoted_string()
+            } else {
+                parser.token()
+            };
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        let scheme = match scheme.to_ascii_lowercase().as_str() {
+            "basic" => AuthScheme::Basic,
+            "digest" => AuthScheme::Digest,
+            _ => continue,
+        };
+        challenges.push(Challenge { scheme, params });
+    }
+    challenges
+}
+
+// 複数のWWW-Authenticateヘッダーから、対応している最も安全なチャレンジを選ぶ
+pub fn select_challenge(values: &[String]) -> Option<Challenge> {
+    let mut best: Option<Challenge> = None;
+    for challenge in values.iter().flat_map(|v| parse_challenges(v)) {
+        if !challenge.is_supported()
+            || best
+                .as_ref()
+                .is_some_and(|b| b.strength() >= challenge.strength())
+        {
+            continue;
+        }
+        best = Some(challenge);
+    }
+    best
+}
+
+struct ChallengeParser {
+    input: Vec<char>,
+    pos: usize,
+}
+
+impl ChallengeParser {
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-5.6.2
+    fn token(&mut self) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~/".contains(c)) {
+                break;
+            }
+            token.push(c);
+            self.pos += 1;
+        }
+        token
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-5.6.4
+    fn quoted_string(&mut self) -> String {
+        let mut value = String::new();
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.peek() {
+                        value.push(escaped);
+                        self.pos += 1;
+                    }
+                }
+                _ => value.push(c),
+            }
+        }
+        value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: String::from(username),
+            password: String::from(password),
+        }
+    }
+
+    pub fn username(&self) -> String {
+        self.username.clone()
+    }
+
+    pub fn password(&self) -> String {
+        self.password.clone()
+    }
+}
+
+// 401のレスポンスを受け取ったときに、ユーザー名とパスワードを用意する
+// UIがダイアログを表示したり、テストが固定の値を返したりする
+pub trait CredentialsProvider: Debug {
+    // Noneを返した場合は、401のレスポンスをそのまま返す
+    fn credentials(&self, url: &Url, challenge: &Challenge) -> Option<Credentials>;
+}
+
+// https://www.rfc-editor.org/rfc/rfc7617#section-2
+pub fn basic_authorization(credentials: &Credentials) -> String {
+    let user_pass = format!("{}:{}", credentials.username, credentials.password);
+    format!("Basic {}", encode_base64(user_pass.as_bytes()))
+}
+
+// https://www.rfc-editor.org/rfc/rfc7616#section-3.4
+// Authorizationヘッダーの値を作る。nonce_countとcnonceはqop=authのときだけ使う
+pub fn digest_authorization(
+    request: &HttpRequest,
+    challenge: &Challenge,
+    credentials: &Credentials,
+    nonce_count: u32,
+    cnonce: &str,
+) -> String {
+    let algorithm = challenge.algorithm().unwrap_or(DigestAlgorithm::Md5);
+    let realm = challenge.realm();
+    let nonce = challenge.param("nonce").unwrap_or_default();
+    let uri = request.target();
+
+    let ha1 = algorithm.hash(&format!(
+        "{}:{}:{}",
+        credentials.username, realm, credentials.password
+    ));
+    let ha2 = algorithm.hash(&format!("{}:{}", request.method(), uri));
+    let qop = challenge.qop().flatten();
+    let nc = format!("{:08x}", nonce_count);
+    let response = match qop {
+        Some(qop) => algorithm.hash(&format!(
+            "{}:{}:{}:{}:{}:{}",
+            ha1, nonce, nc, cnonce, qop, ha2
+        )),
+        None => algorithm.hash(&format!("{}:{}:{}", ha1, nonce, ha2)),
+    };
+
+    let mut value = format!(
+        "Digest username=\"{}\", realm=\"{}\", uri=\"{}\", algorithm={}, nonce=\"{}\"",
+        quote(&credentials.username),
+        quote(&realm),
+        quote(&uri),
+        algorithm,
+        quote(&nonce)
+    );
+    if let Some(qop) = qop {
+        value.push_str(&format!(", nc={}, cnonce=\"{}\", qop={}", nc, cnonce, qop));
+    }
+    value.push_str(&format!(", response=\"{}\"", response));
+    if let Some(opaque) = challenge.param("opaque") {
+        value.push_str(&format!(", opaque=\"{}\"", quote(&opaque)));
+    }
+    value
+}
+
+// quoted-stringの中の " と \ をエスケープする
+fn quote(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[derive(Debug, Clone)]
+struct AuthEntry {
+    origin: String,
+    directory: String, // 認証に成功したURLのディレクトリ。この下のURLには先にAuthorizationを送る
+    challenge: Challenge,
+    credentials: Credentials,
+    nonce_count: u32, // 同じnonceを使ったリクエストの数
+}
+
+impl AuthEntry {
+    fn realm(&self) -> String {
+        self.challenge.realm()
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-11.5
+// 認証に成功した認証情報を、保護空間（オリジンとrealm）ごとに保存する
+#[derive(Debug)]
+pub struct AuthCache {
+    entries: Vec<AuthEntry>,
+    clock: Rc<dyn Clock>,
+    random: Rc<dyn RandomSource>, // cnonceに混ぜる乱数
+    cnonce_count: u64,
+}
+
+impl AuthCache {
+    pub fn new(clock: Rc<dyn Clock>) -> Self {
+        Self {
+            entries: Vec::new(),
+            clock,
+            random: Rc::new(NoRandom::new()),
+            cnonce_count: 0,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn set_random(&mut self, random: Rc<dyn RandomSource>) {
+        self.random = random;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // URLのオリジンとrealmで決まる保護空間の認証情報
+    pub fn get(&self, url: &Url, realm: &str) -> Option<Credentials> {
+        let origin = origin_key(url);
+        self.entries
+            .iter()
+            .find(|e| e.origin == origin && e.realm() == realm)
+            .map(|e| e.credentials.clone())
+    }
+
+    pub fn remove(&mut self, url: &Url, realm: &str) {
+        let origin = origin_key(url);
+        self.entries
+            .retain(|e| !(e.origin == origin && e.realm() == realm));
+    }
+
+    // 認証に成功した認証情報を保存する。同じ保護空間の認証情報は置き換える
+    pub fn store(&mut self, url: &Url, challenge: &Challenge, credentials: &Credentials) {
+        let origin = origin_key(url);
+        let realm = challenge.realm();
+        // 直前のauthorizeで数えたnonceの使用回数を引き継ぐ
+        let nonce_count = self
+            .entries
+            .iter()
+            .find(|e| e.origin == origin && e.realm() == realm)
+            .filter(|e| e.challenge.param("nonce") == challenge.param("nonce"))
+            .map_or(1, |e| e.nonce_count);
+        self.remove(url, &realm);
+
+        let path = url.path();
+        let directory = match path.rfind('/') {
+            Some(i) => String::from(&path[..=i]),
+            None => String::from("/"),
+        };
+        self.entries.push(AuthEntry {
+            origin,
+            directory,
+            challenge: challenge.clone(),
+            credentials: credentials.clone(),
+            nonce_count,
+        });
+    }
+
+    // チャレンジに答えるAuthorizationヘッダーの値を作る
+    pub fn authorize(
+        &mut self,
+        request: &HttpRequest,
+        challenge: &Challenge,
+        credentials: &Credentials,
+    ) -> String {
+        if challenge.scheme() == AuthScheme::Basic {
+            return basic_authorization(credentials);
+        }
+
+        // https://www.rfc-editor.org/rfc/rfc7616#section-3.4
+        // 同じnonceを使うたびにncを1つ増やす
+        let origin = origin_key(&request.url());
+        let realm = challenge.realm();
+        let nonce = challenge.param("nonce");
+        let nonce_count = match self.entries.iter_mut().find(|e| {
+            e.origin == origin && e.realm() == realm && e.challenge.param("nonce") == nonce
+        }) {
+            Some(entry) => {
+                entry.nonce_count += 1;
+                entry.nonce_count
+            }
+            None => 1,
+        };
+        let cnonce = self.cnonce(&nonce.unwrap_or_default());
+        digest_authorization(request, challenge, credentials, nonce_count, &cnonce)
+    }
+
+    // 以前に認証に成功したディレクトリの下へのリクエストには、401を待たずにAuthorizationを付ける
+    // 使った保護空間のrealmとヘッダーの値を返す
+    pub fn preemptive(&mut self, request: &HttpRequest) -> Option<(String, String)> {
+        let url = request.url();
+        let origin = origin_key(&url);
+        let path = url.path();
+        let entry = self
+            .entries
+            .iter()
+            .filter(|e| e.origin == origin && path.starts_with(&e.directory))
+            .max_by_key(|e| e.directory.len())?
+            .clone();
+        let authorization = self.authorize(request, &entry.challenge, &entry.credentials);
+        Some((entry.realm(), authorization))
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc7616#section-3.4
+    // cnonceはサーバーが予測できない値であるべきなので、set_randomで設定した乱数を混ぜる
+    // 乱数を設定していない場合はnonceと時刻と回数だけから作るので、サーバーが予測できてしまう
+    fn cnonce(&mut self, nonce: &str) -> String {
+        self.cnonce_count += 1;
+        let mut entropy = [0u8; 16];
+        if self.random.is_available() {
+            self.random.fill(&mut entropy);
+        }
+        let mut seed =
+            format!("{}:{}:{}:", nonce, self.clock.now(), self.cnonce_count).into_bytes();
+        seed.extend_from_slice(&entropy);
+        String::from(&to_hex(&sha256(&seed))[..32])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::http::request::Method;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).expect("failed to parse url")
+    }
+
+    #[test]
+    fn test_parse_challenges() {
+        let challenges = parse_challenges(
+            "Newauth realm=\"apps\", type=1, title=\"Login to \\\"apps\\\"\", Basic realm=\"simple, with comma\"",
+        );
+        assert_eq!(1, challenges.len());
+        assert_eq!(AuthScheme::Basic, challenges[0].scheme());
+        assert_eq!("simple, with comma", challenges[0].realm());
+
+        let challenges = parse_challenges(
+            "Digest realm=\"test\", qop=\"auth, auth-int\", algorithm=SHA-256, nonce=\"abc\", stale=TRUE, Basic realm=\"test\"",
+        );
+        assert_eq!(2, challenges.len());
+        assert_eq!(Some(DigestAlgorithm::Sha256), challenges[0].algorithm());
+        assert_eq!(Some("abc".to_string()), challenges[0].param("Nonce"));
+        assert!(challenges[0].is_stale());
+        assert_eq!(AuthScheme::Basic, challenges[1].scheme());
+    }
+
+    #[test]
+    fn test_select_challenge() {
+        let values = vec![
+            "Basic realm=\"a\"".to_string(),
+            "Digest realm=\"a\", nonce=\"n\", algorithm=MD5".to_string(),
+            "Digest realm=\"a\", nonce=\"n\", algorithm=SHA-256, qop=\"auth\"".to_string(),
+            "Digest realm=\"a\", nonce=\"n\", algorithm=SHA-512-256".to_string(),
+        ];
+        let challenge = select_challenge(&values).expect("no challenge");
+        assert_eq!(Some(DigestAlgorithm::Sha256), challenge.algorithm());
+
+        // qop=auth-intだけの場合は対応していない
+        let values = vec!["Digest realm=\"a\", nonce=\"n\", qop=\"auth-int\"".to_string()];
+        assert_eq!(None, select_challenge(&values));
+    }
+
+    #[test]
+    fn test_digest_authorization() {
+        // https://www.rfc-editor.org/rfc/rfc7616#section-3.9.1
+        let request = HttpRequest::new(Method::Get, url("http://www.example.org/dir/index.html"));
+        let credentials = Credentials::new("Mufasa", "Circle of Life");
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        let challenge = |algorithm: &str| {
+            parse_challenges(&format!(
+                "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm={}, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"",
+                algorithm
+            ))
+            .remove(0)
+        };
+
+        let value = digest_authorization(&request, &challenge("MD5"), &credentials, 1, cnonce);
+        assert!(value.starts_with("Digest username=\"Mufasa\", realm=\"http-auth@example.org\", uri=\"/dir/index.html\", algorithm=MD5,"));
+        assert!(value.contains(
+            ", nc=00000001, cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", qop=auth,"
+        ));
+        assert!(value.contains("response=\"8ca523f5e9506fed4657c9700eebdbec\""));
+        assert!(value.ends_with("opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""));
+
+        let value = digest_authorization(&request, &challenge("SHA-256"), &credentials, 1, cnonce);
+        assert!(value.contains(
+            "response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\""
+        ));
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut cache = AuthCache::new(Rc::new(ManualClock::new(0)));
+        let basic = parse_challenges("Basic realm=\"users\"").remove(0);
+        let credentials = Credentials::new("Aladdin", "open sesame");
+        cache.store(&url("http://example.com/docs/a.html"), &basic, &credentials);
+        assert_eq!(
+            Some(credentials.clone()),
+            cache.get(&url("http://example.com/other"), "users")
+        );
+        assert_eq!(None, cache.get(&url("http://example.com/"), "admins"));
+        assert_eq!(None, cache.get(&url("https://example.com/"), "users"));
+
+        // 同じディレクトリの下にだけ、先にAuthorizationを送る
+        let request = HttpRequest::new(Method::Get, url("http://example.com/docs/sub/b.html"));
+        assert_eq!(
+            Some((
+                "users".to_string(),
+                "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".to_string()
+            )),
+            cache.preemptive(&request)
+        );
+        let request = HttpRequest::new(Method::Get, url("http://example.com/images/c.png"));
+        assert_eq!(None, cache.preemptive(&request));
+
+        cache.remove(&url("http://example.com/"), "users");
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_digest_nonce_count() {
+        let mut cache = AuthCache::new(Rc::new(ManualClock::new(0)));
+        let digest = parse_challenges("Digest realm=\"r\", nonce=\"n\", qop=\"auth\"").remove(0);
+        let credentials = Credentials::new("user", "pass");
+        let request = HttpRequest::new(Method::Get, url("http://example.com/a"));
+        assert!(cache
+            .authorize(&request, &digest, &credentials)
+            .contains("nc=00000001"));
+        cache.store(&request.url(), &digest, &credentials);
+
+        let (_, first) = cache.preemptive(&request).expect("no authorization");
+        let (_, second) = cache.preemptive(&request).expect("no authorization");
+        assert!(first.contains("nc=00000002"));
+        assert!(second.contains("nc=00000003"));
+        // cnonceは毎回変わる
+        let cnonce = |v: &str| v.split("cnonce=").nth(1).map(|s| s[..34].to_string());
+        assert_ne!(cnonce(&first), cnonce(&second));
+    }
+
+    // 呼び出すたびに違うバイト列を返す乱数
+    #[derive(Debug, Default)]
+    struct CountingRandom {
+        count: core::cell::Cell<u8>,
+    }
+
+    impl RandomSource for CountingRandom {
+        fn fill(&self, buf: &mut [u8]) {
+            self.count.set(self.count.get() + 1);
+            buf.fill(self.count.get());
+        }
+    }
+
+    #[test]
+    fn test_cnonce_random() {
+        let digest = parse_challenges("Digest realm=\"r\", nonce=\"n\", qop=\"auth\"").remove(0);
+        let credentials = Credentials::new("user", "pass");
+        let request = HttpRequest::new(Method::Get, url("http://example.com/a"));
+        let cnonce = |cache: &mut AuthCache| {
+            let value = cache.authorize(&request, &digest, &credentials);
+            value.split("cnonce=").nth(1).map(|s| s[..34].to_string())
+        };
+
+        // 乱数がない場合は、nonceと時刻と回数が同じなら同じcnonceになってしまう
+        let clock = Rc::new(ManualClock::new(1_700_000_000));
+        let mut first = AuthCache::new(clock.clone());
+        let mut second = AuthCache::new(clock.clone());
+        assert_eq!(cnonce(&mut first), cnonce(&mut second));
+
+        // 乱数を設定すると、同じ条件でも違うcnonceになる
+        let random = Rc::new(CountingRandom::default());
+        let mut first = AuthCache::new(clock.clone());
+        first.set_random(random.clone());
+        let mut second = AuthCache::new(clock);
+        second.set_random(random);
+        assert_ne!(cnonce(&mut first), cnonce(&mut second));
+    }
+}
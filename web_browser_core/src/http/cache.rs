@@ -0,0 +1,565 @@
+use crate::clock::Clock;
+use crate::http::cookie::parse_cookie_date;
+use crate::http::header::HeaderMap;
+use crate::http::request::{HttpRequest, Method};
+use crate::http::HttpResponse;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// キャッシュに保存するレスポンスの数の上限のデフォルト値
+pub const DEFAULT_MAX_ENTRIES: usize = 100;
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-15.1
+// 明示的な有効期限がなくても、Last-Modifiedから有効期限を推測してよいステータスコード
+const HEURISTICALLY_CACHEABLE: [u32; 11] = [200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+// https://www.rfc-editor.org/rfc/rfc9111#section-5.2
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let mut cache_control = Self::default();
+        for value in headers.get_all("Cache-Control") {
+            for directive in value.split(',') {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => (name.trim(), argument.trim().trim_matches('"')),
+                    None => (directive.trim(), ""),
+                };
+                match name.to_ascii_lowercase().as_str() {
+                    "no-store" => cache_control.no_store = true,
+                    "no-cache" => cache_control.no_cache = true,
+                    // 不正な値の場合は期限切れとして扱う
+                    "max-age" => cache_control.max_age = Some(argument.parse().unwrap_or(0)),
+                    _ => {}
+                }
+            }
+        }
+        // https://www.rfc-editor.org/rfc/rfc9111#section-5.4
+        if headers
+            .get_all("Pragma")
+            .iter()
+            .any(|v| v.trim().eq_ignore_ascii_case("no-cache"))
+        {
+            cache_control.no_cache = true;
+        }
+        cache_control
+    }
+}
+
+// キャッシュを調べた結果
+#[derive(Debug, Clone)]
+pub enum CacheLookup {
+    // 新鮮なレスポンスがあるので、ネットワークにリクエストを送らなくてよい
+    Fresh(HttpResponse),
+    // 古くなったレスポンスがある。これらのヘッダーを付けてサーバーに検証してもらう
+    Stale(HeaderMap),
+    Miss,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    key: String,
+    vary: Vec<(String, Option<String>)>, // Varyで指定されたリクエストヘッダーとその値
+    response: HttpResponse,
+    response_time: u64,
+    last_used: u64, // 上限を超えたときに、最も長く使われていないものから削除する
+}
+
+impl CacheEntry {
+    // https://www.rfc-editor.org/rfc/rfc9111#section-4.2.1
+    fn freshness_lifetime(&self) -> u64 {
+        let headers = &self.response.headers;
+        if let Some(max_age) = CacheControl::from_headers(headers).max_age {
+            return max_age;
+        }
+        let date = self.date();
+        if let Some(expires) = headers.get("Expires") {
+            // 不正な日付（0など）は期限切れを表す
+            return match parse_cookie_date(&expires) {
+                Some(expires) => expires.saturating_sub(date),
+                None => 0,
+            };
+        }
+        // https://www.rfc-editor.org/rfc/rfc9111#section-4.2.2
+        // Last-Modifiedからの経過時間の10%を有効期限とする
+        if HEURISTICALLY_CACHEABLE.contains(&self.response.status_code) {
+            if let Some(last_modified) = headers
+                .get("Last-Modified")
+                .and_then(|v| parse_cookie_date(&v))
+            {
+                return date.saturating_sub(last_modified) / 10;
+            }
+        }
+        0
+    }
+
+    // Dateヘッダーがない場合はレスポンスを受け取った時刻を使う
+    fn date(&self) -> u64 {
+        self.response
+            .headers
+            .get("Date")
+            .and_then(|v| parse_cookie_date(&v))
+            .unwrap_or(self.response_time)
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9111#section-4.2.3
+    fn current_age(&self, now: u64) -> u64 {
+        let age_value = self
+            .response
+            .headers
+            .get("Age")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        // リクエストを送ってからレスポンスを受け取るまでの時間は無視する
+        let apparent_age = self.response_time.saturating_sub(self.date());
+        let corrected_initial_age = apparent_age.max(age_value);
+        corrected_initial_age.saturating_add(now.saturating_sub(self.response_time))
+    }
+
+    fn is_fresh(&self, now: u64) -> bool {
+        self.freshness_lifetime() > self.current_age(now)
+    }
+
+    fn matches(&self, key: &str, request: &HttpRequest) -> bool {
+        self.key == key
+            && self
+                .vary
+                .iter()
+                .all(|(name, value)| request.headers().get(name) == *value)
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-13.1
+    fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = self.response.headers.get("ETag") {
+            headers.insert("If-None-Match", &etag);
+        }
+        if let Some(last_modified) = self.response.headers.get("Last-Modified") {
+            headers.insert("If-Modified-Since", &last_modified);
+        }
+        headers
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9111
+// メモリ上に持つ、1人のユーザーのためのHTTPキャッシュ。GETのレスポンスだけを保存する
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    entries: Vec<CacheEntry>,
+    clock: Rc<dyn Clock>,
+    max_entries: usize,
+    hits: usize,
+    misses: usize,
+}
+
+impl HttpCache {
+    pub fn new(clock: Rc<dyn Clock>) -> Self {
+        Self {
+            entries: Vec::new(),
+            clock,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+        self.evict();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // キャッシュから返したレスポンスの数。304で検証できた場合も含む
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    // ネットワークから受け取ったレスポンスを使った数
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9111#section-4
+    // リクエストを送る前に呼び出す
+    pub fn lookup(&mut self, request: &HttpRequest) -> CacheLookup {
+        let now = self.clock.now();
+        if request.method() != Method::Get {
+            return CacheLookup::Miss;
+        }
+        let request_cache_control = CacheControl::from_headers(&request.headers());
+        if request_cache_control.no_store {
+            return CacheLookup::Miss;
+        }
+
+        let key = cache_key(request);
+        let entry = match self.entries.iter_mut().find(|e| e.matches(&key, request)) {
+            Some(entry) => entry,
+            None => return CacheLookup::Miss,
+        };
+        entry.last_used = now;
+
+        let response_cache_control = CacheControl::from_headers(&entry.response.headers);
+        // 時刻がわからない場合は新鮮かどうか判断できないので、毎回検証する
+        let must_validate = request_cache_control.no_cache
+            || request_cache_control.max_age == Some(0)
+            || response_cache_control.no_cache
+            || !self.clock.is_available();
+        if !must_validate && entry.is_fresh(now) {
+            self.hits += 1;
+            return CacheLookup::Fresh(entry.response.clone());
+        }
+
+        let headers = entry.conditional_headers();
+        if headers.is_empty() {
+            CacheLookup::Miss
+        } else {
+            CacheLookup::Stale(headers)
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9111#section-3
+    // ネットワークから受け取ったレスポンスを保存し、呼び出し元が使うレスポンスを返す
+    // 304の場合は保存しているレスポンスを更新して返す
+    pub fn store(&mut self, request: &HttpRequest, response: HttpResponse) -> HttpResponse {
+        let now = self.clock.now();
+        let key = cache_key(request);
+
+        // https://www.rfc-editor.org/rfc/rfc9111#section-4.4
+        // 安全でないメソッドが成功した場合は、そのURLのキャッシュを無効にする
+        if !request.method().is_safe() {
+            if (200..400).contains(&response.status_code) {
+                self.entries.retain(|e| e.key != key);
+            }
+            self.misses += 1;
+            return response;
+        }
+        if request.method() != Method::Get {
+            self.misses += 1;
+            return response;
+        }
+
+        // https://www.rfc-editor.org/rfc/rfc9111#section-4.3.4
+        if response.status_code == 304 {
+            if let Some(entry) = self.entries.iter_mut().find(|e| e.matches(&key, request)) {
+                // ボディに関するヘッダーは保存しているレスポンスのものを使う
+                let updated: Vec<_> = response
+                    .headers
+                    .iter()
+                    .filter(|h| {
+                        !h.name().eq_ignore_ascii_case("content-length")
+                            && !h.name().eq_ignore_ascii_case("transfer-encoding")
+                    })
+                    .collect();
+                for header in &updated {
+                    entry.response.headers.remove(&header.name());
+                }
+                for header in &updated {
+                    entry
+                        .response
+                        .headers
+                        .append(&header.name(), &header.value());
+                }
+                entry.response_time = now;
+                entry.last_used = now;
+                self.hits += 1;
+                return entry.response.clone();
+            }
+            self.misses += 1;
+            return response;
+        }
+
+        self.misses += 1;
+        let request_cache_control = CacheControl::from_headers(&request.headers());
+        let response_cache_control = CacheControl::from_headers(&response.headers);
+        let vary = match vary_headers(request, &response.headers) {
+            Some(vary) => vary,
+            None => return response,
+        };
+        self.entries.retain(|e| !e.matches(&key, request));
+        if request_cache_control.no_store || response_cache_control.no_store {
+            return response;
+        }
+
+        let entry = CacheEntry {
+            key,
+            vary,
+            response: response.clone(),
+            response_time: now,
+            last_used: now,
+        };
+        // 有効期限も検証のためのヘッダーもない場合は、保存しても使えない
+        let validators = !entry.conditional_headers().is_empty();
+        if entry.freshness_lifetime() == 0 && !validators {
+            return response;
+        }
+        if !HEURISTICALLY_CACHEABLE.contains(&response.status_code)
+            && response_cache_control.max_age.is_none()
+            && !response.headers.contains("Expires")
+        {
+            return response;
+        }
+        self.entries.push(entry);
+        self.evict();
+        response
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(i, _)| i);
+            match oldest {
+                Some(i) => self.entries.remove(i),
+                None => break,
+            };
+        }
+    }
+}
+
+// フラグメントを除いたURLをキーにする
+fn cache_key(request: &HttpRequest) -> String {
+    request.url().serialize(true)
+}
+
+// https://www.rfc-editor.org/rfc/rfc9111#section-4.1
+// Vary: * の場合は保存できないのでNoneを返す
+fn vary_headers(
+    request: &HttpRequest,
+    response_headers: &HeaderMap,
+) -> Option<Vec<(String, Option<String>)>> {
+    let mut vary = Vec::new();
+    for value in response_headers.get_all("Vary") {
+        for name in value.split(',') {
+            let name = name.trim();
+            if name == "*" {
+                return None;
+            }
+            if !name.is_empty() {
+                vary.push((String::from(name), request.headers().get(name)));
+            }
+        }
+    }
+    Some(vary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ManualClock, NoClock};
+    use crate::url::Url;
+
+    fn request(url: &str) -> HttpRequest {
+        HttpRequest::new(Method::Get, Url::parse(url).expect("failed to parse url"))
+    }
+
+    fn response(raw: &str) -> HttpResponse {
+        HttpResponse::new(raw.as_bytes()).expect("failed to parse http response")
+    }
+
+    fn cache() -> (Rc<ManualClock>, HttpCache) {
+        // 2015-10-21T07:28:00Z
+        let clock = Rc::new(ManualClock::new(1445412480));
+        let cache = HttpCache::new(clock.clone());
+        (clock, cache)
+    }
+
+    #[test]
+    fn test_max_age() {
+        let (clock, mut cache) = cache();
+        let req = request("http://example.com/style.css#top");
+        assert!(matches!(cache.lookup(&req), CacheLookup::Miss));
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nContent-Length: 2\r\n\r\nok"),
+        );
+
+        clock.advance(59);
+        match cache.lookup(&request("http://example.com/style.css")) {
+            CacheLookup::Fresh(response) => assert_eq!("ok", response.body_text()),
+            lookup => panic!("unexpected lookup result: {:?}", lookup),
+        }
+        clock.advance(1);
+        assert!(matches!(cache.lookup(&req), CacheLookup::Miss));
+        assert_eq!(1, cache.hits());
+        assert_eq!(1, cache.misses());
+    }
+
+    #[test]
+    fn test_expires() {
+        let (clock, mut cache) = cache();
+        let req = request("http://example.com/");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nDate: Wed, 21 Oct 2015 07:28:00 GMT\r\nExpires: Wed, 21 Oct 2015 07:29:00 GMT\r\nContent-Length: 0\r\n\r\n"),
+        );
+        assert!(matches!(cache.lookup(&req), CacheLookup::Fresh(_)));
+        clock.advance(60);
+        assert!(matches!(cache.lookup(&req), CacheLookup::Miss));
+
+        // 不正なExpiresは期限切れとして扱う
+        let req = request("http://example.com/expired");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nExpires: 0\r\nContent-Length: 0\r\n\r\n"),
+        );
+        assert!(matches!(cache.lookup(&req), CacheLookup::Miss));
+    }
+
+    #[test]
+    fn test_no_store() {
+        let (_, mut cache) = cache();
+        let req = request("http://example.com/");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: max-age=60, no-store\r\nContent-Length: 0\r\n\r\n"),
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_revalidate_with_etag() {
+        let (clock, mut cache) = cache();
+        let req = request("http://example.com/");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: max-age=10\r\nETag: \"v1\"\r\nContent-Length: 4\r\n\r\nbody"),
+        );
+        clock.advance(10);
+        let headers = match cache.lookup(&req) {
+            CacheLookup::Stale(headers) => headers,
+            lookup => panic!("unexpected lookup result: {:?}", lookup),
+        };
+        assert_eq!(Some("\"v1\"".into()), headers.get("If-None-Match"));
+
+        // 304は保存しているレスポンスを新しいヘッダーで更新して返す
+        let res = cache.store(
+            &req,
+            response(
+                "HTTP/1.1 304 Not Modified\r\nCache-Control: max-age=20\r\nETag: \"v1\"\r\n\r\n",
+            ),
+        );
+        assert_eq!(200, res.status_code());
+        assert_eq!("body", res.body_text());
+        assert_eq!(
+            Some("max-age=20".into()),
+            res.headers().get("Cache-Control")
+        );
+        assert_eq!(1, cache.hits());
+
+        clock.advance(19);
+        assert!(matches!(cache.lookup(&req), CacheLookup::Fresh(_)));
+    }
+
+    #[test]
+    fn test_no_cache() {
+        let (_, mut cache) = cache();
+        let req = request("http://example.com/");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: no-cache, max-age=60\r\nLast-Modified: Tue, 20 Oct 2015 07:28:00 GMT\r\nContent-Length: 0\r\n\r\n"),
+        );
+        match cache.lookup(&req) {
+            CacheLookup::Stale(headers) => assert_eq!(
+                Some("Tue, 20 Oct 2015 07:28:00 GMT".into()),
+                headers.get("If-Modified-Since")
+            ),
+            lookup => panic!("unexpected lookup result: {:?}", lookup),
+        }
+    }
+
+    #[test]
+    fn test_no_clock() {
+        let mut cache = HttpCache::new(Rc::new(NoClock::new()));
+        let req = request("http://example.com/");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: max-age=3600\r\nETag: \"v1\"\r\nContent-Length: 0\r\n\r\n"),
+        );
+        // 時刻がわからない場合は、有効期限内かどうかにかかわらず検証する
+        match cache.lookup(&req) {
+            CacheLookup::Stale(headers) => {
+                assert_eq!(Some("\"v1\"".into()), headers.get("If-None-Match"))
+            }
+            lookup => panic!("unexpected lookup result: {:?}", lookup),
+        }
+
+        let req = request("http://example.com/style.css");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: max-age=3600\r\nContent-Length: 0\r\n\r\n"),
+        );
+        assert!(matches!(cache.lookup(&req), CacheLookup::Miss));
+    }
+
+    #[test]
+    fn test_vary_and_invalidation() {
+        let (_, mut cache) = cache();
+        let mut req = request("http://example.com/");
+        req.headers_mut().insert("Accept-Language", "ja");
+        cache.store(
+            &req,
+            response("HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nVary: Accept-Language\r\nContent-Length: 0\r\n\r\n"),
+        );
+        assert!(matches!(cache.lookup(&req), CacheLookup::Fresh(_)));
+        assert!(matches!(
+            cache.lookup(&request("http://example.com/")),
+            CacheLookup::Miss
+        ));
+
+        // POSTが成功すると同じURLのキャッシュは無効になる
+        let post = HttpRequest::new(
+            Method::Post,
+            Url::parse("http://example.com/").expect("failed to parse url"),
+        );
+        cache.store(
+            &post,
+            response("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"),
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let (clock, mut cache) = cache();
+        cache.set_max_entries(2);
+        for path in ["a", "b", "c"] {
+            let req = request(&alloc::format!("http://example.com/{}", path));
+            cache.store(
+                &req,
+                response(
+                    "HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nContent-Length: 0\r\n\r\n",
+                ),
+            );
+            clock.advance(1);
+        }
+        assert_eq!(2, cache.len());
+        assert!(matches!(
+            cache.lookup(&request("http://example.com/a")),
+            CacheLookup::Miss
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::alloc::string::ToString;
+use crate::http::compression::inflate::Inflater;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc1952#section-2.3.1
+const FTEXT: u8 = 0x01;
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+// https://www.rfc-editor.org/rfc/rfc1952
+// gzip形式のデータを少しずつ受け取って展開する
+#[derive(Debug, Clone)]
+pub struct GzipDecoder {
+    header: Option<Vec<u8>>, // ヘッダーを読み終わるまで受け取ったバイト列を保存しておく
+    inflater: Inflater,
+    trailer: Vec<u8>, // CRC32と元のデータのサイズ
+    crc: u32,
+    size: u32,
+}
+
+impl GzipDecoder {
+    pub fn new() -> Self {
+        Self {
+            header: Some(Vec::new()),
+            inflater: Inflater::new(),
+            trailer: Vec::new(),
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    pub fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), String> {
+        let mut input = input;
+        let header_rest;
+        let remaining;
+        if let Some(header) = &mut self.header {
+            header.extend_from_slice(input);
+            let length = match header_length(header)? {
+                Some(length) => length,
+                None => return Ok(()),
+            };
+            header_rest = header.split_off(length);
+            self.header = None;
+            input = &header_rest;
+        }
+
+        if !self.inflater.is_done() {
+            let start = output.len();
+            self.inflater.feed(input, output)?;
+            self.crc = crc32_update(self.crc, &output[start..]);
+            self.size = self.size.wrapping_add((output.len() - start) as u32);
+            if !self.inflater.is_done() {
+                return Ok(());
+            }
+            remaining = self.inflater.take_remaining();
+            input = &remaining;
+        }
+
+        self.trailer.extend_from_slice(input);
+        if self.trailer.len() >= 8 {
+            let crc = u32::from_le_bytes([
+                self.trailer[0],
+                self.trailer[1],
+                self.trailer[2],
+                self.trailer[3],
+            ]);
+            let size = u32::from_le_bytes([
+                self.trailer[4],
+                self.trailer[5],
+                self.trailer[6],
+                self.trailer[7],
+            ]);
+            if crc != self.crc {
+                return Err("gzip crc mismatch".to_string());
+            }
+            if size != self.size {
+                return Err("gzip size mismatch".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // すべての入力を受け取った後に呼び出す。データが途中で終わっている場合はエラーを返す
+    pub fn finish(&self) -> Result<(), String> {
+        if self.inflater.is_done() && self.trailer.len() >= 8 {
+            return Ok(());
+        }
+        Err("unexpected end of gzip stream".to_string())
+    }
+}
+
+impl Default for GzipDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc1952#section-2.3
+// ヘッダーをすべて受け取っている場合はヘッダーの長さを返す
+fn header_length(header: &[u8]) -> Result<Option<usize>, String> {
+    if header.len() < 10 {
+        return Ok(None);
+    }
+    if header[0] != 0x1f || header[1] != 0x8b {
+        return Err("invalid gzip magic number".to_string());
+    }
+    // CM = 8 はdeflate
+    if header[2] != 8 {
+        return Err("unsupported gzip compression method".to_string());
+    }
+    let flags = header[3];
+    if flags & !(FTEXT | FHCRC | FEXTRA | FNAME | FCOMMENT) != 0 {
+        return Err("reserved gzip flags are set".to_string());
+    }
+
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        if header.len() < pos + 2 {
+            return Ok(None);
+        }
+        pos += 2 + u16::from_le_bytes([header[pos], header[pos + 1]]) as usize;
+    }
+    // ファイル名とコメントは0で終わる文字列
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            match header
+                .get(pos..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0))
+            {
+                Some(end) => pos += end + 1,
+                None => return Ok(None),
+            }
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+
+    if header.len() < pos {
+        return Ok(None);
+    }
+    Ok(Some(pos))
+}
+
+// https://www.rfc-editor.org/rfc/rfc1952#section-8
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    for &b in data {
+        c = CRC32_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // gzip.compress(b"hello hello hello", mtime=0)
+    const GZIP: [u8; 28] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0x57, 0xc8, 0x40, 0x90, 0x00, 0x80, 0x88, 0xf9, 0xe5, 0x11, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0xcbf43926, crc32_update(0, b"123456789"));
+        assert_eq!(0xcbf43926, crc32_update(crc32_update(0, b"1234"), b"56789"));
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut decoder = GzipDecoder::new();
+        let mut output = Vec::new();
+        decoder.feed(&GZIP, &mut output).expect("failed to decode");
+        assert!(decoder.finish().is_ok());
+        assert_eq!(b"hello hello hello".to_vec(), output);
+    }
+
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let mut decoder = GzipDecoder::new();
+        let mut output = Vec::new();
+        for byte in GZIP.iter() {
+            assert!(decoder.finish().is_err());
+            decoder
+                .feed(&[*byte], &mut output)
+                .expect("failed to decode");
+        }
+        assert!(decoder.finish().is_ok());
+        assert_eq!(b"hello hello hello".to_vec(), output);
+    }
+
+    #[test]
+    fn test_header_with_file_name() {
+        // FNAMEに "a.txt" を設定したヘッダー
+        let mut input = alloc::vec![0x1f, 0x8b, 0x08, FNAME, 0, 0, 0, 0, 0, 0x03];
+        input.extend_from_slice(b"a.txt\0");
+        input.extend_from_slice(&GZIP[10..]);
+        let mut decoder = GzipDecoder::new();
+        let mut output = Vec::new();
+        decoder.feed(&input, &mut output).expect("failed to decode");
+        assert_eq!(b"hello hello hello".to_vec(), output);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut decoder = GzipDecoder::new();
+        assert!(decoder.feed(b"not a gzip stream", &mut Vec::new()).is_err());
+
+        let mut input = GZIP.to_vec();
+        input[20] ^= 0xff;
+        let mut decoder = GzipDecoder::new();
+        assert_eq!(
+            Err("gzip crc mismatch".to_string()),
+            decoder.feed(&input, &mut Vec::new())
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use crate::alloc::string::ToString;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc1951
+// DEFLATE形式のデータを少しずつ受け取って展開する
+// 入力が途中で足りなくなった場合は、読み始めた位置まで戻して次の入力を待つ
+
+const MAX_BITS: usize = 15;
+const WINDOW_SIZE: usize = 32 * 1024;
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.5
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.7
+// 符号長の符号長はこの順番で並んでいる
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+// 入力が足りない場合と、データが不正な場合を区別する
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InflateError {
+    NeedInput,
+    Invalid(String),
+}
+
+fn invalid(message: &str) -> InflateError {
+    InflateError::Invalid(message.to_string())
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.2
+// 符号長から作った正規ハフマン符号
+#[derive(Debug, Clone)]
+struct Huffman {
+    counts: [u16; MAX_BITS + 1], // 符号長ごとの符号の数
+    symbols: Vec<u16>,           // 符号の小さい順に並べたシンボル
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, InflateError> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+
+        // 符号が足りすぎている（符号として成り立たない）場合はエラーにする
+        let mut left: i32 = 1;
+        for count in counts.iter().skip(1) {
+            left <<= 1;
+            left -= *count as i32;
+            if left < 0 {
+                return Err(invalid("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = alloc::vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16, InflateError> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..=MAX_BITS {
+            code |= bits.read(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid huffman code"))
+    }
+}
+
+// 下位ビットから順番に読み出す
+struct BitReader<'a> {
+    input: &'a [u8],
+    pos: usize, // 読み出したビット数
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, count: u8) -> Result<u32, InflateError> {
+        let mut value = 0u32;
+        for i in 0..count {
+            let byte = match self.input.get(self.pos / 8) {
+                Some(byte) => *byte,
+                None => return Err(InflateError::NeedInput),
+            };
+            value |= (((byte >> (self.pos % 8)) & 1) as u32) << i;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    // 次のバイト境界まで読み飛ばす
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    BlockHeader,
+    Stored(usize), // 非圧縮ブロックの残りのバイト数
+    Compressed(Huffman, Huffman),
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub struct Inflater {
+    input: Vec<u8>, // まだ処理していない入力
+    bit_pos: usize, // inputの先頭から読み出したビット数
+    state: State,
+    last_block: bool,
+    window: Vec<u8>, // 後方参照のために直近に出力したデータを保存しておく
+}
+
+impl Inflater {
+    pub fn new() -> Self {
+        Self {
+            input: Vec::new(),
+            bit_pos: 0,
+            state: State::BlockHeader,
+            last_block: false,
+            window: Vec::new(),
+        }
+    }
+
+    // 最後のブロックまで展開し終わったかどうか
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    // 展開し終わった後に残っている入力（gzipのトレーラーなど）を取り出す
+    pub fn take_remaining(&mut self) -> Vec<u8> {
+        let start = self.bit_pos.div_ceil(8);
+        let remaining = self.input[start.min(self.input.len())..].to_vec();
+        self.input.clear();
+        self.bit_pos = 0;
+        remaining
+    }
+
+    // inputを展開してoutputに追加する。入力が足りない場合は次に呼ばれたときに続きから展開する
+    pub fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), String> {
+        self.input.extend_from_slice(input);
+
+        let result = loop {
+            if self.is_done() {
+                break Ok(());
+            }
+            let mut bits = BitReader {
+                input: &self.input,
+                pos: self.bit_pos,
+            };
+            match step(
+                &mut self.state,
+                &mut self.last_block,
+                &mut bits,
+                &mut self.window,
+                output,
+            ) {
+                Ok(()) => self.bit_pos = bits.pos,
+                Err(InflateError::NeedInput) => break Ok(()),
+                Err(InflateError::Invalid(message)) => break Err(message),
+            }
+
+            // 後方参照に必要ない出力を捨てる
+            if self.window.len() > WINDOW_SIZE * 2 {
+                let excess = self.window.len() - WINDOW_SIZE;
+                self.window.drain(..excess);
+            }
+        };
+
+        // 使い終わった入力を捨てる
+        if !self.is_done() {
+            let consumed = self.bit_pos / 8;
+            self.input.drain(..consumed);
+            self.bit_pos -= consumed * 8;
+        }
+        result
+    }
+}
+
+impl Default for Inflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 1つのブロックヘッダー、または1つのシンボルを処理する
+// 途中で入力が足りなくなった場合は状態を変えずにNeedInputを返す
+fn step(
+    state: &mut State,
+    last_block: &mut bool,
+    bits: &mut BitReader,
+    window: &mut Vec<u8>,
+    output: &mut Vec<u8>,
+) -> Result<(), InflateError> {
+    match state {
+        // https://www.rfc-editor.org/rfc/rfc1951#section-3.2.3
+        State::BlockHeader => {
+            if *last_block {
+                *state = State::Done;
+                return Ok(());
+            }
+            let is_last = bits.read(1)? == 1;
+            let next = match bits.read(2)? {
+                0 => {
+                    // https://www.rfc-editor.org/rfc/rfc1951#section-3.2.4
+                    bits.align();
+                    let len = bits.read(16)?;
+                    let nlen = bits.read(16)?;
+                    if len != !nlen & 0xffff {
+                        return Err(invalid("stored block length mismatch"));
+                    }
+                    State::Stored(len as usize)
+                }
+                1 => fixed_huffman()?,
+                2 => dynamic_huffman(bits)?,
+                _ => return Err(invalid("invalid block type")),
+            };
+            *last_block = is_last;
+            *state = next;
+        }
+        State::Stored(remaining) => {
+            if *remaining == 0 {
+                *state = State::BlockHeader;
+                return Ok(());
+            }
+            let byte = bits.read(8)? as u8;
+            push_output(byte, window, output);
+            *remaining -= 1;
+        }
+        State::Compressed(literal, distance) => {
+            let symbol = literal.decode(bits)?;
+            match symbol {
+                0..=255 => push_output(symbol as u8, window, output),
+                256 => *state = State::BlockHeader,
+                257..=285 => {
+                    let index = (symbol - 257) as usize;
+                    let length =
+                        LENGTH_BASE[index] as usize + bits.read(LENGTH_EXTRA[index])? as usize;
+                    let dist_symbol = distance.decode(bits)? as usize;
+                    if dist_symbol >= 30 {
+                        return Err(invalid("invalid distance symbol"));
+                    }
+                    let dist = DIST_BASE[dist_symbol] as usize
+                        + bits.read(DIST_EXTRA[dist_symbol])? as usize;
+                    if dist > window.len() {
+                        return Err(invalid("distance too far back"));
+                    }
+                    for _ in 0..length {
+                        let byte = window[window.len() - dist];
+                        push_output(byte, window, output);
+                    }
+                }
+                _ => return Err(invalid("invalid literal/length symbol")),
+            }
+        }
+        State::Done => {}
+    }
+    Ok(())
+}
+
+fn push_output(byte: u8, window: &mut Vec<u8>, output: &mut Vec<u8>) {
+    window.push(byte);
+    output.push(byte);
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.6
+fn fixed_huffman() -> Result<State, InflateError> {
+    let mut lengths = [0u8; 288];
+    for (i, len) in lengths.iter_mut().enumerate() {
+        *len = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    Ok(State::Compressed(
+        Huffman::new(&lengths)?,
+        Huffman::new(&[5u8; 30])?,
+    ))
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.7
+fn dynamic_huffman(bits: &mut BitReader) -> Result<State, InflateError> {
+    let hlit = bits.read(5)? as usize + 257;
+    let hdist = bits.read(5)? as usize + 1;
+    let hclen = bits.read(4)? as usize + 4;
+    if hlit > 286 || hdist > 30 {
+        return Err(invalid("too many length or distance codes"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(hclen) {
+        code_lengths[index] = bits.read(3)? as u8;
+    }
+    let code_length_huffman = Huffman::new(&code_lengths)?;
+
+    let mut lengths: Vec<u8> = Vec::new();
+    while lengths.len() < hlit + hdist {
+        let symbol = code_length_huffman.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => match lengths.last() {
+                Some(&prev) => (prev, 3 + bits.read(2)? as usize),
+                None => return Err(invalid("repeat with no previous length")),
+            },
+            17 => (0, 3 + bits.read(3)? as usize),
+            18 => (0, 11 + bits.read(7)? as usize),
+            _ => return Err(invalid("invalid code length symbol")),
+        };
+        if lengths.len() + repeat > hlit + hdist {
+            return Err(invalid("too many code lengths"));
+        }
+        lengths.resize(lengths.len() + repeat, value);
+    }
+
+    if lengths[256] == 0 {
+        return Err(invalid("missing end-of-block code"));
+    }
+    Ok(State::Compressed(
+        Huffman::new(&lengths[..hlit])?,
+        Huffman::new(&lengths[hlit..])?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    // zlib.compressobj(level, zlib.DEFLATED, -15) で作ったデータ
+    // 非圧縮ブロック（level 0）の "hello"
+    const STORED: [u8; 10] = [0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f];
+    // 固定ハフマン符号の "hello hello hello"
+    const FIXED: [u8; 10] = [0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00];
+    // 動的ハフマン符号の dynamic_text()
+    const DYNAMIC: [u8; 150] = [
+        0x85, 0xd4, 0x4b, 0x0a, 0x02, 0x31, 0x10, 0x45, 0xd1, 0xad, 0xd4, 0x0e, 0x62, 0xd5, 0xf3,
+        0x0b, 0x21, 0x7b, 0x51, 0x8c, 0xb6, 0xd0, 0xda, 0x01, 0x33, 0x71, 0xf7, 0x82, 0x82, 0x33,
+        0x6f, 0xc6, 0x77, 0x76, 0xa8, 0x7a, 0x79, 0xea, 0xf7, 0xb9, 0xe4, 0xd3, 0x72, 0x7e, 0x95,
+        0xdc, 0xca, 0x7c, 0x7b, 0x54, 0x5b, 0xd9, 0x72, 0xb1, 0x3e, 0x55, 0xeb, 0xf5, 0xd9, 0xad,
+        0x1d, 0xaf, 0x35, 0xa7, 0xf6, 0xab, 0x8e, 0x35, 0xb0, 0x0a, 0xeb, 0x1a, 0xeb, 0x06, 0xeb,
+        0x16, 0xeb, 0x0e, 0xeb, 0x1e, 0xeb, 0x81, 0x35, 0x06, 0x58, 0xac, 0xe5, 0xcc, 0xe5, 0xec,
+        0xe5, 0x0c, 0xe6, 0x2c, 0xe6, 0x4c, 0xe6, 0x6c, 0xe6, 0x8c, 0xe6, 0xac, 0x16, 0xac, 0x16,
+        0x83, 0x1b, 0x63, 0xb5, 0x60, 0xb5, 0x60, 0xb5, 0x60, 0xb5, 0x60, 0xb5, 0x60, 0xb5, 0x60,
+        0xb5, 0x60, 0x35, 0xb1, 0x9a, 0x58, 0x4d, 0x83, 0xd7, 0x64, 0x35, 0xb1, 0x9a, 0x58, 0x4d,
+        0xac, 0x26, 0x56, 0x13, 0xab, 0xe9, 0x8f, 0x5a, 0xfa, 0x8e, 0x5a, 0xfa, 0x2c, 0xdc, 0x1b,
+    ];
+
+    fn dynamic_text() -> Vec<u8> {
+        let mut text = String::from("<html><body>");
+        for i in 0..40 {
+            text.push_str(&alloc::format!("<p>line {} of the test page</p>", i));
+        }
+        text.push_str("</body></html>");
+        text.into_bytes()
+    }
+
+    fn inflate(input: &[u8]) -> Result<Vec<u8>, String> {
+        let mut inflater = Inflater::new();
+        let mut output = Vec::new();
+        inflater.feed(input, &mut output)?;
+        assert!(inflater.is_done());
+        Ok(output)
+    }
+
+    #[test]
+    fn test_stored_block() {
+        assert_eq!(Ok(b"hello".to_vec()), inflate(&STORED));
+    }
+
+    #[test]
+    fn test_fixed_huffman() {
+        assert_eq!(Ok(b"hello hello hello".to_vec()), inflate(&FIXED));
+    }
+
+    #[test]
+    fn test_dynamic_huffman() {
+        assert_eq!(Ok(dynamic_text()), inflate(&DYNAMIC));
+    }
+
+    #[test]
+    fn test_streaming() {
+        let mut inflater = Inflater::new();
+        let mut output = Vec::new();
+        for byte in DYNAMIC.iter() {
+            inflater
+                .feed(&[*byte], &mut output)
+                .expect("failed to inflate");
+        }
+        assert!(inflater.is_done());
+        assert_eq!(dynamic_text(), output);
+    }
+
+    #[test]
+    fn test_remaining_input() {
+        let mut input = STORED.to_vec();
+        input.extend_from_slice(&[1, 2, 3]);
+        let mut inflater = Inflater::new();
+        let mut output = Vec::new();
+        inflater
+            .feed(&input, &mut output)
+            .expect("failed to inflate");
+        assert!(inflater.is_done());
+        assert_eq!(vec![1, 2, 3], inflater.take_remaining());
+    }
+
+    #[test]
+    fn test_invalid() {
+        // ブロックの種類が3
+        assert!(inflate(&[0x07]).is_err());
+        // 非圧縮ブロックの長さの補数が一致しない
+        assert!(inflate(&[0x01, 0x05, 0x00, 0x00, 0x00]).is_err());
+    }
+}
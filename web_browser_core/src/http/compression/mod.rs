@@ -0,0 +1,161 @@
+pub mod gzip;
+pub mod inflate;
+pub mod zlib;
+
+use crate::http::compression::gzip::GzipDecoder;
+use crate::http::compression::zlib::ZlibDecoder;
+use crate::http::header::HeaderMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// リクエストのAccept-Encodingに設定する、展開できるコンテンツコーディング
+// https://www.rfc-editor.org/rfc/rfc9110#section-12.5.3
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-8.4.1
+#[derive(Debug, Clone)]
+enum Coding {
+    Gzip(GzipDecoder),
+    Deflate(ZlibDecoder),
+}
+
+impl Coding {
+    fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            Coding::Gzip(decoder) => decoder.feed(input, output),
+            Coding::Deflate(decoder) => decoder.feed(input, output),
+        }
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        match self {
+            Coding::Gzip(decoder) => decoder.finish(),
+            Coding::Deflate(decoder) => decoder.finish(),
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-8.4
+// Content-Encodingに従ってボディを少しずつ展開する
+// 複数のコーディングが適用されている場合は、適用された順番と逆の順番で展開する
+#[derive(Debug, Clone)]
+pub struct ContentDecoder {
+    codings: Vec<Coding>, // 展開する順番に並べる
+    received: bool,       // 1バイトでもボディを受け取ったかどうか
+}
+
+impl ContentDecoder {
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, String> {
+        let mut codings = Vec::new();
+        for value in headers.get_all("Content-Encoding") {
+            for coding in value.split(',') {
+                let coding = coding.trim().to_ascii_lowercase();
+                match coding.as_str() {
+                    "" | "identity" => {}
+                    "gzip" | "x-gzip" => codings.push(Coding::Gzip(GzipDecoder::new())),
+                    "deflate" => codings.push(Coding::Deflate(ZlibDecoder::new())),
+                    _ => return Err(format!("unsupported content-coding: {}", coding)),
+                }
+            }
+        }
+        codings.reverse();
+        Ok(Self {
+            codings,
+            received: false,
+        })
+    }
+
+    // コーディングが適用されていない場合はボディをそのまま使えばよい
+    pub fn is_identity(&self) -> bool {
+        self.codings.is_empty()
+    }
+
+    pub fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), String> {
+        if input.is_empty() {
+            return Ok(());
+        }
+        self.received = true;
+
+        let mut data = input.to_vec();
+        let last = self.codings.len().saturating_sub(1);
+        for (i, coding) in self.codings.iter_mut().enumerate() {
+            if i == last {
+                return coding.feed(&data, output);
+            }
+            let mut decoded = Vec::new();
+            coding.feed(&data, &mut decoded)?;
+            data = decoded;
+        }
+        output.extend_from_slice(&data);
+        Ok(())
+    }
+
+    // ボディをすべて受け取った後に呼び出す
+    // 304や HEADのレスポンスのようにボディがない場合は展開しない
+    pub fn finish(&self) -> Result<(), String> {
+        if !self.received {
+            return Ok(());
+        }
+        for coding in &self.codings {
+            coding.finish()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+
+    // gzip.compress(zlib.compress(b"hello hello hello"), mtime=0)
+    const DEFLATE_THEN_GZIP: [u8; 37] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x98, 0x73, 0xda, 0xe3,
+        0xec, 0xc9, 0x93, 0xe1, 0x27, 0x1c, 0x26, 0x30, 0x58, 0xe9, 0xb1, 0xd5, 0x02, 0x00, 0x64,
+        0xe4, 0x51, 0xc6, 0x10, 0x00, 0x00, 0x00,
+    ];
+
+    fn decoder(content_encoding: &str) -> Result<ContentDecoder, String> {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Encoding", content_encoding);
+        ContentDecoder::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_identity() {
+        let mut decoder =
+            ContentDecoder::from_headers(&HeaderMap::new()).expect("failed to create");
+        assert!(decoder.is_identity());
+        let mut output = Vec::new();
+        decoder
+            .feed(b"plain", &mut output)
+            .expect("failed to decode");
+        assert_eq!(b"plain".to_vec(), output);
+    }
+
+    #[test]
+    fn test_multiple_codings() {
+        let mut decoder = decoder("deflate, GZIP").expect("failed to create");
+        let mut output = Vec::new();
+        for chunk in DEFLATE_THEN_GZIP.chunks(5) {
+            decoder.feed(chunk, &mut output).expect("failed to decode");
+        }
+        assert!(decoder.finish().is_ok());
+        assert_eq!(b"hello hello hello".to_vec(), output);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            Some("unsupported content-coding: br".to_string()),
+            decoder("br").err()
+        );
+    }
+
+    #[test]
+    fn test_empty_body() {
+        let decoder = decoder("gzip").expect("failed to create");
+        assert!(decoder.finish().is_ok());
+    }
+}
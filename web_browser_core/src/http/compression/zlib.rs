@@ -0,0 +1,160 @@
+use crate::alloc::string::ToString;
+use crate::http::compression::inflate::Inflater;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc1950
+// zlib形式のデータを少しずつ受け取って展開する
+// Content-Encoding: deflate はzlib形式だが、ヘッダーのない生のDEFLATEを送るサーバーもあるのでどちらも受け付ける
+#[derive(Debug, Clone)]
+pub struct ZlibDecoder {
+    header: Option<Vec<u8>>, // ヘッダーを読み終わるまで受け取ったバイト列を保存しておく
+    raw: bool,               // zlibのヘッダーがない生のDEFLATEかどうか
+    inflater: Inflater,
+    trailer: Vec<u8>, // Adler-32
+    adler: u32,
+}
+
+impl ZlibDecoder {
+    pub fn new() -> Self {
+        Self {
+            header: Some(Vec::new()),
+            raw: false,
+            inflater: Inflater::new(),
+            trailer: Vec::new(),
+            adler: 1,
+        }
+    }
+
+    pub fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), String> {
+        let mut input = input;
+        let header_rest;
+        let remaining;
+        if let Some(header) = &mut self.header {
+            header.extend_from_slice(input);
+            if header.len() < 2 {
+                return Ok(());
+            }
+            // https://www.rfc-editor.org/rfc/rfc1950#section-2.2
+            // CM = 8、CMF * 256 + FLG が31の倍数のときはzlibのヘッダーとみなす
+            let (cmf, flg) = (header[0], header[1]);
+            self.raw = cmf & 0x0f != 8 || u16::from_be_bytes([cmf, flg]) % 31 > 0;
+            if !self.raw && flg & 0x20 != 0 {
+                return Err("zlib preset dictionary is not supported".to_string());
+            }
+            header_rest = if self.raw {
+                core::mem::take(header)
+            } else {
+                header.split_off(2)
+            };
+            self.header = None;
+            input = &header_rest;
+        }
+
+        if !self.inflater.is_done() {
+            let start = output.len();
+            self.inflater.feed(input, output)?;
+            self.adler = adler32_update(self.adler, &output[start..]);
+            if !self.inflater.is_done() {
+                return Ok(());
+            }
+            remaining = self.inflater.take_remaining();
+            input = &remaining;
+        }
+
+        if self.raw {
+            return Ok(());
+        }
+        self.trailer.extend_from_slice(input);
+        if self.trailer.len() >= 4 {
+            let adler = u32::from_be_bytes([
+                self.trailer[0],
+                self.trailer[1],
+                self.trailer[2],
+                self.trailer[3],
+            ]);
+            if adler != self.adler {
+                return Err("zlib adler-32 mismatch".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // すべての入力を受け取った後に呼び出す。データが途中で終わっている場合はエラーを返す
+    pub fn finish(&self) -> Result<(), String> {
+        if self.inflater.is_done() && (self.raw || self.trailer.len() >= 4) {
+            return Ok(());
+        }
+        Err("unexpected end of deflate stream".to_string())
+    }
+}
+
+impl Default for ZlibDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc1950#section-9
+pub fn adler32_update(adler: u32, data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut a = adler & 0xffff;
+    let mut b = adler >> 16;
+    for &byte in data {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // zlib.compress(b"hello hello hello")
+    const ZLIB: [u8; 16] = [
+        0x78, 0x9c, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00, 0x3a, 0x2e, 0x06,
+        0x7d,
+    ];
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(0x11e60398, adler32_update(1, b"Wikipedia"));
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut decoder = ZlibDecoder::new();
+        let mut output = Vec::new();
+        for byte in ZLIB.iter() {
+            assert!(decoder.finish().is_err());
+            decoder
+                .feed(&[*byte], &mut output)
+                .expect("failed to decode");
+        }
+        assert!(decoder.finish().is_ok());
+        assert_eq!(b"hello hello hello".to_vec(), output);
+    }
+
+    #[test]
+    fn test_decode_raw_deflate() {
+        let mut decoder = ZlibDecoder::new();
+        let mut output = Vec::new();
+        decoder
+            .feed(&ZLIB[2..12], &mut output)
+            .expect("failed to decode");
+        assert!(decoder.finish().is_ok());
+        assert_eq!(b"hello hello hello".to_vec(), output);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut input = ZLIB;
+        input[15] ^= 0xff;
+        let mut decoder = ZlibDecoder::new();
+        assert_eq!(
+            Err("zlib adler-32 mismatch".to_string()),
+            decoder.feed(&input, &mut Vec::new())
+        );
+    }
+}
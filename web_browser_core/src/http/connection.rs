@@ -0,0 +1,176 @@
+use crate::error::Error;
+use crate::http::parser::ResponseParser;
+use crate::http::request::HttpRequest;
+use crate::http::transport::NetworkError;
+use crate::http::HttpResponse;
+
+// TCPやTLSの接続。OSごとのネットワークの実装（net_stdなど）が、接続の作り方と一緒に用意する
+pub trait Connection {
+    // バイト列をすべて送る
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), NetworkError>;
+
+    // 受け取ったバイト数を返す。0の場合は相手が接続を閉じた
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, NetworkError>;
+}
+
+// 1回のリクエストとレスポンスのやりとりが失敗した理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExchangeError {
+    // レスポンスを1バイトも受け取らないうちに接続が閉じられた
+    // 再利用した接続がサーバー側ですでに閉じられていた場合に起こる
+    Closed(Error),
+    Failed(Error),
+}
+
+impl From<ExchangeError> for Error {
+    fn from(e: ExchangeError) -> Self {
+        match e {
+            ExchangeError::Closed(e) | ExchangeError::Failed(e) => e,
+        }
+    }
+}
+
+// 接続にリクエストを送ってレスポンスを受け取る。ボディを受信するたびにon_bodyを呼び出す
+// 接続を維持できる場合は、プールに戻せるように接続も返す
+pub fn exchange<C: Connection>(
+    mut connection: C,
+    request: &HttpRequest,
+    on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+) -> Result<(HttpResponse, Option<C>), ExchangeError> {
+    // リクエストの送信
+    if let Err(e) = connection.write_all(&request.to_bytes()) {
+        return Err(ExchangeError::Closed(e.into()));
+    }
+
+    // レスポンスの受信
+    // Content-Lengthまたはchunkedでボディの終わりがわかる場合は、接続が閉じられるのを待たない
+    let mut parser = ResponseParser::for_method(request.method());
+    let mut head = None;
+    let mut received = false;
+    while !parser.is_complete() {
+        let mut buf = [0u8; 4096];
+        let bytes_read = match connection.read(&mut buf) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                if !received && e == NetworkError::ConnectionReset {
+                    return Err(ExchangeError::Closed(e.into()));
+                }
+                return Err(ExchangeError::Failed(e.into()));
+            }
+        };
+        if bytes_read == 0 {
+            if !received {
+                return Err(ExchangeError::Closed(NetworkError::ConnectionReset.into()));
+            }
+            break;
+        }
+        received = true;
+        parser
+            .feed(&buf[..bytes_read])
+            .map_err(|e| ExchangeError::Failed(e.into()))?;
+
+        if head.is_none() {
+            head = parser.head();
+        }
+        if let Some(head) = &head {
+            let chunk = parser.body_chunk();
+            if !chunk.is_empty() {
+                on_body(head, &chunk);
+            }
+        }
+    }
+
+    let keep_alive = parser.keep_alive() && !request.headers().has_token("Connection", "close");
+    let response = parser
+        .finish()
+        .map_err(|e| ExchangeError::Failed(e.into()))?;
+    Ok((response, if keep_alive { Some(connection) } else { None }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::Method;
+    use crate::url::Url;
+    use alloc::vec::Vec;
+
+    // 用意したバイト列を少しずつ返し、送られてきたバイト列を記録する接続
+    struct BufferConnection {
+        input: Vec<u8>,
+        chunk_size: usize,
+        output: Vec<u8>,
+    }
+
+    impl BufferConnection {
+        fn new(input: &[u8], chunk_size: usize) -> Self {
+            Self {
+                input: input.to_vec(),
+                chunk_size,
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl Connection for BufferConnection {
+        fn write_all(&mut self, bytes: &[u8]) -> Result<(), NetworkError> {
+            self.output.extend_from_slice(bytes);
+            Ok(())
+        }
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, NetworkError> {
+            let size = self.input.len().min(self.chunk_size).min(buf.len());
+            buf[..size].copy_from_slice(&self.input[..size]);
+            self.input.drain(..size);
+            Ok(size)
+        }
+    }
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest::new(Method::Get, Url::parse(url).expect("failed to parse url"))
+    }
+
+    #[test]
+    fn test_exchange() {
+        let connection =
+            BufferConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello", 8);
+        let mut chunks = Vec::new();
+        let (response, connection) =
+            exchange(connection, &get("http://example.com/"), &mut |_, chunk| {
+                chunks.extend_from_slice(chunk)
+            })
+            .expect("failed to exchange");
+        assert_eq!("hello", response.body_text());
+        assert_eq!(b"hello".to_vec(), chunks);
+        // Content-Lengthで終わりがわかるので、接続は再利用できる
+        let connection = connection.expect("connection should be kept alive");
+        assert!(connection.output.starts_with(b"GET / HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_exchange_connection_close() {
+        let connection =
+            BufferConnection::new(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nbye", 64);
+        let (response, connection) =
+            exchange(connection, &get("http://example.com/"), &mut |_, _| {})
+                .expect("failed to exchange");
+        assert_eq!("bye", response.body_text());
+        assert!(connection.is_none());
+    }
+
+    #[test]
+    fn test_exchange_closed() {
+        // 1バイトも受け取らないうちに閉じられた場合は、送り直せるように区別する
+        let connection = BufferConnection::new(b"", 64);
+        assert_eq!(
+            Some(ExchangeError::Closed(NetworkError::ConnectionReset.into())),
+            exchange(connection, &get("http://example.com/"), &mut |_, _| {}).err()
+        );
+
+        let connection =
+            BufferConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhe", 64);
+        assert!(matches!(
+            exchange(connection, &get("http://example.com/"), &mut |_, _| {}),
+            Err(ExchangeError::Failed(_))
+        ));
+    }
+}
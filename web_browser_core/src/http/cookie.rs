@@ -0,0 +1,716 @@
+use crate::clock::Clock;
+use crate::http::header::HeaderMap;
+use crate::url::host::Host;
+use crate::url::Url;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc6265bis#section-4.1.2.7
+// ブラウザがどのリクエストのコンテキストかを知らないので、属性は保存するだけで送信の判定には使わない
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+    Default, // 属性が指定されていない
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.3
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    path: String,
+    expires: Option<u64>, // Noneの場合はセッションクッキー
+    secure: bool,
+    http_only: bool,
+    same_site: SameSite,
+    host_only: bool, // Domain属性がなく、設定したホストにだけ送る
+    creation: u64,   // 保存した順番。Cookieヘッダーの並び順に使う
+}
+
+impl Cookie {
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    pub fn domain(&self) -> String {
+        self.domain.clone()
+    }
+
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    pub fn expires(&self) -> Option<u64> {
+        self.expires
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn is_http_only(&self) -> bool {
+        self.http_only
+    }
+
+    pub fn same_site(&self) -> SameSite {
+        self.same_site
+    }
+
+    pub fn is_host_only(&self) -> bool {
+        self.host_only
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        match self.expires {
+            Some(expires) => expires <= now,
+            None => false,
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc6265#section-5.4
+    // リクエストのURLにこのクッキーを送るかどうか
+    pub fn matches(&self, url: &Url) -> bool {
+        let host = canonical_host(url);
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain, is_ip_address(url))
+        };
+        domain_ok
+            && path_match(&url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+    }
+}
+
+// Set-Cookieヘッダーの属性をまだ解釈していない状態
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.2
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<u64>,
+    max_age: Option<i64>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: SameSite,
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.2
+fn parse_set_cookie(input: &str) -> Option<SetCookie> {
+    let (name_value, attributes) = match input.split_once(';') {
+        Some((name_value, attributes)) => (name_value, attributes),
+        None => (input, ""),
+    };
+    // = を含まない場合はクッキー全体を無視する
+    let (name, value) = name_value.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = SetCookie {
+        name: String::from(name),
+        value: String::from(value.trim()),
+        expires: None,
+        max_age: None,
+        domain: None,
+        path: None,
+        secure: false,
+        http_only: false,
+        same_site: SameSite::Default,
+    };
+
+    for attribute in attributes.split(';') {
+        let (key, value) = match attribute.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (attribute.trim(), ""),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            // https://www.rfc-editor.org/rfc/rfc6265#section-5.2.1
+            "expires" => {
+                if let Some(expires) = parse_cookie_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            }
+            // https://www.rfc-editor.org/rfc/rfc6265#section-5.2.2
+            "max-age" => {
+                let digits = value.strip_prefix('-').unwrap_or(value);
+                if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                    // 大きすぎる値は上限に丸める
+                    let delta = digits.parse::<i64>().unwrap_or(i64::MAX);
+                    cookie.max_age = Some(if value.starts_with('-') {
+                        -delta
+                    } else {
+                        delta
+                    });
+                }
+            }
+            // https://www.rfc-editor.org/rfc/rfc6265#section-5.2.3
+            "domain" if !value.is_empty() => {
+                let domain = value.strip_prefix('.').unwrap_or(value);
+                cookie.domain = Some(domain.to_ascii_lowercase());
+            }
+            // https://www.rfc-editor.org/rfc/rfc6265#section-5.2.4
+            "path" => {
+                cookie.path = if value.starts_with('/') {
+                    Some(String::from(value))
+                } else {
+                    None
+                };
+            }
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" => {
+                cookie.same_site = match value.to_ascii_lowercase().as_str() {
+                    "strict" => SameSite::Strict,
+                    "lax" => SameSite::Lax,
+                    "none" => SameSite::None,
+                    _ => SameSite::Default,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Some(cookie)
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.1.1
+// 日付を1970-01-01T00:00:00Zからの経過秒数に変換する。1970年より前は0にする
+pub fn parse_cookie_date(input: &str) -> Option<u64> {
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+
+    let is_delimiter = |c: char| matches!(c, '\x09' | '\x20'..='\x2f' | '\x3b'..='\x40' | '\x5b'..='\x60' | '\x7b'..='\x7e');
+    for token in input.split(is_delimiter).filter(|t| !t.is_empty()) {
+        if time.is_none() {
+            if let Some(t) = parse_time(token) {
+                time = Some(t);
+                continue;
+            }
+        }
+        if day.is_none() {
+            if let Some(d) = parse_digits(token, 1, 2) {
+                day = Some(d);
+                continue;
+            }
+        }
+        if month.is_none() {
+            if let Some(m) = parse_month(token) {
+                month = Some(m);
+                continue;
+            }
+        }
+        if year.is_none() {
+            if let Some(y) = parse_digits(token, 2, 4) {
+                year = Some(y);
+                continue;
+            }
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let (day, month, mut year) = (day?, month?, year?);
+    if (70..=99).contains(&year) {
+        year += 1900;
+    } else if year <= 69 {
+        year += 2000;
+    }
+    if !(1..=31).contains(&day) || year < 1601 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    if day > days_in_month(year, month) {
+        return None;
+    }
+
+    let days = days_from_civil(year as i64, month, day);
+    let seconds = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+    Some(seconds.max(0) as u64)
+}
+
+// 先頭のmin〜max桁の数字を読む。数字のあとには数字以外が続いてもよい
+fn parse_digits(token: &str, min: usize, max: usize) -> Option<u32> {
+    let length = token.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if length < min || length > max {
+        return None;
+    }
+    token[..length].parse().ok()
+}
+
+// hms-time = time-field ":" time-field ":" time-field
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let mut fields = token.splitn(3, ':');
+    let hour = fields.next()?;
+    let minute = fields.next()?;
+    let second = fields.next()?;
+    let second_length = second.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if hour.len() > 2 || minute.len() > 2 || !(1..=2).contains(&second_length) {
+        return None;
+    }
+    Some((
+        parse_digits(hour, 1, 2)?,
+        parse_digits(minute, 1, 2)?,
+        parse_digits(second, 1, 2)?,
+    ))
+}
+
+fn parse_month(token: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let prefix = token.get(..3)?.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|m| *m == prefix)
+        .map(|i| i as u32 + 1)
+}
+
+// 翌月の1日との差から、うるう年も含めた月の日数を求める
+fn days_in_month(year: u32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (days_from_civil(next_year as i64, next_month, 1) - days_from_civil(year as i64, month, 1))
+        as u32
+}
+
+// グレゴリオ暦の日付から1970-01-01までの日数を計算する
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// クッキーのドメインと比べるためのホスト名
+fn canonical_host(url: &Url) -> String {
+    url.hostname().to_ascii_lowercase()
+}
+
+fn is_ip_address(url: &Url) -> bool {
+    matches!(url.host(), Some(Host::Ipv4(_)) | Some(Host::Ipv6(_)))
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.1.3
+fn domain_match(host: &str, domain: &str, is_ip_address: bool) -> bool {
+    if host == domain {
+        return true;
+    }
+    !is_ip_address
+        && host.len() > domain.len()
+        && host.ends_with(domain)
+        && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.1.4
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    if !path.starts_with('/') {
+        return String::from("/");
+    }
+    match path.rfind('/') {
+        Some(0) | None => String::from("/"),
+        Some(i) => String::from(&path[..i]),
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.1.4
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    let request_path = if request_path.is_empty() {
+        "/"
+    } else {
+        request_path
+    };
+    match request_path.strip_prefix(cookie_path) {
+        Some(rest) => rest.is_empty() || cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc6265#section-5.3
+// ブラウザが持つクッキーの保存場所。有効期限は差し替えられる時計をもとに判定する
+// 時刻を取得できない場合は、受け取ったレスポンスのDateヘッダーのうち最も新しい時刻を今の時刻とみなす
+#[derive(Debug, Clone)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+    clock: Rc<dyn Clock>,
+    latest_date: u64, // これまでに受け取ったDateヘッダーの最も新しい時刻
+    next_creation: u64,
+}
+
+impl CookieJar {
+    pub fn new(clock: Rc<dyn Clock>) -> Self {
+        Self {
+            cookies: Vec::new(),
+            clock,
+            latest_date: 0,
+            next_creation: 0,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    // 有効期限が切れていないクッキーの一覧
+    pub fn cookies(&mut self) -> Vec<Cookie> {
+        self.evict_expired();
+        self.cookies.clone()
+    }
+
+    pub fn evict_expired(&mut self) {
+        let now = self.now();
+        self.cookies.retain(|c| !c.is_expired(now));
+    }
+
+    // レスポンスのSet-Cookieヘッダーをすべて保存する
+    pub fn store_response_cookies(&mut self, url: &Url, headers: &HeaderMap) {
+        if let Some(date) = headers.get("Date").and_then(|v| parse_cookie_date(&v)) {
+            self.latest_date = self.latest_date.max(date);
+        }
+        for value in headers.get_all("Set-Cookie") {
+            self.set_cookie(url, &value);
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc6265#section-5.3
+    // Set-Cookieヘッダーの値を1つ保存する。無視した場合はfalseを返す
+    pub fn set_cookie(&mut self, url: &Url, set_cookie: &str) -> bool {
+        if !matches!(url.scheme().as_str(), "http" | "https") {
+            return false;
+        }
+        let parsed = match parse_set_cookie(set_cookie) {
+            Some(parsed) => parsed,
+            None => return false,
+        };
+        let now = self.now();
+        let host = canonical_host(url);
+        let secure_origin = url.scheme() == "https";
+
+        // Max-AgeはExpiresより優先する
+        let expires = match parsed.max_age {
+            Some(delta) if delta <= 0 => Some(0),
+            Some(delta) => Some(now.saturating_add(delta as u64)),
+            None => parsed.expires,
+        };
+
+        let (domain, host_only) = match parsed.domain {
+            Some(domain) if domain != host => {
+                // パブリックサフィックスのリストを持たないので、ドットを含まないドメイン（comなど）は拒否する
+                if !domain.contains('.') || !domain_match(&host, &domain, is_ip_address(url)) {
+                    return false;
+                }
+                (domain, false)
+            }
+            Some(domain) => (domain, false),
+            None => (host, true),
+        };
+
+        // https://www.rfc-editor.org/rfc/rfc6265bis#section-5.7
+        // Secure属性のクッキーは安全なオリジンからしか設定できない
+        if parsed.secure && !secure_origin {
+            return false;
+        }
+        // SameSite=NoneにはSecure属性が必要
+        if parsed.same_site == SameSite::None && !parsed.secure {
+            return false;
+        }
+
+        let mut cookie = Cookie {
+            name: parsed.name,
+            value: parsed.value,
+            domain,
+            path: parsed.path.unwrap_or_else(|| default_path(url)),
+            expires,
+            secure: parsed.secure,
+            http_only: parsed.http_only,
+            same_site: parsed.same_site,
+            host_only,
+            creation: self.next_creation,
+        };
+
+        // 同じ名前、ドメイン、パスのクッキーは置き換える。作成された順番は古いものを引き継ぐ
+        if let Some(i) = self.cookies.iter().position(|c| {
+            c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path
+        }) {
+            let old = self.cookies.remove(i);
+            cookie.creation = old.creation;
+        } else {
+            self.next_creation += 1;
+        }
+
+        // 有効期限が過ぎている場合は、既存のクッキーを削除するだけになる
+        if cookie.is_expired(now) {
+            return true;
+        }
+        self.cookies.push(cookie);
+        true
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc6265#section-5.4
+    // リクエストに付けるCookieヘッダーの値。送るクッキーがない場合はNoneを返す
+    pub fn cookie_header(&mut self, url: &Url) -> Option<String> {
+        self.evict_expired();
+        let mut cookies: Vec<&Cookie> = self.cookies.iter().filter(|c| c.matches(url)).collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        // パスが長いものを先に、同じ長さなら先に作られたものを先に並べる
+        cookies.sort_by(|a, b| {
+            b.path
+                .len()
+                .cmp(&a.path.len())
+                .then(a.creation.cmp(&b.creation))
+        });
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    fn now(&self) -> u64 {
+        if self.clock.is_available() {
+            self.clock.now()
+        } else {
+            self.latest_date
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::clock::{ManualClock, NoClock};
+
+    // 2015-10-21T07:28:00Z
+    const NOW: u64 = 1445412480;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).expect("failed to parse url")
+    }
+
+    fn jar() -> (Rc<ManualClock>, CookieJar) {
+        let clock = Rc::new(ManualClock::new(NOW));
+        let jar = CookieJar::new(clock.clone());
+        (clock, jar)
+    }
+
+    #[test]
+    fn test_parse_cookie_date() {
+        assert_eq!(
+            Some(NOW),
+            parse_cookie_date("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+        assert_eq!(
+            Some(NOW),
+            parse_cookie_date("Wednesday, 21-Oct-15 07:28:00 GMT")
+        );
+        assert_eq!(Some(NOW), parse_cookie_date("Wed Oct 21 07:28:00 2015"));
+        assert_eq!(Some(951782400), parse_cookie_date("29 Feb 2000 00:00:00"));
+        assert_eq!(Some(0), parse_cookie_date("Thu, 01 Jan 1970 00:00:00 GMT"));
+        assert_eq!(None, parse_cookie_date("30 Feb 2000 00:00:00"));
+        assert_eq!(None, parse_cookie_date("21 Oct 2015"));
+        assert_eq!(None, parse_cookie_date("21 Foo 2015 07:28:00"));
+    }
+
+    #[test]
+    fn test_parse_attributes() {
+        let cookie = parse_set_cookie(
+            "sid=abc123; Domain=.Example.com; Path=/app; Max-Age=60; Secure; HttpOnly; SameSite=Lax",
+        )
+        .expect("failed to parse");
+        assert_eq!("sid", cookie.name);
+        assert_eq!("abc123", cookie.value);
+        assert_eq!(Some("example.com".to_string()), cookie.domain);
+        assert_eq!(Some("/app".to_string()), cookie.path);
+        assert_eq!(Some(60), cookie.max_age);
+        assert!(cookie.secure);
+        assert!(cookie.http_only);
+        assert_eq!(SameSite::Lax, cookie.same_site);
+
+        // = がない場合は無視する
+        assert_eq!(None, parse_set_cookie("novalue"));
+        assert_eq!(None, parse_set_cookie("=value"));
+        // 不正なMax-Ageは無視する
+        let cookie = parse_set_cookie("a=b; Max-Age=1x; Path=relative").expect("failed to parse");
+        assert_eq!(None, cookie.max_age);
+        assert_eq!(None, cookie.path);
+    }
+
+    #[test]
+    fn test_domain_matching() {
+        let (_, mut jar) = jar();
+        let origin = url("http://www.example.com/");
+        assert!(jar.set_cookie(&origin, "host=1"));
+        assert!(jar.set_cookie(&origin, "domain=2; Domain=example.com"));
+        // 別のドメインやトップレベルドメインには設定できない
+        assert!(!jar.set_cookie(&origin, "other=3; Domain=example.org"));
+        assert!(!jar.set_cookie(&origin, "tld=4; Domain=com"));
+
+        assert_eq!(
+            Some("host=1; domain=2".to_string()),
+            jar.cookie_header(&url("http://www.example.com/"))
+        );
+        assert_eq!(
+            Some("domain=2".to_string()),
+            jar.cookie_header(&url("http://api.example.com/"))
+        );
+        assert_eq!(
+            Some("domain=2".to_string()),
+            jar.cookie_header(&url("http://example.com/"))
+        );
+        assert_eq!(None, jar.cookie_header(&url("http://notexample.com/")));
+    }
+
+    #[test]
+    fn test_path_matching() {
+        let (_, mut jar) = jar();
+        let origin = url("http://example.com/account/login");
+        jar.set_cookie(&origin, "default=1");
+        jar.set_cookie(&origin, "root=2; Path=/");
+        jar.set_cookie(&origin, "deep=3; Path=/account/settings");
+
+        // デフォルトのパスは /account になる。パスが長いものから並べる
+        assert_eq!(
+            Some("deep=3; default=1; root=2".to_string()),
+            jar.cookie_header(&url("http://example.com/account/settings/profile"))
+        );
+        assert_eq!(
+            Some("default=1; root=2".to_string()),
+            jar.cookie_header(&url("http://example.com/account"))
+        );
+        assert_eq!(
+            Some("root=2".to_string()),
+            jar.cookie_header(&url("http://example.com/accounts"))
+        );
+    }
+
+    #[test]
+    fn test_secure() {
+        let (_, mut jar) = jar();
+        assert!(!jar.set_cookie(&url("http://example.com/"), "a=1; Secure"));
+        assert!(jar.set_cookie(&url("https://example.com/"), "a=1; Secure"));
+        assert!(!jar.set_cookie(&url("https://example.com/"), "b=2; SameSite=None"));
+        assert_eq!(None, jar.cookie_header(&url("http://example.com/")));
+        assert_eq!(
+            Some("a=1".to_string()),
+            jar.cookie_header(&url("https://example.com/"))
+        );
+    }
+
+    #[test]
+    fn test_expiration() {
+        let (clock, mut jar) = jar();
+        let origin = url("http://example.com/");
+        jar.set_cookie(&origin, "session=1");
+        jar.set_cookie(&origin, "short=2; Max-Age=10");
+        // Max-AgeはExpiresより優先する
+        jar.set_cookie(
+            &origin,
+            "long=3; Expires=Wed, 21 Oct 2015 07:28:05 GMT; Max-Age=100",
+        );
+        assert_eq!(3, jar.len());
+
+        clock.advance(10);
+        assert_eq!(
+            Some("session=1; long=3".to_string()),
+            jar.cookie_header(&origin)
+        );
+        assert_eq!(2, jar.len());
+
+        // 過去の有効期限で上書きすると削除される
+        jar.set_cookie(&origin, "session=1; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(Some("long=3".to_string()), jar.cookie_header(&origin));
+
+        clock.advance(100);
+        assert!(jar.cookies().is_empty());
+    }
+
+    #[test]
+    fn test_delete_with_past_expires() {
+        let (_, mut jar) = jar();
+        let origin = url("http://example.com/");
+        jar.set_cookie(&origin, "id=1");
+        jar.set_cookie(&origin, "theme=dark");
+        assert!(jar.set_cookie(&origin, "id=; Expires=Thu, 01 Jan 2010 00:00:00 GMT"));
+        assert_eq!(Some("theme=dark".to_string()), jar.cookie_header(&origin));
+        assert_eq!(1, jar.len());
+    }
+
+    #[test]
+    fn test_expiration_without_clock() {
+        let mut jar = CookieJar::new(Rc::new(NoClock::new()));
+        let origin = url("http://example.com/");
+        let mut headers = HeaderMap::new();
+        headers.append("Date", "Wed, 21 Oct 2015 07:28:00 GMT");
+        headers.append("Set-Cookie", "id=1");
+        headers.append("Set-Cookie", "short=2; Max-Age=10");
+        jar.store_response_cookies(&origin, &headers);
+        assert_eq!(2, jar.len());
+
+        // Dateヘッダーの時刻をもとに、過去の有効期限のクッキーを削除する
+        let mut headers = HeaderMap::new();
+        headers.append("Date", "Wed, 21 Oct 2015 07:28:10 GMT");
+        headers.append("Set-Cookie", "id=; Expires=Thu, 01 Jan 2010 00:00:00 GMT");
+        jar.store_response_cookies(&origin, &headers);
+        assert!(jar.cookies().is_empty());
+    }
+
+    #[test]
+    fn test_replace() {
+        let (_, mut jar) = jar();
+        let origin = url("http://example.com/");
+        jar.set_cookie(&origin, "a=1");
+        jar.set_cookie(&origin, "b=2");
+        jar.set_cookie(&origin, "a=3");
+        assert_eq!(2, jar.len());
+        assert_eq!(Some("a=3; b=2".to_string()), jar.cookie_header(&origin));
+    }
+
+    #[test]
+    fn test_store_response_cookies() {
+        let (_, mut jar) = jar();
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("set-cookie", "b=2; HttpOnly");
+        jar.store_response_cookies(&url("http://example.com/"), &headers);
+        let cookies = jar.cookies();
+        assert_eq!(2, cookies.len());
+        assert!(cookies[0].is_host_only());
+        assert!(cookies[1].is_http_only());
+    }
+}
@@ -0,0 +1,908 @@
+use crate::error::Error;
+use crate::http::auth::{select_challenge, AuthCache, CredentialsProvider};
+use crate::http::cache::{CacheLookup, HttpCache};
+use crate::http::compression::ACCEPT_ENCODING;
+use crate::http::cookie::CookieJar;
+use crate::http::proxy::ProxyConfig;
+use crate::http::request::{HttpRequest, Method};
+use crate::http::transport::HttpTransport;
+use crate::http::{HttpError, HttpResponse};
+use crate::url::Url;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// https://fetch.spec.whatwg.org/#http-redirect-fetch
+// リダイレクトをたどる回数の上限のデフォルト値
+pub const DEFAULT_MAX_REDIRECTS: usize = 20;
+
+// トランスポートを使ってリクエストを送り、リダイレクトをたどって最終的なレスポンスを返す
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    transport: Rc<dyn HttpTransport>,
+    max_redirects: usize,
+    cookie_jar: Option<Rc<RefCell<CookieJar>>>, // 設定されている場合はクッキーを送受信する
+    cache: Option<Rc<RefCell<HttpCache>>>,
+    proxy: Option<ProxyConfig>, // 設定されている場合は、no_proxyに含まれないホストへのリクエストをプロキシに送る
+    auth_cache: Option<Rc<RefCell<AuthCache>>>, // 設定されている場合は、401のレスポンスに認証情報を付けて送り直す
+    credentials_provider: Option<Rc<dyn CredentialsProvider>>,
+}
+
+impl Fetcher {
+    pub fn new(transport: Rc<dyn HttpTransport>) -> Self {
+        Self {
+            transport,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cookie_jar: None,
+            cache: None,
+            proxy: None,
+            auth_cache: None,
+            credentials_provider: None,
+        }
+    }
+
+    pub fn set_max_redirects(&mut self, max_redirects: usize) {
+        self.max_redirects = max_redirects;
+    }
+
+    pub fn max_redirects(&self) -> usize {
+        self.max_redirects
+    }
+
+    pub fn set_cookie_jar(&mut self, cookie_jar: Rc<RefCell<CookieJar>>) {
+        self.cookie_jar = Some(cookie_jar);
+    }
+
+    pub fn set_cache(&mut self, cache: Rc<RefCell<HttpCache>>) {
+        self.cache = Some(cache);
+    }
+
+    pub fn set_proxy(&mut self, proxy: Option<ProxyConfig>) {
+        self.proxy = proxy;
+    }
+
+    pub fn set_auth_cache(&mut self, auth_cache: Rc<RefCell<AuthCache>>) {
+        self.auth_cache = Some(auth_cache);
+    }
+
+    pub fn set_credentials_provider(&mut self, provider: Rc<dyn CredentialsProvider>) {
+        self.credentials_provider = Some(provider);
+    }
+
+    // レスポンスにはリダイレクトでたどったURLのリストを設定する。最後のURLが実際のドキュメントのURLになる
+    pub fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.fetch_streaming(request, &mut |_, _| {})
+    }
+
+    // 最終的なレスポンスのボディを受け取るたびにon_bodyを呼び出す。リダイレクトのレスポンスのボディは渡さない
+    pub fn fetch_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let mut request = request;
+        // 展開できるコンテンツコーディングを伝える
+        if !request.headers().contains("Accept-Encoding") {
+            request
+                .headers_mut()
+                .insert("Accept-Encoding", ACCEPT_ENCODING);
+        }
+        let mut url_list = Vec::new();
+        url_list.push(request.url());
+
+        loop {
+            let mut response = self.send_with_auth(&request, on_body)?;
+
+            let location = match response.headers().location() {
+                Some(location) if is_redirect_status(response.status_code()) => location,
+                // Locationがないリダイレクトはそのままレスポンスとして扱う
+                _ => {
+                    response.set_url_list(url_list);
+                    return Ok(response);
+                }
+            };
+
+            if url_list.len() > self.max_redirects {
+                return Err(HttpError::TooManyRedirects(self.max_redirects).into());
+            }
+
+            request = redirect_request(&request, response.status_code(), &location)?;
+            url_list.push(request.url());
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-11.6.1
+    // 401のレスポンスを受け取ったら、保護空間に保存していた認証情報か、CredentialsProviderが用意した認証情報を付けて送り直す
+    fn send_with_auth(
+        &self,
+        request: &HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let auth_cache = match &self.auth_cache {
+            Some(auth_cache) => auth_cache,
+            None => return self.send(request, on_body),
+        };
+        let url = request.url();
+        let mut sent = request.clone();
+        let mut used_realm = None; // 保存していた認証情報を送った保護空間
+        if !request.headers().contains("Authorization") {
+            if let Some((realm, authorization)) = auth_cache.borrow_mut().preemptive(request) {
+                sent.headers_mut().insert("Authorization", &authorization);
+                used_realm = Some(realm);
+            }
+        }
+        let mut response = self.send(&sent, on_body)?;
+
+        // 保存していた認証情報とCredentialsProviderの認証情報で、それぞれ1回まで送り直す
+        let mut asked = false;
+        for _ in 0..2 {
+            if response.status_code() != 401 {
+                break;
+            }
+            let challenge = match select_challenge(&response.headers().get_all("WWW-Authenticate"))
+            {
+                Some(challenge) => challenge,
+                None => break,
+            };
+            let realm = challenge.realm();
+            let used = used_realm.as_deref() == Some(realm.as_str());
+            let cached = auth_cache.borrow().get(&url, &realm);
+            let credentials = match cached {
+                // まだ送っていないか、nonceが古くなっただけの場合は保存していた認証情報を使う
+                Some(credentials) if !used || challenge.is_stale() => credentials,
+                _ => {
+                    // 拒否された認証情報は忘れる
+                    if used {
+                        auth_cache.borrow_mut().remove(&url, &realm);
+                    }
+                    let provided = match &self.credentials_provider {
+                        Some(provider) if !asked => provider.credentials(&url, &challenge),
+                        _ => None,
+                    };
+                    asked = true;
+                    match provided {
+                        Some(credentials) => credentials,
+                        None => break,
+                    }
+                }
+            };
+
+            let authorization =
+                auth_cache
+                    .borrow_mut()
+                    .authorize(request, &challenge, &credentials);
+            sent = request.clone();
+            sent.headers_mut().insert("Authorization", &authorization);
+            used_realm = Some(realm);
+            response = self.send(&sent, on_body)?;
+            if response.status_code() != 401 {
+                auth_cache
+                    .borrow_mut()
+                    .store(&url, &challenge, &credentials);
+            }
+        }
+
+        // 送り直さなかった401のボディは、ここで呼び出し元に渡す
+        if !self.streams_body(&response) && is_final_response(&response) {
+            on_body(&response, &response.body());
+        }
+        Ok(response)
+    }
+
+    // 受け取りながらボディを呼び出し元に渡すレスポンスかどうか
+    // 認証情報を付けて送り直すかもしれない401のボディは、送り直さないと決まってから渡す
+    fn streams_body(&self, response: &HttpResponse) -> bool {
+        let challenge =
+            response.status_code() == 401 && response.headers().contains("WWW-Authenticate");
+        is_final_response(response) && !(challenge && self.auth_cache.is_some())
+    }
+
+    // リクエストを1つ送る。キャッシュとクッキーはリダイレクトのたびにそのURLのものを使う
+    fn send(
+        &self,
+        request: &HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let url = request.url();
+        let mut sent = request.clone();
+        if let Some(cache) = &self.cache {
+            let lookup = cache.borrow_mut().lookup(request);
+            match lookup {
+                CacheLookup::Fresh(response) => {
+                    if self.streams_body(&response) {
+                        on_body(&response, &response.body());
+                    }
+                    return Ok(response);
+                }
+                CacheLookup::Stale(conditional_headers) => {
+                    for header in &conditional_headers {
+                        sent.headers_mut().insert(&header.name(), &header.value());
+                    }
+                }
+                CacheLookup::Miss => {}
+            }
+        }
+        if let Some(cookie_jar) = &self.cookie_jar {
+            if !sent.headers().contains("Cookie") {
+                if let Some(cookie) = cookie_jar.borrow_mut().cookie_header(&url) {
+                    sent.headers_mut().insert("Cookie", &cookie);
+                }
+            }
+        }
+
+        if let Some(proxy) = &self.proxy {
+            if proxy.uses_proxy(&url) {
+                sent.set_proxy(Some(proxy.clone()));
+            }
+        }
+
+        let response = self.transport.send_streaming(sent, &mut |head, chunk| {
+            if self.streams_body(head) {
+                on_body(head, chunk);
+            }
+        })?;
+        if let Some(cookie_jar) = &self.cookie_jar {
+            cookie_jar
+                .borrow_mut()
+                .store_response_cookies(&url, &response.headers());
+        }
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(response),
+        };
+        // 304の場合はキャッシュしているレスポンスに置き換わるので、そのボディを渡す
+        let not_modified = response.status_code() == 304;
+        let response = cache.borrow_mut().store(request, response);
+        if not_modified && response.status_code() != 304 && self.streams_body(&response) {
+            on_body(&response, &response.body());
+        }
+        Ok(response)
+    }
+}
+
+// リダイレクトしたり、キャッシュで置き換えたりせずに、そのまま呼び出し元に返すレスポンスかどうか
+fn is_final_response(response: &HttpResponse) -> bool {
+    let redirect =
+        is_redirect_status(response.status_code()) && response.headers().location().is_some();
+    !redirect && response.status_code() != 304
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-15.4
+fn is_redirect_status(status_code: u32) -> bool {
+    matches!(status_code, 301 | 302 | 303 | 307 | 308)
+}
+
+// https://fetch.spec.whatwg.org/#http-redirect-fetch
+// リダイレクト先へのリクエストを作る
+fn redirect_request(
+    request: &HttpRequest,
+    status_code: u32,
+    location: &str,
+) -> Result<HttpRequest, Error> {
+    let current_url = request.url();
+    let mut url = current_url
+        .join(location)
+        .map_err(|error| HttpError::InvalidRedirect {
+            location: location.to_string(),
+            error,
+        })?;
+    if !matches!(url.scheme().as_str(), "http" | "https") {
+        return Err(HttpError::UnsupportedScheme(url.scheme()).into());
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-10.2.2
+    // Locationにフラグメントがない場合は元のURLのフラグメントを引き継ぐ
+    if url.fragment().is_none() {
+        if let Some(fragment) = current_url.fragment() {
+            url = url.join(&format!("#{}", fragment))?;
+        }
+    }
+
+    // 301と302のPOST、303のGETとHEAD以外はGETに書き換えてボディを送らない
+    // 307と308はメソッドとボディをそのまま送る
+    let method = request.method();
+    let rewrite_to_get = match status_code {
+        301 | 302 => method == Method::Post,
+        303 => !matches!(method, Method::Get | Method::Head),
+        _ => false,
+    };
+
+    let mut builder = HttpRequest::builder(
+        if rewrite_to_get { Method::Get } else { method },
+        url.clone(),
+    );
+    for header in &request.headers() {
+        let name = header.name().to_ascii_lowercase();
+        // HostとCookieは新しいURLから作り直す
+        if name == "host" || name == "cookie" {
+            continue;
+        }
+        // ボディを送らなくなる場合は、ボディに関するヘッダーも送らない
+        if rewrite_to_get && (name.starts_with("content-") || name == "transfer-encoding") {
+            continue;
+        }
+        // 別のオリジンには認証情報を送らない
+        if name == "authorization" && !is_same_origin(&current_url, &url) {
+            continue;
+        }
+        builder = builder.header(&header.name(), &header.value());
+    }
+    if !rewrite_to_get {
+        if let Some(body) = request.body() {
+            builder = builder.body(body);
+        }
+    }
+    builder.build()
+}
+
+// https://html.spec.whatwg.org/multipage/browsers.html#same-origin
+fn is_same_origin(a: &Url, b: &Url) -> bool {
+    a.scheme() == b.scheme() && a.host() == b.host() && a.port_or_default() == b.port_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::clock::{Clock, ManualClock, NoClock};
+    use crate::http::auth::{Challenge, Credentials};
+    use crate::http::transport::MockTransport;
+    use alloc::string::String;
+    use alloc::vec;
+    use core::cell::Cell;
+
+    fn fetch(
+        transport: MockTransport,
+        method: Method,
+        url: &str,
+    ) -> (Rc<MockTransport>, Result<HttpResponse, Error>) {
+        let transport = Rc::new(transport);
+        let fetcher = Fetcher::new(transport.clone());
+        let request = HttpRequest::builder(method, Url::parse(url).expect("failed to parse url"))
+            .header("Content-Type", "text/plain")
+            .header("Authorization", "Basic dXNlcjpwYXNz")
+            .body(b"data".to_vec())
+            .build()
+            .expect("failed to build request");
+        let result = fetcher.fetch(request);
+        (transport, result)
+    }
+
+    fn url_list(response: &HttpResponse) -> Vec<String> {
+        response.url_list().iter().map(|u| u.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_redirect() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+        );
+        let (_, result) = fetch(transport, Method::Get, "http://example.com/");
+        let response = result.expect("failed to fetch");
+        assert_eq!(vec!["http://example.com/"], url_list(&response));
+        assert_eq!(
+            Some("http://example.com/".to_string()),
+            response.url().map(|u| u.to_string())
+        );
+    }
+
+    #[test]
+    fn test_proxy() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 302 Found\r\nLocation: http://intranet.corp/\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://intranet.corp/",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        );
+        let transport = Rc::new(transport);
+        let mut fetcher = Fetcher::new(transport.clone());
+        let mut proxy = ProxyConfig::new("proxy.example", 3128);
+        proxy.add_no_proxy("corp");
+        fetcher.set_proxy(Some(proxy.clone()));
+        fetcher
+            .fetch(HttpRequest::new(
+                Method::Get,
+                Url::parse("http://example.com/").expect("failed to parse url"),
+            ))
+            .expect("failed to fetch");
+
+        // no_proxyに含まれるホストへのリダイレクト先には直接接続する
+        let requests = transport.requests();
+        assert_eq!(Some(proxy), requests[0].proxy());
+        assert_eq!("http://example.com/", requests[0].target());
+        assert_eq!(None, requests[1].proxy());
+        assert_eq!("/", requests[1].target());
+    }
+
+    #[test]
+    fn test_follow_redirects() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/old",
+            b"HTTP/1.1 301 Moved Permanently\r\nLocation: /dir/new\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://example.com/dir/new",
+            b"HTTP/1.1 302 Found\r\nLocation: http://www.example.com/final\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://www.example.com/final",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nfinal",
+        );
+        let (transport, result) = fetch(transport, Method::Get, "http://example.com/old#top");
+        let response = result.expect("failed to fetch");
+        assert_eq!("final", response.body_text());
+        assert_eq!(
+            vec![
+                "http://example.com/old#top",
+                "http://example.com/dir/new#top",
+                "http://www.example.com/final#top"
+            ],
+            url_list(&response)
+        );
+
+        // 別のオリジンへのリダイレクトではAuthorizationを送らない
+        let requests = transport.requests();
+        assert!(requests[1].headers().contains("Authorization"));
+        assert!(!requests[2].headers().contains("Authorization"));
+        assert_eq!("www.example.com", requests[2].host_header());
+        assert_eq!(
+            Some("gzip, deflate".to_string()),
+            requests[0].headers().get("Accept-Encoding")
+        );
+    }
+
+    #[test]
+    fn test_method_rewriting() {
+        for (status, expected_method, has_body) in [
+            ("301", Method::Get, false),
+            ("302", Method::Get, false),
+            ("303", Method::Get, false),
+            ("307", Method::Post, true),
+            ("308", Method::Post, true),
+        ] {
+            let mut transport = MockTransport::new();
+            let raw = format!(
+                "HTTP/1.1 {} Redirect\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n",
+                status
+            );
+            transport.add_response("http://example.com/form", raw.as_bytes());
+            transport.add_response(
+                "http://example.com/next",
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+            );
+            let (transport, result) = fetch(transport, Method::Post, "http://example.com/form");
+            assert!(result.is_ok());
+
+            let redirected = &transport.requests()[1];
+            assert_eq!(expected_method, redirected.method(), "status {}", status);
+            assert_eq!(has_body, redirected.body().is_some(), "status {}", status);
+            assert_eq!(
+                has_body,
+                redirected.headers().contains("Content-Type"),
+                "status {}",
+                status
+            );
+        }
+
+        // 303以外はGETのメソッドを書き換えない。PUTは303のときだけGETになる
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/a",
+            b"HTTP/1.1 302 Found\r\nLocation: /b\r\n\r\n",
+        );
+        transport.add_response(
+            "http://example.com/b",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        );
+        let (transport, _) = fetch(transport, Method::Put, "http://example.com/a");
+        assert_eq!(Method::Put, transport.requests()[1].method());
+    }
+
+    #[test]
+    fn test_too_many_redirects() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/a",
+            b"HTTP/1.1 302 Found\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://example.com/b",
+            b"HTTP/1.1 302 Found\r\nLocation: /a\r\nContent-Length: 0\r\n\r\n",
+        );
+        let transport = Rc::new(transport);
+        let mut fetcher = Fetcher::new(transport.clone());
+        fetcher.set_max_redirects(3);
+        let url = Url::parse("http://example.com/a").expect("failed to parse url");
+        assert_eq!(
+            Some(Error::Http(HttpError::TooManyRedirects(3))),
+            fetcher.fetch(HttpRequest::new(Method::Get, url)).err()
+        );
+        // 最初のリクエストと3回のリダイレクト
+        assert_eq!(4, transport.requests().len());
+    }
+
+    #[test]
+    fn test_cookies() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/login",
+            b"HTTP/1.1 302 Found\r\nLocation: /home\r\nSet-Cookie: sid=abc; Path=/\r\nSet-Cookie: lang=ja; Domain=example.com\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://example.com/home",
+            b"HTTP/1.1 302 Found\r\nLocation: http://other.example.org/\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://other.example.org/",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        );
+        let transport = Rc::new(transport);
+        let cookie_jar = Rc::new(RefCell::new(CookieJar::new(Rc::new(ManualClock::new(0)))));
+        let mut fetcher = Fetcher::new(transport.clone());
+        fetcher.set_cookie_jar(cookie_jar.clone());
+        let url = Url::parse("http://example.com/login").expect("failed to parse url");
+        fetcher
+            .fetch(HttpRequest::new(Method::Post, url))
+            .expect("failed to fetch");
+
+        let requests = transport.requests();
+        assert!(!requests[0].headers().contains("Cookie"));
+        assert_eq!(
+            Some("sid=abc; lang=ja".to_string()),
+            requests[1].headers().get("Cookie")
+        );
+        // 別のドメインにはクッキーを送らない
+        assert!(!requests[2].headers().contains("Cookie"));
+        assert_eq!(2, cookie_jar.borrow().len());
+    }
+
+    // If-None-Matchが今のETagと一致する場合は304を返し、それ以外は200を返すトランスポート
+    #[derive(Debug)]
+    struct RevalidatingTransport {
+        etag: &'static str,
+        cache_control: &'static str,
+        requests: RefCell<Vec<HttpRequest>>,
+    }
+
+    impl RevalidatingTransport {
+        fn new(etag: &'static str, cache_control: &'static str) -> Self {
+            Self {
+                etag,
+                cache_control,
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn requests(&self) -> Vec<HttpRequest> {
+            self.requests.borrow().clone()
+        }
+    }
+
+    impl HttpTransport for RevalidatingTransport {
+        fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+            let not_modified = !self.etag.is_empty()
+                && request.headers().get("If-None-Match").as_deref() == Some(self.etag);
+            let etag = if self.etag.is_empty() {
+                String::new()
+            } else {
+                format!("ETag: {}\r\n", self.etag)
+            };
+            self.requests.borrow_mut().push(request);
+            let raw = if not_modified {
+                format!(
+                    "HTTP/1.1 304 Not Modified\r\nCache-Control: {}\r\n{}\r\n",
+                    self.cache_control, etag
+                )
+            } else {
+                format!(
+                    "HTTP/1.1 200 OK\r\nCache-Control: {}\r\n{}Content-Length: 3\r\n\r\np{{}}",
+                    self.cache_control, etag
+                )
+            };
+            Ok(HttpResponse::new(raw.as_bytes())?)
+        }
+    }
+
+    fn cached_fetcher<T: HttpTransport + 'static>(
+        transport: Rc<T>,
+        clock: Rc<dyn Clock>,
+    ) -> (Fetcher, Rc<RefCell<HttpCache>>) {
+        let cache = Rc::new(RefCell::new(HttpCache::new(clock)));
+        let mut fetcher = Fetcher::new(transport);
+        fetcher.set_cache(cache.clone());
+        (fetcher, cache)
+    }
+
+    fn fetch_url(fetcher: &Fetcher, url: &str) -> HttpResponse {
+        fetcher.fetch(get(url)).expect("failed to fetch")
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/old",
+            b"HTTP/1.1 301 Moved Permanently\r\nLocation: /style.css\r\nCache-Control: max-age=3600\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://example.com/style.css",
+            b"HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nETag: \"v1\"\r\nContent-Length: 3\r\n\r\np{}",
+        );
+        let transport = Rc::new(transport);
+        let clock = Rc::new(ManualClock::new(1445412480));
+        let (fetcher, cache) = cached_fetcher(transport.clone(), clock.clone());
+
+        let response = fetch_url(&fetcher, "http://example.com/old");
+        assert_eq!("p{}", response.body_text());
+        assert_eq!(2, transport.requests().len());
+
+        // 新鮮なうちはリダイレクトも含めてネットワークに送らない
+        let response = fetch_url(&fetcher, "http://example.com/old");
+        assert_eq!(2, url_list(&response).len());
+        assert_eq!("p{}", response.body_text());
+        assert_eq!(2, transport.requests().len());
+        assert_eq!(2, cache.borrow().hits());
+    }
+
+    #[test]
+    fn test_cache_revalidation() {
+        let transport = Rc::new(RevalidatingTransport::new("\"v1\"", "max-age=60"));
+        let clock = Rc::new(ManualClock::new(1445412480));
+        let (fetcher, cache) = cached_fetcher(transport.clone(), clock.clone());
+        let url = "http://example.com/style.css";
+        assert_eq!(200, fetch_url(&fetcher, url).status_code());
+
+        // 古くなったら検証のリクエストを送り、304の場合は保存しているボディを返す
+        clock.advance(60);
+        let response = fetch_url(&fetcher, url);
+        let requests = transport.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(
+            Some("\"v1\"".to_string()),
+            requests[1].headers().get("If-None-Match")
+        );
+        assert_eq!(200, response.status_code());
+        assert_eq!("p{}", response.body_text());
+        assert_eq!(1, cache.borrow().hits());
+
+        // 304で有効期限が延びるので、次はネットワークに送らない
+        clock.advance(59);
+        assert_eq!("p{}", fetch_url(&fetcher, url).body_text());
+        assert_eq!(2, transport.requests().len());
+        assert_eq!(2, cache.borrow().hits());
+    }
+
+    #[test]
+    fn test_cache_expired_without_validator() {
+        let transport = Rc::new(RevalidatingTransport::new("", "max-age=60"));
+        let clock = Rc::new(ManualClock::new(1445412480));
+        let (fetcher, cache) = cached_fetcher(transport.clone(), clock.clone());
+        let url = "http://example.com/style.css";
+        fetch_url(&fetcher, url);
+        clock.advance(59);
+        fetch_url(&fetcher, url);
+        assert_eq!(1, transport.requests().len());
+
+        // 検証に使うヘッダーがないので、期限が切れたら条件なしで取得し直す
+        clock.advance(1);
+        assert_eq!("p{}", fetch_url(&fetcher, url).body_text());
+        let requests = transport.requests();
+        assert_eq!(2, requests.len());
+        assert!(!requests[1].headers().contains("If-None-Match"));
+        assert_eq!(1, cache.borrow().hits());
+        assert_eq!(2, cache.borrow().misses());
+    }
+
+    #[test]
+    fn test_cache_without_clock() {
+        let transport = Rc::new(RevalidatingTransport::new("\"v1\"", "max-age=3600"));
+        let (fetcher, cache) = cached_fetcher(transport.clone(), Rc::new(NoClock::new()));
+        let url = "http://example.com/style.css";
+        fetch_url(&fetcher, url);
+
+        // 時刻がわからない場合は、有効期限内でも毎回検証する
+        assert_eq!("p{}", fetch_url(&fetcher, url).body_text());
+        let requests = transport.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(
+            Some("\"v1\"".to_string()),
+            requests[1].headers().get("If-None-Match")
+        );
+        assert_eq!(1, cache.borrow().hits());
+    }
+
+    #[test]
+    fn test_cache_request_directives() {
+        let transport = Rc::new(RevalidatingTransport::new("\"v1\"", "max-age=3600"));
+        let clock = Rc::new(ManualClock::new(1445412480));
+        let (fetcher, cache) = cached_fetcher(transport.clone(), clock);
+        let url = "http://example.com/style.css";
+        fetch_url(&fetcher, url);
+
+        // リクエストにmax-age=0かno-cacheがある場合は、新鮮なレスポンスでも検証する
+        for directive in ["max-age=0", "no-cache"] {
+            let request =
+                HttpRequest::builder(Method::Get, Url::parse(url).expect("failed to parse url"))
+                    .header("Cache-Control", directive)
+                    .build()
+                    .expect("failed to build request");
+            let response = fetcher.fetch(request).expect("failed to fetch");
+            assert_eq!("p{}", response.body_text());
+            assert_eq!(
+                Some("\"v1\"".to_string()),
+                transport
+                    .requests()
+                    .last()
+                    .and_then(|r| r.headers().get("If-None-Match")),
+                "{}",
+                directive
+            );
+        }
+        assert_eq!(3, transport.requests().len());
+        // 304の場合は保存しているレスポンスを返すので、ヒットとして数える
+        assert_eq!(2, cache.borrow().hits());
+    }
+
+    #[test]
+    fn test_fetch_streaming() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 302 Found\r\nLocation: /index.html\r\nContent-Length: 8\r\n\r\nredirect",
+        );
+        transport.add_response(
+            "http://example.com/index.html",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello",
+        );
+        let fetcher = Fetcher::new(Rc::new(transport));
+        let url = Url::parse("http://example.com/").expect("failed to parse url");
+        let mut chunks = Vec::new();
+        let response = fetcher
+            .fetch_streaming(HttpRequest::new(Method::Get, url), &mut |head, chunk| {
+                assert_eq!(200, head.status_code());
+                chunks.push(chunk.to_vec());
+            })
+            .expect("failed to fetch");
+        // リダイレクトのボディは渡さない
+        assert_eq!(vec![b"hello".to_vec()], chunks);
+        assert_eq!("hello", response.body_text());
+    }
+
+    #[test]
+    fn test_redirect_without_location() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 302 Found\r\nContent-Length: 0\r\n\r\n",
+        );
+        let (_, result) = fetch(transport, Method::Get, "http://example.com/");
+        assert_eq!(302, result.expect("failed to fetch").status_code());
+    }
+
+    // Authorizationが正しい場合だけ200を返し、それ以外は401を返すトランスポート
+    #[derive(Debug, Default)]
+    struct ProtectedTransport {
+        requests: RefCell<Vec<HttpRequest>>,
+    }
+
+    impl HttpTransport for ProtectedTransport {
+        fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+            let authorized =
+                request.headers().get("Authorization") == Some("Basic dXNlcjpwYXNz".to_string());
+            self.requests.borrow_mut().push(request);
+            let raw: &[u8] = if authorized {
+                b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nsecret"
+            } else {
+                b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"members\"\r\nContent-Length: 6\r\n\r\ndenied"
+            };
+            HttpResponse::new(raw).map_err(Error::from)
+        }
+    }
+
+    // 決まった認証情報を返し、呼び出された回数を数える
+    #[derive(Debug)]
+    struct FixedProvider {
+        credentials: Option<Credentials>,
+        count: Cell<usize>,
+    }
+
+    impl CredentialsProvider for FixedProvider {
+        fn credentials(&self, url: &Url, challenge: &Challenge) -> Option<Credentials> {
+            assert_eq!("example.com", url.hostname());
+            assert_eq!("members", challenge.realm());
+            self.count.set(self.count.get() + 1);
+            self.credentials.clone()
+        }
+    }
+
+    fn protected_fetcher(
+        credentials: Option<Credentials>,
+    ) -> (Rc<ProtectedTransport>, Rc<FixedProvider>, Fetcher) {
+        let transport = Rc::new(ProtectedTransport::default());
+        let provider = Rc::new(FixedProvider {
+            credentials,
+            count: Cell::new(0),
+        });
+        let mut fetcher = Fetcher::new(transport.clone());
+        fetcher.set_auth_cache(Rc::new(RefCell::new(AuthCache::new(Rc::new(
+            ManualClock::new(0),
+        )))));
+        fetcher.set_credentials_provider(provider.clone());
+        (transport, provider, fetcher)
+    }
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest::new(Method::Get, Url::parse(url).expect("failed to parse url"))
+    }
+
+    #[test]
+    fn test_authentication() {
+        let (transport, provider, fetcher) =
+            protected_fetcher(Some(Credentials::new("user", "pass")));
+        let mut chunks = Vec::new();
+        let response = fetcher
+            .fetch_streaming(get("http://example.com/docs/a"), &mut |_, chunk| {
+                chunks.push(String::from_utf8_lossy(chunk).to_string())
+            })
+            .expect("failed to fetch");
+        // 401のボディは呼び出し元に渡さない
+        assert_eq!(vec!["secret"], chunks);
+        assert_eq!(200, response.status_code());
+        assert_eq!(1, provider.count.get());
+        assert_eq!(2, transport.requests.borrow().len());
+
+        // 同じディレクトリの下には、保存した認証情報を最初から付けて送る
+        let response = fetcher
+            .fetch(get("http://example.com/docs/b"))
+            .expect("failed to fetch");
+        assert_eq!(200, response.status_code());
+        assert_eq!(1, provider.count.get());
+        assert_eq!(3, transport.requests.borrow().len());
+
+        // 別のディレクトリでも、同じ保護空間の401には保存した認証情報で答える
+        let response = fetcher
+            .fetch(get("http://example.com/images/c"))
+            .expect("failed to fetch");
+        assert_eq!(200, response.status_code());
+        assert_eq!(1, provider.count.get());
+        assert_eq!(5, transport.requests.borrow().len());
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        // 認証情報が用意されない場合は401をそのまま返す
+        let (transport, provider, fetcher) = protected_fetcher(None);
+        let mut chunks = Vec::new();
+        let response = fetcher
+            .fetch_streaming(get("http://example.com/"), &mut |_, chunk| {
+                chunks.push(String::from_utf8_lossy(chunk).to_string())
+            })
+            .expect("failed to fetch");
+        assert_eq!(401, response.status_code());
+        assert_eq!(vec!["denied"], chunks);
+        assert_eq!(1, provider.count.get());
+        assert_eq!(1, transport.requests.borrow().len());
+
+        // 間違った認証情報は1回だけ試し、保存しない
+        let (transport, provider, fetcher) =
+            protected_fetcher(Some(Credentials::new("user", "wrong")));
+        let response = fetcher
+            .fetch(get("http://example.com/"))
+            .expect("failed to fetch");
+        assert_eq!(401, response.status_code());
+        assert_eq!(1, provider.count.get());
+        assert_eq!(2, transport.requests.borrow().len());
+        assert!(fetcher
+            .auth_cache
+            .as_ref()
+            .is_some_and(|c| c.borrow().is_empty()));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::http::header::HeaderMap;
+use crate::http::parser::parse_header;
+use crate::http::{Header, HttpParseError};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-6.3
+// レスポンスのボディの長さをどのように決定するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFraming {
+    NoBody,               // 1xx、204、304のレスポンスはボディを持たない
+    ContentLength(usize), // Content-Lengthで指定されたバイト数
+    Chunked,              // Transfer-Encoding: chunked
+    CloseDelimited,       // 接続が閉じられるまで
+}
+
+impl BodyFraming {
+    pub fn from_headers(status_code: u32, headers: &HeaderMap) -> Result<Self, HttpParseError> {
+        if (100..200).contains(&status_code) || status_code == 204 || status_code == 304 {
+            return Ok(BodyFraming::NoBody);
+        }
+
+        let transfer_encoding = headers.get_all("Transfer-Encoding");
+        let content_length = headers.get_all("Content-Length");
+
+        // Transfer-EncodingとContent-Lengthの両方がある場合はリクエストスマグリングの恐れがあるためエラーにする
+        if !transfer_encoding.is_empty() && !content_length.is_empty() {
+            return Err(HttpParseError::ConflictingFraming);
+        }
+
+        if !transfer_encoding.is_empty() {
+            // 最後に適用されたコーディングがchunkedのときのみchunkedとして解釈できる
+            let last_coding = transfer_encoding
+                .iter()
+                .flat_map(|v| v.split(','))
+                .map(|c| c.trim())
+                .rfind(|c| !c.is_empty())
+                .unwrap_or("");
+            if last_coding.eq_ignore_ascii_case("chunked") {
+                return Ok(BodyFraming::Chunked);
+            }
+            return Ok(BodyFraming::CloseDelimited);
+        }
+
+        if !content_length.is_empty() {
+            return Ok(BodyFraming::ContentLength(parse_content_length(
+                &content_length,
+            )?));
+        }
+
+        Ok(BodyFraming::CloseDelimited)
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-8.6
+// Content-Length: 42, 42 のように同じ値が複数ある場合は1つの値として扱い、異なる値がある場合はエラーにする
+pub(crate) fn parse_content_length(values: &[String]) -> Result<usize, HttpParseError> {
+    let mut length: Option<usize> = None;
+    for value in values.iter().flat_map(|v| v.split(',')) {
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(HttpParseError::InvalidContentLength(value.to_string()));
+        }
+        let parsed: usize = value
+            .parse()
+            .map_err(|_| HttpParseError::InvalidContentLength(value.to_string()))?;
+        match length {
+            Some(l) if l != parsed => return Err(HttpParseError::ConflictingFraming),
+            _ => length = Some(parsed),
+        }
+    }
+    length.ok_or_else(|| HttpParseError::InvalidContentLength(String::new()))
+}
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-7.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkState {
+    Size,        // チャンクサイズの行を読んでいる
+    Data(usize), // チャンクのデータを読んでいる。値は残りのバイト数
+    DataEnd,     // チャンクのデータの後のCRLFを読んでいる
+    Trailer,     // 最後のチャンクの後のトレーラーを読んでいる
+    Done,
+}
+
+// ボディのバイト列を少しずつ受け取り、フレーミングを取り除いたボディを出力する
+#[derive(Debug, Clone)]
+pub struct BodyDecoder {
+    framing: BodyFraming,
+    received: usize, // ContentLengthのときに受け取ったバイト数
+    chunk: ChunkState,
+    line: Vec<u8>, // chunkedのときに行の途中までを保存しておくバッファ
+    trailers: Vec<Header>,
+}
+
+impl BodyDecoder {
+    pub fn new(framing: BodyFraming) -> Self {
+        Self {
+            framing,
+            received: 0,
+            chunk: ChunkState::Size,
+            line: Vec::new(),
+            trailers: Vec::new(),
+        }
+    }
+
+    pub fn framing(&self) -> BodyFraming {
+        self.framing
+    }
+
+    // chunkedの最後に送られてくるトレーラーのヘッダー
+    pub fn trailers(&self) -> HeaderMap {
+        HeaderMap::from(self.trailers.clone())
+    }
+
+    // ボディをすべて受け取ったかどうか。CloseDelimitedの場合は接続が閉じられるまで完了しない
+    pub fn is_complete(&self) -> bool {
+        match self.framing {
+            BodyFraming::NoBody => true,
+            BodyFraming::ContentLength(length) => self.received == length,
+            BodyFraming::Chunked => self.chunk == ChunkState::Done,
+            BodyFraming::CloseDelimited => false,
+        }
+    }
+
+    // inputを処理してボディのデータをoutputに追加し、消費したバイト数を返す
+    // メッセージの終わり以降のバイト（次のレスポンスなど）は消費しない
+    pub fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<usize, HttpParseError> {
+        match self.framing {
+            BodyFraming::NoBody => Ok(0),
+            BodyFraming::ContentLength(length) => {
+                let n = input.len().min(length - self.received);
+                output.extend_from_slice(&input[..n]);
+                self.received += n;
+                Ok(n)
+            }
+            BodyFraming::Chunked => self.feed_chunked(input, output),
+            BodyFraming::CloseDelimited => {
+                output.extend_from_slice(input);
+                Ok(input.len())
+            }
+        }
+    }
+
+    // 接続が閉じられたときに呼び出す。ボディが途中で終わっている場合はエラーを返す
+    pub fn finish(&self) -> Result<(), HttpParseError> {
+        if self.framing == BodyFraming::CloseDelimited || self.is_complete() {
+            return Ok(());
+        }
+        Err(HttpParseError::IncompleteBody)
+    }
+
+    fn feed_chunked(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<usize, HttpParseError> {
+        let mut pos = 0;
+        while pos < input.len() && self.chunk != ChunkState::Done {
+            if let ChunkState::Data(remaining) = self.chunk {
+                let n = (input.len() - pos).min(remaining);
+                output.extend_from_slice(&input[pos..pos + n]);
+                pos += n;
+                self.chunk = if remaining == n {
+                    ChunkState::DataEnd
+                } else {
+                    ChunkState::Data(remaining - n)
+                };
+                continue;
+            }
+
+            // Data以外の状態は行単位で処理するので、改行が出てくるまでバッファに貯める
+            let b = input[pos];
+            pos += 1;
+            if b != b'\n' {
+                self.line.push(b);
+                continue;
+            }
+            let mut line = core::mem::take(&mut self.line);
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+
+            match self.chunk {
+                // chunk = chunk-size [ chunk-ext ] CRLF chunk-data CRLF
+                ChunkState::Size => {
+                    let size = parse_chunk_size(&line)?;
+                    self.chunk = if size == 0 {
+                        ChunkState::Trailer
+                    } else {
+                        ChunkState::Data(size)
+                    };
+                }
+                ChunkState::DataEnd => {
+                    if !line.is_empty() {
+                        return Err(HttpParseError::InvalidChunk(latin1(&line)));
+                    }
+                    self.chunk = ChunkState::Size;
+                }
+                // trailer-section = *( field-line CRLF )
+                ChunkState::Trailer => {
+                    if line.is_empty() {
+                        self.chunk = ChunkState::Done;
+                    } else {
+                        self.trailers.push(parse_header(&line)?);
+                    }
+                }
+                ChunkState::Data(_) | ChunkState::Done => unreachable!(),
+            }
+        }
+        Ok(pos)
+    }
+}
+
+// chunk-size = 1*HEXDIG、chunk-ext = *( BWS ";" BWS chunk-ext-name [ BWS "=" BWS chunk-ext-val ] )
+// 拡張は使用しないので読み飛ばす
+fn parse_chunk_size(line: &[u8]) -> Result<usize, HttpParseError> {
+    let size_end = line.iter().position(|&b| b == b';').unwrap_or(line.len());
+    let size = latin1(&line[..size_end]);
+    let size = size.trim_matches(|c| c == ' ' || c == '\t');
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(HttpParseError::InvalidChunk(latin1(line)));
+    }
+    usize::from_str_radix(size, 16).map_err(|_| HttpParseError::InvalidChunk(latin1(line)))
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn headers(list: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in list {
+            headers.append(name, value);
+        }
+        headers
+    }
+
+    #[test]
+    fn test_framing_from_headers() {
+        assert_eq!(
+            Ok(BodyFraming::CloseDelimited),
+            BodyFraming::from_headers(200, &HeaderMap::new())
+        );
+        assert_eq!(
+            Ok(BodyFraming::NoBody),
+            BodyFraming::from_headers(304, &headers(&[("Content-Length", "10")]))
+        );
+        assert_eq!(
+            Ok(BodyFraming::ContentLength(10)),
+            BodyFraming::from_headers(200, &headers(&[("content-length", "10")]))
+        );
+        assert_eq!(
+            Ok(BodyFraming::ContentLength(10)),
+            BodyFraming::from_headers(200, &headers(&[("Content-Length", "10, 10")]))
+        );
+        assert_eq!(
+            Ok(BodyFraming::Chunked),
+            BodyFraming::from_headers(200, &headers(&[("Transfer-Encoding", "gzip, Chunked")]))
+        );
+        assert_eq!(
+            Ok(BodyFraming::CloseDelimited),
+            BodyFraming::from_headers(200, &headers(&[("Transfer-Encoding", "gzip")]))
+        );
+    }
+
+    #[test]
+    fn test_conflicting_framing() {
+        assert_eq!(
+            Err(HttpParseError::ConflictingFraming),
+            BodyFraming::from_headers(
+                200,
+                &headers(&[("Content-Length", "3"), ("Transfer-Encoding", "chunked")])
+            )
+        );
+        assert_eq!(
+            Err(HttpParseError::ConflictingFraming),
+            BodyFraming::from_headers(
+                200,
+                &headers(&[("Content-Length", "3"), ("Content-Length", "4")])
+            )
+        );
+        assert_eq!(
+            Err(HttpParseError::InvalidContentLength("-1".to_string())),
+            BodyFraming::from_headers(200, &headers(&[("Content-Length", "-1")]))
+        );
+    }
+
+    #[test]
+    fn test_content_length() {
+        let mut decoder = BodyDecoder::new(BodyFraming::ContentLength(5));
+        let mut body = Vec::new();
+        assert_eq!(Ok(3), decoder.feed(b"abc", &mut body));
+        assert!(!decoder.is_complete());
+        assert_eq!(Err(HttpParseError::IncompleteBody), decoder.finish());
+        // 5バイトを超えた分は消費しない
+        assert_eq!(Ok(2), decoder.feed(b"deHTTP/1.1", &mut body));
+        assert!(decoder.is_complete());
+        assert_eq!(b"abcde".to_vec(), body);
+    }
+
+    #[test]
+    fn test_chunked() {
+        let input = b"4\r\nWiki\r\n5;ext=1\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\n";
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        let mut body = Vec::new();
+        assert_eq!(Ok(input.len()), decoder.feed(input, &mut body));
+        assert!(decoder.is_complete());
+        assert_eq!(b"Wikipedia in\r\n\r\nchunks.".to_vec(), body);
+        assert_eq!(Some("never".to_string()), decoder.trailers().get("expires"));
+    }
+
+    #[test]
+    fn test_chunked_split_input() {
+        let input = b"3\r\nabc\r\n10\r\n0123456789abcdef\r\n0\r\n\r\n";
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        let mut body = Vec::new();
+        // 1バイトずつ渡しても同じ結果になる
+        for b in input.iter() {
+            assert_eq!(Ok(1), decoder.feed(&[*b], &mut body));
+        }
+        assert!(decoder.is_complete());
+        assert_eq!(b"abc0123456789abcdef".to_vec(), body);
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        assert_eq!(
+            Err(HttpParseError::InvalidChunk("xyz".to_string())),
+            decoder.feed(b"xyz\r\n", &mut vec![])
+        );
+
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        assert_eq!(
+            Err(HttpParseError::InvalidChunk("de".to_string())),
+            decoder.feed(b"3\r\nabcde\r\n", &mut vec![])
+        );
+
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        assert!(decoder.feed(b"3\r\nabc\r\n", &mut vec![]).is_ok());
+        assert_eq!(Err(HttpParseError::IncompleteBody), decoder.finish());
+    }
+}
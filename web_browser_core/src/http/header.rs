@@ -0,0 +1,337 @@
+use crate::alloc::string::ToString;
+use crate::http::framing::parse_content_length;
+use crate::http::Header;
+use crate::mime::MimeType;
+use crate::url::percent_encoding::percent_decode;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-5
+// ヘッダーの集合。名前の大文字小文字は区別せず、同じ名前のヘッダーを複数持てる
+// 挿入した順番を保つため、Vecで保持する
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HeaderMap {
+    headers: Vec<Header>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self {
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    // 同じ名前のヘッダーがあっても置き換えずに末尾に追加する（Set-Cookieなど）
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.headers
+            .push(Header::new(name.to_string(), value.to_string()));
+    }
+
+    // 同じ名前のヘッダーをすべて削除してから追加する
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.remove(name);
+        self.append(name, value);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.headers.retain(|h| !h.name.eq_ignore_ascii_case(name));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|h| h.name.eq_ignore_ascii_case(name))
+    }
+
+    // 最初に見つかったヘッダーの値を返す
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.clone())
+    }
+
+    // 同じ名前のヘッダーの値をすべて、受け取った順番で返す
+    pub fn get_all(&self, name: &str) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.clone())
+            .collect()
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-5.6.1
+    // Connectionのようにカンマ区切りのトークンを値に持つヘッダーが、tokenを含むかどうか
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.get_all(name)
+            .iter()
+            .flat_map(|v| v.split(','))
+            .any(|t| t.trim().eq_ignore_ascii_case(token))
+    }
+
+    // 挿入した順番でヘッダーを返す
+    pub fn iter(&self) -> core::slice::Iter<'_, Header> {
+        self.headers.iter()
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-8.3
+    pub fn content_type(&self) -> Option<MimeType> {
+        self.get("Content-Type").and_then(|v| MimeType::parse(&v))
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-8.6
+    // 値が数字でない場合や、複数の異なる値がある場合はNoneを返す
+    pub fn content_length(&self) -> Option<usize> {
+        parse_content_length(&self.get_all("Content-Length")).ok()
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-10.2.2
+    // 相対URLの場合があるので、解決はリクエストしたURLをもとに呼び出し側で行う
+    pub fn location(&self) -> Option<String> {
+        self.get("Location")
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc6266#section-4
+    pub fn content_disposition(&self) -> Option<ContentDisposition> {
+        self.get("Content-Disposition")
+            .and_then(|v| ContentDisposition::parse(&v))
+    }
+}
+
+impl From<Vec<Header>> for HeaderMap {
+    fn from(headers: Vec<Header>) -> Self {
+        Self { headers }
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = &'a Header;
+    type IntoIter = core::slice::Iter<'a, Header>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.headers.iter()
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc6266#section-4.1
+// disposition-type *( ";" disposition-parm )
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDisposition {
+    disposition_type: String, // 小文字にしたinline、attachmentなど
+    filename: Option<String>,
+}
+
+impl ContentDisposition {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = split_parameters(value).into_iter();
+        let disposition_type = parts.next()?.trim().to_ascii_lowercase();
+        if !is_token(&disposition_type) {
+            return None;
+        }
+
+        let mut filename = None;
+        let mut extended_filename = None;
+        for part in parts {
+            let (name, value) = match part.split_once('=') {
+                Some((n, v)) => (n.trim().to_ascii_lowercase(), v.trim()),
+                None => continue,
+            };
+            if name == "filename" && filename.is_none() {
+                filename = Some(unquote(value));
+            } else if name == "filename*" && extended_filename.is_none() {
+                extended_filename = decode_ext_value(value);
+            }
+        }
+
+        Some(Self {
+            disposition_type,
+            // https://www.rfc-editor.org/rfc/rfc6266#section-4.3
+            // 両方ある場合はfilename*を優先する
+            filename: extended_filename.or(filename),
+        })
+    }
+
+    pub fn disposition_type(&self) -> String {
+        self.disposition_type.clone()
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc6266#section-4.2
+    // 知らない種類はattachmentとして扱う
+    pub fn is_attachment(&self) -> bool {
+        self.disposition_type != "inline"
+    }
+
+    pub fn filename(&self) -> Option<String> {
+        self.filename.clone()
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc8187#section-3.2
+// charset'language'value-chars の形式。UTF-8以外の文字コードは扱わない
+fn decode_ext_value(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+    if !charset.eq_ignore_ascii_case("utf-8") {
+        return None;
+    }
+    String::from_utf8(percent_decode(encoded.as_bytes())).ok()
+}
+
+// ; で区切る。ただし引用符で囲まれた中の ; は区切りとみなさない
+fn split_parameters(value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == ';' && !quoted {
+            parts.push(core::mem::take(&mut current));
+            continue;
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-5.6.4
+// quoted-string = DQUOTE *( qdtext / quoted-pair ) DQUOTE
+fn unquote(value: &str) -> String {
+    let inner = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return value.to_string(),
+    };
+    let mut result = String::new();
+    let mut escaped = false;
+    for c in inner.chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        result.push(c);
+    }
+    result
+}
+
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(crate::http::parser::is_token_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_case_insensitive() {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text/html");
+        assert_eq!(Some("text/html".to_string()), headers.get("content-type"));
+        assert_eq!(Some("text/html".to_string()), headers.get("CONTENT-TYPE"));
+        assert!(headers.contains("content-TYPE"));
+        assert_eq!(None, headers.get("Content-Length"));
+    }
+
+    #[test]
+    fn test_multiple_values() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Date", "xx");
+        headers.append("set-cookie", "b=2");
+        assert_eq!(Some("a=1".to_string()), headers.get("Set-Cookie"));
+        assert_eq!(
+            vec!["a=1".to_string(), "b=2".to_string()],
+            headers.get_all("Set-Cookie")
+        );
+        assert_eq!(
+            vec!["Set-Cookie", "Date", "set-cookie"],
+            headers.iter().map(|h| h.name()).collect::<Vec<_>>()
+        );
+
+        headers.insert("SET-COOKIE", "c=3");
+        assert_eq!(vec!["c=3".to_string()], headers.get_all("set-cookie"));
+        assert_eq!(2, headers.len());
+        headers.remove("date");
+        assert_eq!(1, headers.len());
+    }
+
+    #[test]
+    fn test_content_type() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "Content-Type",
+            "Text/HTML; Charset=\"Shift_JIS\"; boundary=\"a;b\"",
+        );
+        let content_type = headers
+            .content_type()
+            .expect("failed to parse content-type");
+        assert_eq!("text/html", content_type.essence());
+        assert_eq!(Some("Shift_JIS".to_string()), content_type.charset());
+        assert_eq!(Some("a;b".to_string()), content_type.parameter("boundary"));
+
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text");
+        assert_eq!(None, headers.content_type());
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "Content-Disposition",
+            "Attachment; filename=\"a;b.txt\"; filename*=UTF-8''%E8%B3%87%E6%96%99.txt",
+        );
+        let disposition = headers
+            .content_disposition()
+            .expect("failed to parse content-disposition");
+        assert_eq!("attachment", disposition.disposition_type());
+        assert!(disposition.is_attachment());
+        assert_eq!(Some("資料.txt".to_string()), disposition.filename());
+
+        let disposition =
+            ContentDisposition::parse("inline; filename=report.pdf").expect("failed to parse");
+        assert!(!disposition.is_attachment());
+        assert_eq!(Some("report.pdf".to_string()), disposition.filename());
+        assert_eq!(None, ContentDisposition::parse("; filename=x"));
+    }
+
+    #[test]
+    fn test_content_length() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, headers.content_length());
+        headers.append("Content-Length", "42");
+        assert_eq!(Some(42), headers.content_length());
+        headers.append("content-length", "42");
+        assert_eq!(Some(42), headers.content_length());
+        headers.append("Content-Length", "42, 42");
+        assert_eq!(Some(42), headers.content_length());
+        headers.append("Content-Length", "43");
+        assert_eq!(None, headers.content_length());
+        headers.insert("Content-Length", "abc");
+        assert_eq!(None, headers.content_length());
+    }
+
+    #[test]
+    fn test_location() {
+        let mut headers = HeaderMap::new();
+        headers.append("location", "/next");
+        assert_eq!(Some("/next".to_string()), headers.location());
+    }
+}
@@ -0,0 +1,338 @@
+pub mod auth;
+pub mod cache;
+pub mod compression;
+pub mod connection;
+pub mod cookie;
+pub mod fetch;
+pub mod framing;
+pub mod header;
+pub mod parser;
+pub mod pool;
+pub mod proxy;
+pub mod request;
+pub mod transport;
+
+use crate::alloc::string::ToString;
+use crate::error::Error;
+use crate::http::header::HeaderMap;
+use crate::http::parser::ResponseParser;
+use crate::url::{Url, UrlError};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    version: String,
+    status_code: u32,
+    reason: String,
+    headers: HeaderMap,
+    body: Vec<u8>,
+    trailers: HeaderMap, // chunkedのボディの後に送られてくるヘッダー
+    url_list: Vec<Url>,  // リクエストしたURLと、リダイレクトでたどったURL
+}
+
+impl HttpResponse {
+    // https://www.rfc-editor.org/rfc/rfc9112#section-2.1
+    // TCPストリームから受け取ったバイト列をHTTPレスポンスとして解釈する
+    // ボディはContent-Lengthまたはchunkedのフレーミングに従って取り出す
+    pub fn new(raw_response: &[u8]) -> Result<Self, HttpParseError> {
+        let mut parser = ResponseParser::new();
+        parser.feed(raw_response)?;
+        parser.finish()
+    }
+
+    // ネットワークを使わずに用意したレスポンス（data: URLやローカルのファイルなど）を作る
+    pub fn from_parts(status_code: u32, reason: &str, headers: HeaderMap, body: Vec<u8>) -> Self {
+        Self {
+            version: "HTTP/1.1".to_string(),
+            status_code,
+            reason: reason.to_string(),
+            headers,
+            body,
+            trailers: HeaderMap::new(),
+            url_list: Vec::new(),
+        }
+    }
+
+    pub fn version(&self) -> String {
+        self.version.clone()
+    }
+
+    pub fn status_code(&self) -> u32 {
+        self.status_code
+    }
+
+    pub fn reason(&self) -> String {
+        self.reason.clone()
+    }
+
+    pub fn headers(&self) -> HeaderMap {
+        self.headers.clone()
+    }
+
+    pub fn body(&self) -> Vec<u8> {
+        self.body.clone()
+    }
+
+    pub fn trailers(&self) -> HeaderMap {
+        self.trailers.clone()
+    }
+
+    // https://fetch.spec.whatwg.org/#concept-response-url
+    // レスポンスのURL。リダイレクトした場合は最後にリクエストしたURLになる
+    pub fn url(&self) -> Option<Url> {
+        self.url_list.last().cloned()
+    }
+
+    pub fn url_list(&self) -> Vec<Url> {
+        self.url_list.clone()
+    }
+
+    pub fn set_url_list(&mut self, url_list: Vec<Url>) {
+        self.url_list = url_list;
+    }
+
+    // ボディをUTF-8の文字列として返す。不正なバイト列はU+FFFDに置き換える
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    // 名前の大文字小文字は区別しない。同じ名前のヘッダーが複数ある場合は最初の値を返す
+    pub fn header_value(&self, name: &str) -> Option<String> {
+        self.headers.get(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    name: String,
+    value: String,
+}
+
+impl Header {
+    pub fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpParseError {
+    Incomplete,                     // ヘッダーの終わりを示す空行が見つからない
+    InvalidStatusLine(String),      // ステータスラインの形式が不正
+    InvalidVersion(String),         // HTTP/1.1 のような形式でない
+    InvalidStatusCode(String),      // 3桁の数字でない
+    InvalidHeader(String),          // name: value の形式でない
+    InvalidContentLength(String),   // Content-Lengthが数字でない
+    ConflictingFraming,             // ボディの長さの指定が矛盾している
+    InvalidChunk(String),           // チャンクの形式が不正
+    IncompleteBody,                 // ボディの途中で接続が閉じられた
+    InvalidContentEncoding(String), // Content-Encodingに従って展開できない
+}
+
+impl Display for HttpParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HttpParseError::Incomplete => write!(f, "incomplete http response"),
+            HttpParseError::InvalidStatusLine(s) => write!(f, "invalid status line: {}", s),
+            HttpParseError::InvalidVersion(s) => write!(f, "invalid http version: {}", s),
+            HttpParseError::InvalidStatusCode(s) => write!(f, "invalid status code: {}", s),
+            HttpParseError::InvalidHeader(s) => write!(f, "invalid header: {}", s),
+            HttpParseError::InvalidContentLength(s) => write!(f, "invalid content-length: {}", s),
+            HttpParseError::ConflictingFraming => write!(f, "conflicting message framing"),
+            HttpParseError::InvalidChunk(s) => write!(f, "invalid chunk: {}", s),
+            HttpParseError::IncompleteBody => write!(f, "incomplete http response body"),
+            HttpParseError::InvalidContentEncoding(s) => {
+                write!(f, "invalid content encoding: {}", s)
+            }
+        }
+    }
+}
+
+impl From<HttpParseError> for Error {
+    fn from(e: HttpParseError) -> Self {
+        Error::Http(HttpError::Parse(e))
+    }
+}
+
+// HTTPのリクエストを組み立てたり、レスポンスをたどったりするときのエラー
+// 接続や送受信の失敗はNetworkErrorで表す
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpError {
+    Parse(HttpParseError),      // レスポンスを解釈できない
+    UnsupportedScheme(String),  // http、https以外のURLへのリクエスト
+    InvalidHeaderName(String),  // トークンでないヘッダーの名前
+    InvalidHeaderValue(String), // CR、LF、NULを含むヘッダーの名前
+    TooManyRedirects(usize),    // リダイレクトの上限
+    InvalidRedirect { location: String, error: UrlError },
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HttpError::Parse(e) => write!(f, "{}", e),
+            HttpError::UnsupportedScheme(s) => write!(f, "unsupported scheme: {}", s),
+            HttpError::InvalidHeaderName(name) => write!(f, "invalid header name: {:?}", name),
+            HttpError::InvalidHeaderValue(name) => write!(f, "invalid header value for {}", name),
+            HttpError::TooManyRedirects(max) => {
+                write!(f, "too many redirects (more than {})", max)
+            }
+            HttpError::InvalidRedirect { location, error } => {
+                write!(f, "invalid redirect location {}: {}", location, error)
+            }
+        }
+    }
+}
+
+impl From<HttpError> for Error {
+    fn from(e: HttpError) -> Self {
+        Error::Http(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_status_line_only() {
+        let raw = b"HTTP/1.1 200 OK\n\n";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.reason(), "OK");
+    }
+
+    #[test]
+    fn test_one_header() {
+        let raw = b"HTTP/1.1 200 OK\nDate:xx xx xx\n\n";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.reason(), "OK");
+        assert_eq!(res.header_value("Date"), Some("xx xx xx".to_string()));
+    }
+
+    #[test]
+    fn test_two_headers() {
+        let raw = b"HTTP/1.1 200 OK\nDate:xx xx xx\nContent-Length: 42\n\n0123456789abcdefghijklmnopqrstuvwxyzABCDEF";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.reason(), "OK");
+        assert_eq!(res.header_value("Date"), Some("xx xx xx".to_string()));
+        assert_eq!(res.header_value("Content-Length"), Some("42".to_string()));
+    }
+
+    #[test]
+    fn test_body() {
+        let raw = b"HTTP/1.1 200 OK\nDate:xx xx xx\n\nbody message";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.reason(), "OK");
+        assert_eq!(res.header_value("Date"), Some("xx xx xx".to_string()));
+        assert_eq!(res.body_text(), "body message".to_string());
+    }
+
+    #[test]
+    fn test_crlf() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 13\r\n\r\n<p>\r\nhi</p>\r\n";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.reason(), "OK");
+        assert_eq!(
+            res.header_value("Content-Type"),
+            Some("text/html".to_string())
+        );
+        assert_eq!(res.header_value("Content-Length"), Some("13".to_string()));
+        assert_eq!(res.body(), b"<p>\r\nhi</p>\r\n".to_vec());
+    }
+
+    #[test]
+    fn test_no_reason_phrase() {
+        let raw = b"HTTP/1.1 204\r\n\r\n";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.status_code(), 204);
+        assert_eq!(res.reason(), "");
+    }
+
+    #[test]
+    fn test_binary_body() {
+        let mut raw = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0x89, 0x50, 0x4e, 0x47, 0xff, 0x00]);
+        let res = HttpResponse::new(&raw).expect("Failed to parse http response");
+        assert_eq!(res.body(), [0x89, 0x50, 0x4e, 0x47, 0xff, 0x00].to_vec());
+    }
+
+    #[test]
+    fn test_folded_header() {
+        let raw = b"HTTP/1.1 200 OK\r\nX-Long: first\r\n  second\r\n\r\n";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(res.header_value("X-Long"), Some("first second".to_string()));
+    }
+
+    #[test]
+    fn test_header_map() {
+        let raw = b"HTTP/1.1 200 OK\r\ncontent-type: text/html; charset=utf-8\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nContent-Length: 0\r\n\r\n";
+        let res = HttpResponse::new(raw).expect("Failed to parse http response");
+        assert_eq!(
+            res.header_value("Content-Type"),
+            Some("text/html; charset=utf-8".to_string())
+        );
+        assert_eq!(
+            res.headers().get_all("set-cookie"),
+            vec!["a=1".to_string(), "b=2".to_string()]
+        );
+        assert_eq!(
+            res.headers().content_type().map(|c| c.essence()),
+            Some("text/html".to_string())
+        );
+        assert_eq!(res.headers().content_length(), Some(0));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let raw = b"HTTP/1.1 200 OK";
+        assert_eq!(
+            HttpResponse::new(raw).err(),
+            Some(HttpParseError::Incomplete)
+        );
+    }
+
+    #[test]
+    fn test_invalid_status_line() {
+        assert_eq!(
+            HttpResponse::new(b"HTTP/1.1\r\n\r\n").err(),
+            Some(HttpParseError::InvalidStatusLine("HTTP/1.1".to_string()))
+        );
+        assert_eq!(
+            HttpResponse::new(b"ICY 200 OK\r\n\r\n").err(),
+            Some(HttpParseError::InvalidVersion("ICY".to_string()))
+        );
+        assert_eq!(
+            HttpResponse::new(b"HTTP/1.1 OK\r\n\r\n").err(),
+            Some(HttpParseError::InvalidStatusCode("OK".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        assert_eq!(
+            HttpResponse::new(b"HTTP/1.1 200 OK\r\nno colon\r\n\r\n").err(),
+            Some(HttpParseError::InvalidHeader("no colon".to_string()))
+        );
+        assert_eq!(
+            HttpResponse::new(b"HTTP/1.1 200 OK\r\nName : value\r\n\r\n").err(),
+            Some(HttpParseError::InvalidHeader("Name : value".to_string()))
+        );
+    }
+}
@@ -0,0 +1,501 @@
+use crate::alloc::string::ToString;
+use crate::http::compression::ContentDecoder;
+use crate::http::framing::{BodyDecoder, BodyFraming};
+use crate::http::header::HeaderMap;
+use crate::http::request::Method;
+use crate::http::{Header, HttpParseError, HttpResponse};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ステータスラインとヘッダー
+#[derive(Debug, Clone)]
+struct ResponseHead {
+    version: String,
+    status_code: u32,
+    reason: String,
+    headers: HeaderMap,
+}
+
+// TCPストリームから受け取ったバイト列を少しずつ渡してHTTPレスポンスを組み立てる
+// ヘッダーを読み終わった時点でボディのフレーミングを決め、ボディの終わりを判断する
+#[derive(Debug, Clone)]
+pub struct ResponseParser {
+    buf: Vec<u8>, // ヘッダーを読み終わるまで受け取ったバイト列を保存しておく
+    head: Option<ResponseHead>,
+    decoder: Option<BodyDecoder>,
+    content_decoder: Option<ContentDecoder>,
+    body: Vec<u8>,
+    yielded: usize,        // body_chunkで返したボディのバイト数
+    head_request: bool,    // HEADリクエストへのレスポンスはボディを持たない
+    connect_request: bool, // CONNECTへの2xxのレスポンスの後はトンネルになるので、ボディを持たない
+    overrun: bool,         // メッセージの終わり以降にバイトを受け取った
+}
+
+impl ResponseParser {
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            head: None,
+            decoder: None,
+            content_decoder: None,
+            body: Vec::new(),
+            yielded: 0,
+            head_request: false,
+            connect_request: false,
+            overrun: false,
+        }
+    }
+
+    // 送ったリクエストのメソッドによってボディの有無が変わるため、メソッドを指定して作る
+    pub fn for_method(method: Method) -> Self {
+        let mut parser = Self::new();
+        parser.head_request = method == Method::Head;
+        parser.connect_request = method == Method::Connect;
+        parser
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> Result<(), HttpParseError> {
+        if self.decoder.is_some() {
+            return self.feed_body(data);
+        }
+
+        self.buf.extend_from_slice(data);
+        let (head, consumed) = match parse_head(&self.buf)? {
+            Some(result) => result,
+            None => return Ok(()),
+        };
+
+        // https://www.rfc-editor.org/rfc/rfc9112#section-6.3
+        let tunnel = self.connect_request && (200..300).contains(&head.status_code);
+        let framing = if self.head_request || tunnel {
+            BodyFraming::NoBody
+        } else {
+            BodyFraming::from_headers(head.status_code, &head.headers)?
+        };
+        self.decoder = Some(BodyDecoder::new(framing));
+        self.content_decoder = Some(
+            ContentDecoder::from_headers(&head.headers)
+                .map_err(HttpParseError::InvalidContentEncoding)?,
+        );
+        self.head = Some(head);
+
+        let rest = self.buf.split_off(consumed);
+        self.buf.clear();
+        self.feed_body(&rest)
+    }
+
+    // フレーミングを取り除いてから、Content-Encodingに従って展開する
+    fn feed_body(&mut self, data: &[u8]) -> Result<(), HttpParseError> {
+        let (decoder, content_decoder) = match (&mut self.decoder, &mut self.content_decoder) {
+            (Some(decoder), Some(content_decoder)) => (decoder, content_decoder),
+            _ => return Ok(()),
+        };
+        let mut chunk = Vec::new();
+        if decoder.feed(data, &mut chunk)? < data.len() {
+            self.overrun = true;
+        }
+        content_decoder
+            .feed(&chunk, &mut self.body)
+            .map_err(HttpParseError::InvalidContentEncoding)
+    }
+
+    // レスポンスをすべて受け取ったかどうか。trueになったら接続からの読み込みをやめてよい
+    pub fn is_complete(&self) -> bool {
+        self.decoder.as_ref().is_some_and(|d| d.is_complete())
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9112#section-9.3
+    // レスポンスを受け取った後に、同じ接続を次のリクエストに使えるかどうか
+    // ボディの終わりが接続の切断でしかわからない場合や、余計なバイトを受け取った場合は使えない
+    pub fn keep_alive(&self) -> bool {
+        let head = match &self.head {
+            Some(head) => head,
+            None => return false,
+        };
+        if !self.is_complete() || self.overrun || head.headers.has_token("Connection", "close") {
+            return false;
+        }
+        // HTTP/1.0はConnection: keep-aliveがある場合だけ接続を維持する
+        head.version != "HTTP/1.0" || head.headers.has_token("Connection", "keep-alive")
+    }
+
+    // ステータスラインとヘッダーを読み終わっていれば、ボディが空のレスポンスとして返す
+    pub fn head(&self) -> Option<HttpResponse> {
+        self.head.as_ref().map(|head| HttpResponse {
+            version: head.version.clone(),
+            status_code: head.status_code,
+            reason: head.reason.clone(),
+            headers: head.headers.clone(),
+            body: Vec::new(),
+            trailers: HeaderMap::new(),
+            url_list: Vec::new(),
+        })
+    }
+
+    // 前回呼び出してから展開できたボディを返す
+    // 受け取った分から少しずつ処理するときに使う。finish()が返すレスポンスにはボディ全体が含まれる
+    pub fn body_chunk(&mut self) -> Vec<u8> {
+        let chunk = self.body[self.yielded..].to_vec();
+        self.yielded = self.body.len();
+        chunk
+    }
+
+    // 接続が閉じられた、またはis_complete()がtrueになったときに呼び出す
+    pub fn finish(self) -> Result<HttpResponse, HttpParseError> {
+        let (head, decoder, content_decoder) = match (self.head, self.decoder, self.content_decoder)
+        {
+            (Some(head), Some(decoder), Some(content_decoder)) => (head, decoder, content_decoder),
+            _ => return Err(HttpParseError::Incomplete),
+        };
+        decoder.finish()?;
+        content_decoder
+            .finish()
+            .map_err(HttpParseError::InvalidContentEncoding)?;
+
+        Ok(HttpResponse {
+            version: head.version,
+            status_code: head.status_code,
+            reason: head.reason,
+            headers: head.headers,
+            body: self.body,
+            trailers: decoder.trailers(),
+            url_list: Vec::new(),
+        })
+    }
+}
+
+impl Default for ResponseParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-2.1
+// ヘッダーの終わりを示す空行まで読めた場合は、ステータスラインとヘッダー、消費したバイト数を返す
+fn parse_head(input: &[u8]) -> Result<Option<(ResponseHead, usize)>, HttpParseError> {
+    let mut lines = LineReader::new(input);
+
+    // ステータスラインの前の空行は無視する
+    // https://www.rfc-editor.org/rfc/rfc9112#section-2.2
+    let status_line = loop {
+        match lines.next_line() {
+            Some([]) => continue,
+            Some(line) => break line,
+            None => return Ok(None),
+        }
+    };
+    let (version, status_code, reason) = parse_status_line(status_line)?;
+
+    // 空行が出てくるまでヘッダーとして解釈する
+    let mut headers: Vec<Header> = Vec::new();
+    loop {
+        let line = match lines.next_line() {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+
+        if line.is_empty() {
+            break;
+        }
+
+        // 空白から始まる行は前のヘッダーの値の続き（obs-fold）として扱う
+        // https://www.rfc-editor.org/rfc/rfc9112#section-5.2
+        if line[0] == b' ' || line[0] == b'\t' {
+            match headers.last_mut() {
+                Some(last) => {
+                    last.value.push(' ');
+                    last.value.push_str(latin1_to_string(line).trim());
+                    continue;
+                }
+                None => return Err(HttpParseError::InvalidHeader(latin1_to_string(line))),
+            }
+        }
+
+        headers.push(parse_header(line)?);
+    }
+
+    Ok(Some((
+        ResponseHead {
+            version,
+            status_code,
+            reason,
+            headers: HeaderMap::from(headers),
+        },
+        lines.pos,
+    )))
+}
+
+// バイト列を1行ずつ読み出す。行末のCRLFまたはLFは取り除く
+struct LineReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> LineReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    // 改行が見つからない場合は行が途中で終わっているとみなしNoneを返す
+    fn next_line(&mut self) -> Option<&'a [u8]> {
+        let rest = &self.input[self.pos..];
+        let end = rest.iter().position(|&b| b == b'\n')?;
+        self.pos += end + 1;
+
+        let line = &rest[..end];
+        Some(line.strip_suffix(b"\r").unwrap_or(line))
+    }
+}
+
+// ヘッダーの値はASCII以外が含まれる可能性があるので、1バイトを1文字（ISO-8859-1）として扱う
+fn latin1_to_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-4
+// status-line = HTTP-version SP status-code SP [ reason-phrase ]
+fn parse_status_line(line: &[u8]) -> Result<(String, u32, String), HttpParseError> {
+    let line = latin1_to_string(line);
+    let (version, rest) = match line.split_once(' ') {
+        Some((v, r)) => (v, r),
+        None => return Err(HttpParseError::InvalidStatusLine(line.clone())),
+    };
+
+    if !is_valid_version(version) {
+        return Err(HttpParseError::InvalidVersion(version.to_string()));
+    }
+
+    // 理由フレーズは省略されることがある（例: HTTP/1.1 200）
+    let (status_code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+    if status_code.len() != 3 || !status_code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(HttpParseError::InvalidStatusCode(status_code.to_string()));
+    }
+
+    Ok((
+        version.to_string(),
+        status_code
+            .parse()
+            .map_err(|_| HttpParseError::InvalidStatusCode(status_code.to_string()))?,
+        reason.to_string(),
+    ))
+}
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-2.3
+// HTTP-version = "HTTP" "/" DIGIT "." DIGIT
+fn is_valid_version(version: &str) -> bool {
+    let bytes = version.as_bytes();
+    bytes.len() == 8
+        && bytes.starts_with(b"HTTP/")
+        && bytes[5].is_ascii_digit()
+        && bytes[6] == b'.'
+        && bytes[7].is_ascii_digit()
+}
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-5
+// field-line = field-name ":" OWS field-value OWS
+pub fn parse_header(line: &[u8]) -> Result<Header, HttpParseError> {
+    let line = latin1_to_string(line);
+    let (name, value) = match line.split_once(':') {
+        Some((n, v)) => (n, v),
+        None => return Err(HttpParseError::InvalidHeader(line.clone())),
+    };
+
+    // フィールド名とコロンの間に空白を含めることはできない
+    if name.is_empty() || !name.bytes().all(is_token_char) {
+        return Err(HttpParseError::InvalidHeader(line.clone()));
+    }
+
+    Ok(Header::new(
+        name.to_string(),
+        value.trim_matches(|c| c == ' ' || c == '\t').to_string(),
+    ))
+}
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-5.6.2
+pub fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+// https://fetch.spec.whatwg.org/#http-whitespace
+pub fn is_http_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | ' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_in_pieces() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+        let mut parser = ResponseParser::new();
+        for b in raw.iter() {
+            assert!(!parser.is_complete());
+            parser.feed(&[*b]).expect("failed to feed");
+        }
+        assert!(parser.is_complete());
+        let res = parser.finish().expect("failed to parse http response");
+        assert_eq!(res.body(), b"hello".to_vec());
+    }
+
+    #[test]
+    fn test_content_length_ignores_extra_bytes() {
+        let mut parser = ResponseParser::new();
+        parser
+            .feed(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhiHTTP/1.1 200 OK\r\n")
+            .expect("failed to feed");
+        assert!(parser.is_complete());
+        let res = parser.finish().expect("failed to parse http response");
+        assert_eq!(res.body(), b"hi".to_vec());
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let feed = |raw: &[u8]| {
+            let mut parser = ResponseParser::new();
+            parser.feed(raw).expect("failed to feed");
+            parser.keep_alive()
+        };
+        assert!(feed(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi"));
+        assert!(feed(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n"
+        ));
+        assert!(feed(
+            b"HTTP/1.0 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\n"
+        ));
+        // ボディを受け取り終わっていない
+        assert!(!feed(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhi"));
+        // ボディの終わりが接続の切断でしかわからない
+        assert!(!feed(b"HTTP/1.1 200 OK\r\n\r\nhi"));
+        assert!(!feed(
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+        ));
+        assert!(!feed(b"HTTP/1.0 200 OK\r\nContent-Length: 0\r\n\r\n"));
+        assert!(!feed(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhiHTTP/1.1 200 OK\r\n"
+        ));
+    }
+
+    #[test]
+    fn test_connect_response() {
+        let mut parser = ResponseParser::for_method(Method::Connect);
+        parser
+            .feed(b"HTTP/1.1 200 Connection established\r\nContent-Length: 10\r\n\r\n")
+            .expect("failed to feed");
+        assert!(parser.is_complete());
+
+        // 2xx以外のレスポンスには通常どおりボディがある
+        let mut parser = ResponseParser::for_method(Method::Connect);
+        parser
+            .feed(b"HTTP/1.1 407 Proxy Authentication Required\r\nContent-Length: 2\r\n\r\n")
+            .expect("failed to feed");
+        assert!(!parser.is_complete());
+    }
+
+    #[test]
+    fn test_head_response() {
+        let mut parser = ResponseParser::for_method(Method::Head);
+        parser
+            .feed(b"HTTP/1.1 200 OK\r\nContent-Length: 1024\r\n\r\n")
+            .expect("failed to feed");
+        assert!(parser.is_complete());
+        let res = parser.finish().expect("failed to parse http response");
+        assert_eq!(res.headers().content_length(), Some(1024));
+        assert!(res.body().is_empty());
+    }
+
+    #[test]
+    fn test_gzip_chunked_body() {
+        // gzip.compress(b"hello hello hello", mtime=0) をchunkedで送る
+        let mut raw =
+            b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n"
+                .to_vec();
+        raw.extend_from_slice(b"a\r\n");
+        raw.extend_from_slice(&[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03]);
+        raw.extend_from_slice(b"\r\n12\r\n");
+        raw.extend_from_slice(&[
+            0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00, 0x80, 0x88, 0xf9, 0xe5,
+            0x11, 0x00, 0x00, 0x00,
+        ]);
+        raw.extend_from_slice(b"\r\n0\r\n\r\n");
+
+        let mut parser = ResponseParser::new();
+        for chunk in raw.chunks(7) {
+            parser.feed(chunk).expect("failed to feed");
+        }
+        assert!(parser.is_complete());
+        let res = parser.finish().expect("failed to parse http response");
+        assert_eq!(res.body_text(), "hello hello hello");
+    }
+
+    #[test]
+    fn test_truncated_gzip_body() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb,
+        ]);
+        let mut parser = ResponseParser::new();
+        parser.feed(&raw).expect("failed to feed");
+        assert!(matches!(
+            parser.finish().err(),
+            Some(HttpParseError::InvalidContentEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_body_chunks() {
+        let mut parser = ResponseParser::new();
+        parser
+            .feed(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n")
+            .expect("failed to feed");
+        assert!(parser.head().is_none());
+
+        parser.feed(b"\r\n3\r\nab").expect("failed to feed");
+        assert_eq!(200, parser.head().expect("no head").status_code());
+        assert_eq!(b"ab".to_vec(), parser.body_chunk());
+        parser.feed(b"c\r\n2\r\nde\r\n").expect("failed to feed");
+        assert_eq!(b"cde".to_vec(), parser.body_chunk());
+        assert!(parser.body_chunk().is_empty());
+
+        parser.feed(b"0\r\n\r\n").expect("failed to feed");
+        let res = parser.finish().expect("failed to parse http response");
+        assert_eq!("abcde", res.body_text());
+    }
+
+    #[test]
+    fn test_incomplete_body() {
+        let mut parser = ResponseParser::new();
+        parser
+            .feed(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort")
+            .expect("failed to feed");
+        assert!(!parser.is_complete());
+        assert_eq!(parser.finish().err(), Some(HttpParseError::IncompleteBody));
+    }
+
+    #[test]
+    fn test_chunked_with_trailers() {
+        let mut parser = ResponseParser::new();
+        parser
+            .feed(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n")
+            .expect("failed to feed");
+        assert!(!parser.is_complete());
+        parser
+            .feed(b"6;name=value\r\n world\r\n0\r\nX-Checksum: abc\r\n\r\n")
+            .expect("failed to feed");
+        assert!(parser.is_complete());
+        let res = parser.finish().expect("failed to parse http response");
+        assert_eq!(res.body_text(), "hello world");
+        assert_eq!(res.trailers().get("x-checksum"), Some("abc".to_string()));
+    }
+
+    #[test]
+    fn test_conflicting_framing() {
+        let mut parser = ResponseParser::new();
+        assert_eq!(
+            parser.feed(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n"
+            ),
+            Err(HttpParseError::ConflictingFraming)
+        );
+    }
+}
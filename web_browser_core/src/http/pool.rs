@@ -0,0 +1,201 @@
+use crate::http::proxy::ProxyConfig;
+use crate::url::Url;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// アイドル状態で保持しておく接続の数の上限のデフォルト値
+pub const DEFAULT_MAX_IDLE: usize = 16;
+// 1つのオリジンについて保持しておく接続の数の上限のデフォルト値。多くのブラウザは6本まで同時に接続する
+pub const DEFAULT_MAX_IDLE_PER_ORIGIN: usize = 6;
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-9.3
+// レスポンスを受け取り終わった接続をオリジンごとに保持しておき、同じオリジンへのリクエストで再利用する
+// プロキシを通した接続と直接の接続は、同じオリジンでも別のものとして扱う
+// 接続の型はOSごとのネットワークの実装（net_stdなど）が決める
+#[derive(Debug)]
+pub struct ConnectionPool<C> {
+    idle: Vec<(String, C)>, // 古いものから順に、connection_keyと接続を保存する
+    max_idle: usize,
+    max_idle_per_origin: usize,
+}
+
+impl<C> ConnectionPool<C> {
+    pub fn new() -> Self {
+        Self {
+            idle: Vec::new(),
+            max_idle: DEFAULT_MAX_IDLE,
+            max_idle_per_origin: DEFAULT_MAX_IDLE_PER_ORIGIN,
+        }
+    }
+
+    // 0の場合は接続を再利用しない
+    pub fn set_max_idle(&mut self, max_idle: usize) {
+        self.max_idle = max_idle;
+        self.evict();
+    }
+
+    pub fn set_max_idle_per_origin(&mut self, max_idle_per_origin: usize) {
+        self.max_idle_per_origin = max_idle_per_origin;
+        self.evict();
+    }
+
+    pub fn len(&self) -> usize {
+        self.idle.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.idle.is_empty()
+    }
+
+    // 保持している接続をすべて閉じる
+    pub fn clear(&mut self) {
+        self.idle.clear();
+    }
+
+    // URLのオリジンへの、proxyを通した（Noneの場合は直接の）アイドル状態の接続を取り出す
+    // 最も新しく戻されたものを返す
+    pub fn take(&mut self, url: &Url, proxy: Option<&ProxyConfig>) -> Option<C> {
+        let key = connection_key(url, proxy);
+        let index = self.idle.iter().rposition(|(origin, _)| *origin == key)?;
+        Some(self.idle.remove(index).1)
+    }
+
+    // レスポンスを受け取り終わった接続を戻す。上限を超えた場合は古いものから閉じる
+    pub fn put(&mut self, url: &Url, proxy: Option<&ProxyConfig>, connection: C) {
+        self.idle.push((connection_key(url, proxy), connection));
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        // オリジンごとの上限を超えたものを古い順に取り除く
+        let mut i = self.idle.len();
+        while i > 0 {
+            i -= 1;
+            let origin = &self.idle[i].0;
+            let newer = self.idle[i + 1..]
+                .iter()
+                .filter(|(o, _)| o == origin)
+                .count();
+            if newer >= self.max_idle_per_origin {
+                self.idle.remove(i);
+            }
+        }
+
+        while self.idle.len() > self.max_idle {
+            self.idle.remove(0);
+        }
+    }
+}
+
+impl<C> Default for ConnectionPool<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/browsers.html#concept-origin-tuple
+// スキーム、ホスト、ポートが同じ場合は同じ接続を使える
+pub fn origin_key(url: &Url) -> String {
+    format!(
+        "{}://{}:{}",
+        url.scheme(),
+        url.hostname(),
+        url.port_or_default().unwrap_or(0)
+    )
+}
+
+// 接続を再利用できるかどうかを決めるキー。オリジンと、経由するプロキシのホストとポートからなる
+// 直接の接続をプロキシを設定した後に使ったり、プロキシへの接続を直接のリクエストに使ったりしないようにする
+pub fn connection_key(url: &Url, proxy: Option<&ProxyConfig>) -> String {
+    match proxy {
+        Some(proxy) => format!("{} via {}:{}", origin_key(url), proxy.host(), proxy.port()),
+        None => format!("{} direct", origin_key(url)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).expect("failed to parse url")
+    }
+
+    #[test]
+    fn test_origin_key() {
+        assert_eq!(
+            "http://example.com:80",
+            origin_key(&url("http://example.com/index.html"))
+        );
+        assert_eq!(
+            origin_key(&url("http://example.com:80/a")),
+            origin_key(&url("http://EXAMPLE.com/b?c"))
+        );
+        assert_ne!(
+            origin_key(&url("http://example.com/")),
+            origin_key(&url("https://example.com/"))
+        );
+    }
+
+    #[test]
+    fn test_take_and_put() {
+        let mut pool = ConnectionPool::new();
+        let a = url("http://a.example/");
+        let b = url("http://b.example/");
+        assert_eq!(None, pool.take(&a, None));
+
+        pool.put(&a, None, 1);
+        pool.put(&b, None, 2);
+        pool.put(&a, None, 3);
+        assert_eq!(3, pool.len());
+        assert_eq!(Some(3), pool.take(&url("http://a.example:80/other"), None));
+        assert_eq!(Some(1), pool.take(&a, None));
+        assert_eq!(None, pool.take(&a, None));
+        assert_eq!(Some(2), pool.take(&b, None));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_proxy() {
+        let mut pool = ConnectionPool::new();
+        let a = url("http://a.example/");
+        let proxy = ProxyConfig::new("proxy.example", 3128);
+        let other_proxy = ProxyConfig::new("proxy.example", 8080);
+
+        // プロキシを設定する前の直接の接続は、プロキシを通すリクエストに使わない
+        pool.put(&a, None, 1);
+        assert_eq!(None, pool.take(&a, Some(&proxy)));
+        pool.put(&a, Some(&proxy), 2);
+        assert_eq!(None, pool.take(&a, Some(&other_proxy)));
+        assert_eq!(Some(1), pool.take(&a, None));
+        assert_eq!(None, pool.take(&a, None));
+        assert_eq!(Some(2), pool.take(&a, Some(&proxy)));
+    }
+
+    #[test]
+    fn test_max_idle() {
+        let mut pool = ConnectionPool::new();
+        pool.set_max_idle_per_origin(2);
+        let a = url("http://a.example/");
+        let b = url("http://b.example/");
+        for i in 0..3 {
+            pool.put(&a, None, i);
+        }
+        pool.put(&b, None, 10);
+        assert_eq!(3, pool.len());
+
+        // 全体の上限を超えたら、オリジンに関係なく古いものから閉じる
+        pool.set_max_idle(2);
+        let mut remaining = vec![];
+        while let Some(c) = pool.take(&a, None).or_else(|| pool.take(&b, None)) {
+            remaining.push(c);
+        }
+        assert_eq!(vec![2, 10], remaining);
+
+        pool.set_max_idle(0);
+        pool.put(&a, None, 0);
+        assert!(pool.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::http::request::{HttpRequest, Method};
+use crate::url::Url;
+use crate::utils::encode_base64;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// リクエストを中継するHTTPプロキシの設定
+// httpのURLへのリクエストはabsolute-formでプロキシに送り、httpsのURLへはCONNECTでトンネルを作る
+// https://www.rfc-editor.org/rfc/rfc9112#section-3.2.2
+// https://www.rfc-editor.org/rfc/rfc9110#section-9.3.6
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>, // Basic認証のユーザー名とパスワード
+    no_proxy: Vec<String>,                 // プロキシを通さずに直接接続するホスト
+}
+
+impl ProxyConfig {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_ascii_lowercase(),
+            port,
+            credentials: None,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    pub fn host(&self) -> String {
+        self.host.clone()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn set_credentials(&mut self, username: &str, password: &str) {
+        self.credentials = Some((String::from(username), String::from(password)));
+    }
+
+    // ホスト名を指定すると、そのサブドメインも直接接続する。* はすべてのホストに一致する
+    pub fn add_no_proxy(&mut self, host: &str) {
+        let host = host.trim().trim_start_matches('.').to_ascii_lowercase();
+        if !host.is_empty() {
+            self.no_proxy.push(host);
+        }
+    }
+
+    pub fn no_proxy(&self) -> Vec<String> {
+        self.no_proxy.clone()
+    }
+
+    // URLへのリクエストをプロキシに送るかどうか
+    pub fn uses_proxy(&self, url: &Url) -> bool {
+        if !matches!(url.scheme().as_str(), "http" | "https") {
+            return false;
+        }
+        let host = url.hostname().to_ascii_lowercase();
+        !self.no_proxy.iter().any(|pattern| {
+            pattern == "*"
+                || host == *pattern
+                || host
+                    .strip_suffix(pattern.as_str())
+                    .is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-11.7.2
+    // Proxy-Authorizationヘッダーの値。認証情報がない場合はNoneを返す
+    pub fn authorization(&self) -> Option<String> {
+        let (username, password) = self.credentials.as_ref()?;
+        let credentials = format!("{}:{}", username, password);
+        Some(format!("Basic {}", encode_base64(credentials.as_bytes())))
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-9.3.6
+    // URLのホストとポートへのトンネルを作るリクエスト
+    pub fn connect_request(&self, url: &Url) -> HttpRequest {
+        let mut request = HttpRequest::new(Method::Connect, url.clone());
+        if let Some(authorization) = self.authorization() {
+            request
+                .headers_mut()
+                .insert("Proxy-Authorization", &authorization);
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).expect("failed to parse url")
+    }
+
+    #[test]
+    fn test_no_proxy() {
+        let mut proxy = ProxyConfig::new("proxy.example", 8080);
+        proxy.add_no_proxy("internal.example");
+        proxy.add_no_proxy(".LOCAL");
+        assert!(proxy.uses_proxy(&url("http://example.com/")));
+        assert!(proxy.uses_proxy(&url("https://notinternal.example/")));
+        assert!(!proxy.uses_proxy(&url("http://internal.example/")));
+        assert!(!proxy.uses_proxy(&url("https://www.internal.example:8443/")));
+        assert!(!proxy.uses_proxy(&url("http://printer.local/")));
+
+        proxy.add_no_proxy("*");
+        assert!(!proxy.uses_proxy(&url("http://example.com/")));
+    }
+
+    #[test]
+    fn test_authorization() {
+        let mut proxy = ProxyConfig::new("proxy.example", 8080);
+        assert_eq!(None, proxy.authorization());
+        // https://www.rfc-editor.org/rfc/rfc7617#section-2
+        proxy.set_credentials("Aladdin", "open sesame");
+        assert_eq!(
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".to_string()),
+            proxy.authorization()
+        );
+    }
+
+    #[test]
+    fn test_connect_request() {
+        let mut proxy = ProxyConfig::new("proxy.example", 8080);
+        proxy.set_credentials("user", "pass");
+        let request = proxy.connect_request(&url("https://example.com/index.html"));
+        assert_eq!(
+            "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n",
+            String::from_utf8(request.to_bytes()).expect("not utf-8")
+        );
+    }
+}
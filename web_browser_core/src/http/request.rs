@@ -0,0 +1,403 @@
+use crate::alloc::string::ToString;
+use crate::error::Error;
+use crate::http::header::HeaderMap;
+use crate::http::parser::is_token_char;
+use crate::http::proxy::ProxyConfig;
+use crate::http::HttpError;
+use crate::url::Url;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::str::FromStr;
+
+// https://www.rfc-editor.org/rfc/rfc9110#section-9
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Options,
+    Patch,
+    Connect,
+}
+
+impl Method {
+    // https://www.rfc-editor.org/rfc/rfc9110#section-9.2.1
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Method::Get | Method::Head | Method::Options)
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Method::Put | Method::Delete)
+    }
+
+    // ボディに意味を持つメソッド。ボディがなくてもContent-Length: 0を送る
+    fn expects_body(&self) -> bool {
+        matches!(self, Method::Post | Method::Put | Method::Patch)
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Options => "OPTIONS",
+            Method::Patch => "PATCH",
+            Method::Connect => "CONNECT",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// メソッド名は大文字小文字を区別する
+// https://www.rfc-editor.org/rfc/rfc9110#section-9.1
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "GET" => Ok(Method::Get),
+            "HEAD" => Ok(Method::Head),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "DELETE" => Ok(Method::Delete),
+            "OPTIONS" => Ok(Method::Options),
+            "PATCH" => Ok(Method::Patch),
+            "CONNECT" => Ok(Method::Connect),
+            _ => Err(format!("unsupported method {:?}", s)),
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9112#section-3
+// サーバーに送るリクエスト。接続先はurlのホストとポート、プロキシを通す場合はプロキシになる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    method: Method,
+    url: Url,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+    proxy: Option<ProxyConfig>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: None,
+            proxy: None,
+        }
+    }
+
+    pub fn builder(method: Method, url: Url) -> HttpRequestBuilder {
+        HttpRequestBuilder::new(method, url)
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9112#section-3.2
+    // プロキシに送るhttpのURLへのリクエストはabsolute-form、CONNECTはauthority-formにする
+    pub fn target(&self) -> String {
+        if self.method == Method::Connect {
+            return self.authority();
+        }
+        if self.is_forwarded() {
+            return format!(
+                "{}://{}{}",
+                self.url.scheme(),
+                self.host_header(),
+                self.origin_form()
+            );
+        }
+        self.origin_form()
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9112#section-3.2.1
+    // origin-form = absolute-path [ "?" query ]
+    fn origin_form(&self) -> String {
+        let mut target = self.url.path();
+        if target.is_empty() {
+            target.push('/');
+        }
+        if let Some(query) = self.url.query() {
+            target.push('?');
+            target.push_str(&query);
+        }
+        target
+    }
+
+    pub fn headers(&self) -> HeaderMap {
+        self.headers.clone()
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn body(&self) -> Option<Vec<u8>> {
+        self.body.clone()
+    }
+
+    // リクエストを中継するプロキシ。Fetcherがブラウザの設定をもとに設定する
+    pub fn set_proxy(&mut self, proxy: Option<ProxyConfig>) {
+        self.proxy = proxy;
+    }
+
+    pub fn proxy(&self) -> Option<ProxyConfig> {
+        self.proxy.clone()
+    }
+
+    // プロキシにそのまま送って転送してもらうリクエストかどうか
+    // httpsのURLへのリクエストはCONNECTで作ったトンネルの中で送るので、通常のリクエストと同じ形にする
+    fn is_forwarded(&self) -> bool {
+        self.proxy.is_some() && self.url.scheme() == "http"
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9112#section-3.2.3
+    // authority-form = uri-host ":" port
+    fn authority(&self) -> String {
+        format!(
+            "{}:{}",
+            self.url.hostname(),
+            self.url.port_or_default().unwrap_or(0)
+        )
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-7.2
+    // Hostヘッダーの値。デフォルトポートの場合はポート番号を省略する
+    pub fn host_header(&self) -> String {
+        // CONNECTはポート番号を省略しない
+        if self.method == Method::Connect {
+            return self.authority();
+        }
+        let mut host = self.url.hostname();
+        if let Some(port) = self.url.port() {
+            host.push(':');
+            host.push_str(&port.to_string());
+        }
+        host
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9112#section-2.1
+    // HTTP-message = start-line CRLF *( field-line CRLF ) CRLF [ message-body ]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut message = String::new();
+        message.push_str(&self.method.to_string());
+        message.push(' ');
+        message.push_str(&self.target());
+        message.push_str(" HTTP/1.1\r\n");
+
+        // Hostヘッダーは必須で、最初に送る
+        if !self.headers.contains("Host") {
+            message.push_str("Host: ");
+            message.push_str(&self.host_header());
+            message.push_str("\r\n");
+        }
+        if self.is_forwarded() && !self.headers.contains("Proxy-Authorization") {
+            if let Some(authorization) = self.proxy.as_ref().and_then(|p| p.authorization()) {
+                message.push_str("Proxy-Authorization: ");
+                message.push_str(&authorization);
+                message.push_str("\r\n");
+            }
+        }
+        for header in &self.headers {
+            message.push_str(&header.name());
+            message.push_str(": ");
+            message.push_str(&header.value());
+            message.push_str("\r\n");
+        }
+
+        // https://www.rfc-editor.org/rfc/rfc9110#section-8.6
+        let body_length = self.body.as_ref().map(|b| b.len());
+        if !self.headers.contains("Content-Length") && !self.headers.contains("Transfer-Encoding") {
+            if let Some(length) = body_length.or(self.method.expects_body().then_some(0)) {
+                message.push_str("Content-Length: ");
+                message.push_str(&length.to_string());
+                message.push_str("\r\n");
+            }
+        }
+        message.push_str("\r\n");
+
+        let mut bytes = message.into_bytes();
+        if let Some(body) = &self.body {
+            bytes.extend_from_slice(body);
+        }
+        bytes
+    }
+}
+
+// ヘッダーとボディを設定してHttpRequestを作る
+// ヘッダーの名前と値はbuild()のときに検証する
+#[derive(Debug, Clone)]
+pub struct HttpRequestBuilder {
+    request: HttpRequest,
+}
+
+impl HttpRequestBuilder {
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            request: HttpRequest::new(method, url),
+        }
+    }
+
+    // 同じ名前のヘッダーがあっても置き換えずに追加する
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.request.headers.append(name, value);
+        self
+    }
+
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.request.body = Some(body);
+        self
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9110#section-5.5
+    // CRやLFを含む値はヘッダーインジェクションにつながるためエラーにする
+    pub fn build(self) -> Result<HttpRequest, Error> {
+        if !matches!(self.request.url.scheme().as_str(), "http" | "https") {
+            return Err(HttpError::UnsupportedScheme(self.request.url.scheme()).into());
+        }
+        for header in &self.request.headers {
+            let name = header.name();
+            if name.is_empty() || !name.bytes().all(is_token_char) {
+                return Err(HttpError::InvalidHeaderName(name).into());
+            }
+            if header
+                .value()
+                .chars()
+                .any(|c| c == '\r' || c == '\n' || c == '\0')
+            {
+                return Err(HttpError::InvalidHeaderValue(name).into());
+            }
+        }
+        Ok(self.request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).expect("failed to parse url")
+    }
+
+    #[test]
+    fn test_get() {
+        let request =
+            HttpRequest::builder(Method::Get, url("http://example.com/index.html?q=1#top"))
+                .header("Accept", "text/html")
+                .build()
+                .expect("failed to build request");
+        assert_eq!("/index.html?q=1", request.target());
+        assert_eq!(
+            b"GET /index.html?q=1 HTTP/1.1\r\nHost: example.com\r\nAccept: text/html\r\n\r\n"
+                .to_vec(),
+            request.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_root_target_and_port() {
+        let request = HttpRequest::new(Method::Get, url("http://localhost:8000"));
+        assert_eq!("/", request.target());
+        assert_eq!("localhost:8000", request.host_header());
+        let request = HttpRequest::new(Method::Head, url("http://example.com:80/a/b"));
+        assert_eq!("/a/b", request.target());
+        assert_eq!("example.com", request.host_header());
+    }
+
+    #[test]
+    fn test_absolute_form() {
+        let mut proxy = ProxyConfig::new("proxy.example", 3128);
+        proxy.set_credentials("user", "pass");
+        let mut request =
+            HttpRequest::new(Method::Get, url("http://user:pw@example.com:8080/a?b#c"));
+        request.set_proxy(Some(proxy.clone()));
+        assert_eq!("http://example.com:8080/a?b", request.target());
+        assert_eq!(
+            b"GET http://example.com:8080/a?b HTTP/1.1\r\nHost: example.com:8080\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n".to_vec(),
+            request.to_bytes()
+        );
+
+        // httpsのURLへのリクエストはトンネルの中で送るので、origin-formのまま認証情報も送らない
+        let mut request = HttpRequest::new(Method::Get, url("https://example.com/a"));
+        request.set_proxy(Some(proxy));
+        assert_eq!(
+            b"GET /a HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(),
+            request.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_post_body() {
+        let request = HttpRequest::builder(Method::Post, url("http://example.com/form"))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(b"a=1&b=2".to_vec())
+            .build()
+            .expect("failed to build request");
+        assert_eq!(
+            b"POST /form HTTP/1.1\r\nHost: example.com\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: 7\r\n\r\na=1&b=2".to_vec(),
+            request.to_bytes()
+        );
+
+        let request = HttpRequest::new(Method::Post, url("http://example.com/form"));
+        assert_eq!(
+            b"POST /form HTTP/1.1\r\nHost: example.com\r\nContent-Length: 0\r\n\r\n".to_vec(),
+            request.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        assert_eq!(
+            Some(Error::Http(HttpError::InvalidHeaderValue(
+                "X-Test".to_string()
+            ))),
+            HttpRequest::builder(Method::Get, url("http://example.com/"))
+                .header("X-Test", "a\r\nInjected: 1")
+                .build()
+                .err()
+        );
+        assert_eq!(
+            Some(Error::Http(HttpError::InvalidHeaderName(
+                "Bad Name".to_string()
+            ))),
+            HttpRequest::builder(Method::Get, url("http://example.com/"))
+                .header("Bad Name", "a")
+                .build()
+                .err()
+        );
+        assert_eq!(
+            Some(Error::Http(HttpError::UnsupportedScheme("ftp".to_string()))),
+            HttpRequest::builder(Method::Get, url("ftp://example.com/"))
+                .build()
+                .err()
+        );
+    }
+
+    #[test]
+    fn test_method() {
+        assert_eq!(Ok(Method::Delete), "DELETE".parse());
+        assert!("get".parse::<Method>().is_err());
+        assert_eq!("OPTIONS", Method::Options.to_string());
+        assert!(Method::Put.is_idempotent());
+        assert!(!Method::Post.is_idempotent());
+    }
+}
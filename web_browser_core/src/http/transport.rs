@@ -0,0 +1,181 @@
+use crate::error::Error;
+use crate::http::parser::ResponseParser;
+use crate::http::request::HttpRequest;
+use crate::http::HttpResponse;
+use crate::url::Url;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::{Debug, Display};
+
+// リクエストを送ってレスポンスを受け取る手段
+// OSごとのネットワークの実装（net_wasabiなど）やテスト用のモックがこのトレイトを実装する
+pub trait HttpTransport: Debug {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+
+    // ボディを受け取るたびに、ステータスとヘッダーを参照するためのレスポンスと、受け取ったボディの一部でon_bodyを呼び出す
+    // 戻り値のレスポンスにはボディ全体が含まれる
+    // デフォルトの実装は、レスポンスをすべて受け取ってから1回だけon_bodyを呼び出す
+    fn send_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let response = self.send(request)?;
+        let body = response.body();
+        if !body.is_empty() {
+            on_body(&response, &body);
+        }
+        Ok(response)
+    }
+}
+
+// 接続やデータの送受信のエラー。OSごとのネットワークの実装が、失敗した段階に応じて使い分ける
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkError {
+    NoTransport,               // 通信する手段が設定されていない
+    Dns(String),               // ホスト名からIPアドレスを解決できない
+    ConnectionRefused(String), // 接続先のアドレスが接続を拒否した
+    Connect(String),           // それ以外の理由で接続できない
+    Timeout,                   // 接続や受信が時間内に終わらない
+    ConnectionReset,           // 通信の途中で接続が切られた
+    Certificate(String),       // サーバーの証明書を検証できない
+    Tls(String),               // それ以外の理由でTLSの通信に失敗した
+    Proxy(String),             // プロキシがトンネルを作れない
+    Send(String),
+    Receive(String),
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NetworkError::NoTransport => write!(f, "no transport is configured"),
+            NetworkError::Dns(host) => write!(f, "failed to resolve {}", host),
+            NetworkError::ConnectionRefused(address) => {
+                write!(f, "connection refused: {}", address)
+            }
+            NetworkError::Connect(s) => write!(f, "failed to connect: {}", s),
+            NetworkError::Timeout => write!(f, "timed out"),
+            NetworkError::ConnectionReset => write!(f, "connection reset"),
+            NetworkError::Certificate(s) => write!(f, "invalid certificate: {}", s),
+            NetworkError::Tls(s) => write!(f, "tls error: {}", s),
+            NetworkError::Proxy(s) => write!(f, "proxy error: {}", s),
+            NetworkError::Send(s) => write!(f, "failed to send: {}", s),
+            NetworkError::Receive(s) => write!(f, "failed to receive: {}", s),
+        }
+    }
+}
+
+impl From<NetworkError> for Error {
+    fn from(e: NetworkError) -> Self {
+        Error::Network(e)
+    }
+}
+
+// URLごとに用意したレスポンスを返すテスト用のトランスポート
+// 受け取ったリクエストは記録しておき、後から確認できる
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Vec<(String, Vec<u8>)>, // フラグメントを除いたURLと、HTTPレスポンスのバイト列
+    requests: RefCell<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self {
+            responses: Vec::new(),
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+
+    // urlへのリクエストに対してraw_responseを返すようにする。同じURLの場合は上書きする
+    pub fn add_response(&mut self, url: &str, raw_response: &[u8]) {
+        let key = normalize_url(url);
+        self.responses.retain(|(u, _)| *u != key);
+        self.responses.push((key, raw_response.to_vec()));
+    }
+
+    // 送られてきたリクエストを順番通りに返す
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.borrow().clone()
+    }
+}
+
+impl MockTransport {
+    // リクエストを記録し、用意したレスポンスのバイト列を渡したパーサーを返す
+    fn parse_response(&self, request: HttpRequest) -> Result<ResponseParser, Error> {
+        let key = request.url().serialize(true);
+        let method = request.method();
+        self.requests.borrow_mut().push(request);
+
+        let raw = match self.responses.iter().find(|(u, _)| *u == key) {
+            Some((_, raw)) => raw,
+            // 用意していないURLには接続できなかったことにする
+            None => return Err(NetworkError::ConnectionRefused(key).into()),
+        };
+        let mut parser = ResponseParser::for_method(method);
+        parser.feed(raw)?;
+        Ok(parser)
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        Ok(self.parse_response(request)?.finish()?)
+    }
+
+    // ボディが途中で切れているレスポンスは、受け取れた分だけon_bodyに渡してから失敗する
+    fn send_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let mut parser = self.parse_response(request)?;
+        if let Some(head) = parser.head() {
+            let chunk = parser.body_chunk();
+            if !chunk.is_empty() {
+                on_body(&head, &chunk);
+            }
+        }
+        Ok(parser.finish()?)
+    }
+}
+
+// 登録したURLとリクエストのURLの表記の違い（末尾の / の有無など）を吸収する
+fn normalize_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => url.serialize(true),
+        Err(_) => String::from(url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::Method;
+
+    #[test]
+    fn test_mock_transport() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi",
+        );
+
+        let url = Url::parse("http://example.com/#top").expect("failed to parse url");
+        let response = transport
+            .send(HttpRequest::new(Method::Get, url))
+            .expect("failed to send request");
+        assert_eq!(response.body_text(), "hi");
+        assert_eq!(transport.requests().len(), 1);
+
+        let url = Url::parse("http://example.com/missing").expect("failed to parse url");
+        assert_eq!(
+            transport.send(HttpRequest::new(Method::Get, url)).err(),
+            Some(Error::Network(NetworkError::ConnectionRefused(
+                "http://example.com/missing".into()
+            )))
+        );
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
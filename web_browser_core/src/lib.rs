@@ -2,8 +2,16 @@
 
 extern crate alloc;
 
+pub mod clock;
+pub mod dns;
+pub mod download;
+pub mod encoding;
 pub mod error;
+pub mod hash;
 pub mod http;
+pub mod loader;
+pub mod mime;
+pub mod random;
 pub mod renderer;
 pub mod url;
 pub mod browser;
@@ -0,0 +1,142 @@
+use crate::loader::LoadError;
+use crate::mime::MimeType;
+use crate::url::percent_encoding::percent_decode;
+use crate::url::Url;
+use crate::utils::decode_base64;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// MIMEタイプがない、または解釈できない場合に使う値
+pub const DEFAULT_MIME_TYPE: &str = "text/plain;charset=US-ASCII";
+
+// https://fetch.spec.whatwg.org/#data-urls
+// data:[<mediatype>][;base64],<data>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataUrl {
+    mime_type: String, // Content-Typeとしてそのまま使える値
+    body: Vec<u8>,
+}
+
+impl DataUrl {
+    // https://fetch.spec.whatwg.org/#data-url-processor
+    pub fn parse(url: &Url) -> Result<Self, LoadError> {
+        let serialized = url.serialize(true);
+        let input = match serialized.strip_prefix("data:") {
+            Some(input) => input,
+            None => return Err(LoadError::InvalidDataUrl(serialized)),
+        };
+        let (mime_type, body) = match input.split_once(',') {
+            Some(pair) => pair,
+            None => return Err(LoadError::InvalidDataUrl(serialized)),
+        };
+        let mut mime_type = mime_type
+            .trim_matches(|c: char| c.is_ascii_whitespace())
+            .to_string();
+        let mut body = percent_decode(body.as_bytes());
+
+        // ;base64 で終わる場合はパーセントデコードしたボディをさらにBase64としてデコードする
+        if let Some(rest) = strip_base64(&mime_type) {
+            body = match decode_base64(&body) {
+                Some(body) => body,
+                None => return Err(LoadError::InvalidDataUrl(serialized)),
+            };
+            mime_type = rest;
+        }
+
+        // data:;charset=utf-8, のようにタイプを省略した場合はtext/plainとみなす
+        if mime_type.starts_with(';') {
+            mime_type.insert_str(0, "text/plain");
+        }
+        if MimeType::parse(&mime_type).is_none() {
+            mime_type = DEFAULT_MIME_TYPE.to_string();
+        }
+
+        Ok(Self { mime_type, body })
+    }
+
+    pub fn mime_type(&self) -> String {
+        self.mime_type.clone()
+    }
+
+    pub fn charset(&self) -> Option<String> {
+        MimeType::parse(&self.mime_type).and_then(|mime_type| mime_type.charset())
+    }
+
+    pub fn body(&self) -> Vec<u8> {
+        self.body.clone()
+    }
+}
+
+// ; と0個以上の空白に続いて、大文字小文字を区別せずに base64 で終わる場合は、それらを取り除いた残りを返す
+fn strip_base64(mime_type: &str) -> Option<String> {
+    let bytes = mime_type.as_bytes();
+    if bytes.len() < 6 || !bytes[bytes.len() - 6..].eq_ignore_ascii_case(b"base64") {
+        return None;
+    }
+    mime_type[..bytes.len() - 6]
+        .trim_end_matches(' ')
+        .strip_suffix(';')
+        .map(|rest| rest.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn parse(url: &str) -> Result<DataUrl, LoadError> {
+        DataUrl::parse(&Url::parse(url).expect("failed to parse url"))
+    }
+
+    #[test]
+    fn test_percent_encoded() {
+        let data = parse("data:text/html;charset=utf-8,%3Cp%3Ehi%3C/p%3E#top")
+            .expect("failed to parse data url");
+        assert_eq!("text/html;charset=utf-8", data.mime_type());
+        assert_eq!(Some("utf-8".to_string()), data.charset());
+        assert_eq!(b"<p>hi</p>".to_vec(), data.body());
+
+        // タイプを省略した場合
+        let data = parse("data:,Hello%2C%20World").expect("failed to parse data url");
+        assert_eq!(DEFAULT_MIME_TYPE, data.mime_type());
+        assert_eq!(Some("US-ASCII".to_string()), data.charset());
+        assert_eq!(b"Hello, World".to_vec(), data.body());
+
+        let data = parse("data:;charset=Shift_JIS,%82%A0").expect("failed to parse data url");
+        assert_eq!("text/plain;charset=Shift_JIS", data.mime_type());
+        assert_eq!(vec![0x82, 0xa0], data.body());
+    }
+
+    #[test]
+    fn test_base64() {
+        let data = parse("data:image/gif;BASE64,R0lGODlh").expect("failed to parse data url");
+        assert_eq!("image/gif", data.mime_type());
+        assert_eq!(None, data.charset());
+        assert_eq!(b"GIF89a".to_vec(), data.body());
+
+        // 空白や末尾の = の省略を許す
+        let data = parse("data:text/plain; base64,aGV sbG8").expect("failed to parse data url");
+        assert_eq!("text/plain", data.mime_type());
+        assert_eq!(b"hello".to_vec(), data.body());
+
+        assert!(matches!(
+            parse("data:text/plain;base64,a"),
+            Err(LoadError::InvalidDataUrl(_))
+        ));
+        assert!(matches!(
+            parse("data:text/plain;base64,!!!!"),
+            Err(LoadError::InvalidDataUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            parse("data:text/plain"),
+            Err(LoadError::InvalidDataUrl(_))
+        ));
+        // 解釈できないMIMEタイプはtext/plainにする
+        let data = parse("data:nonsense,x").expect("failed to parse data url");
+        assert_eq!(DEFAULT_MIME_TYPE, data.mime_type());
+    }
+}
@@ -0,0 +1,339 @@
+pub mod data;
+
+use crate::error::Error;
+use crate::http::fetch::Fetcher;
+use crate::http::header::HeaderMap;
+use crate::http::request::HttpRequest;
+use crate::http::transport::NetworkError;
+use crate::http::HttpResponse;
+use crate::loader::data::DataUrl;
+use crate::url::percent_encoding::percent_decode;
+use crate::url::Url;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display};
+
+// file: URLのパスからファイルの中身を読み込む手段
+// std環境ではnet_stdのStdFileSystemが、テストやOSのファイルシステムがない環境ではMemoryFileSystemが実装する
+pub trait FileSystem: Debug {
+    fn read(&self, path: &str) -> Result<Vec<u8>, LoadError>;
+}
+
+// パスごとに用意した中身を返すファイルシステム
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileSystem {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    // 同じパスの場合は上書きする
+    pub fn add_file(&mut self, path: &str, contents: &[u8]) {
+        self.files.retain(|(p, _)| p != path);
+        self.files.push((path.to_string(), contents.to_vec()));
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &str) -> Result<Vec<u8>, LoadError> {
+        match self.files.iter().find(|(p, _)| p == path) {
+            Some((_, contents)) => Ok(contents.clone()),
+            None => Err(LoadError::NotFound(path.to_string())),
+        }
+    }
+}
+
+// URLのスキームに応じてドキュメントを読み込む
+// http、httpsはFetcherに任せ、data:、file:、about:はネットワークを使わずにレスポンスを作る
+#[derive(Debug, Clone, Default)]
+pub struct Loader {
+    fetcher: Option<Fetcher>,
+    file_system: Option<Rc<dyn FileSystem>>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self {
+            fetcher: None,
+            file_system: None,
+        }
+    }
+
+    pub fn set_fetcher(&mut self, fetcher: Fetcher) {
+        self.fetcher = Some(fetcher);
+    }
+
+    pub fn set_file_system(&mut self, file_system: Rc<dyn FileSystem>) {
+        self.file_system = Some(file_system);
+    }
+
+    pub fn load(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        self.load_streaming(request, &mut |_, _| {})
+    }
+
+    // ボディを受け取るたびにon_bodyを呼び出す。ネットワークを使わない場合は1回だけ呼び出す
+    // http、https以外のURLではリクエストのメソッドとヘッダーを使わない
+    pub fn load_streaming(
+        &self,
+        request: HttpRequest,
+        on_body: &mut dyn FnMut(&HttpResponse, &[u8]),
+    ) -> Result<HttpResponse, Error> {
+        let url = request.url();
+        let mut response = match url.scheme().as_str() {
+            "http" | "https" => {
+                return match &self.fetcher {
+                    Some(fetcher) => fetcher.fetch_streaming(request, on_body),
+                    None => Err(NetworkError::NoTransport.into()),
+                }
+            }
+            "data" => load_data(&url)?,
+            "file" => self.load_file(&url)?,
+            "about" => load_about(&url)?,
+            scheme => return Err(LoadError::UnsupportedScheme(scheme.to_string()).into()),
+        };
+
+        response.set_url_list(vec![url]);
+        let body = response.body();
+        if !body.is_empty() {
+            on_body(&response, &body);
+        }
+        Ok(response)
+    }
+
+    // https://fetch.spec.whatwg.org/#scheme-fetch
+    // パスをパーセントデコードしてファイルシステムから読み込み、拡張子からContent-Typeを決める
+    // 拡張子からわからない場合はContent-Typeを付けず、表示するときにボディから判定する
+    fn load_file(&self, url: &Url) -> Result<HttpResponse, LoadError> {
+        // file://localhost/ はURLを解釈するときに空のホストになっている
+        let host = url.hostname();
+        if !host.is_empty() {
+            return Err(LoadError::RemoteFile(host));
+        }
+        let file_system = match &self.file_system {
+            Some(file_system) => file_system,
+            None => return Err(LoadError::NoFileSystem),
+        };
+
+        let path = String::from_utf8_lossy(&percent_decode(url.path().as_bytes())).to_string();
+        let body = file_system.read(&path)?;
+        Ok(ok_response(guess_mime_type(&path), body))
+    }
+}
+
+fn load_data(url: &Url) -> Result<HttpResponse, LoadError> {
+    let data = DataUrl::parse(url)?;
+    Ok(ok_response(Some(&data.mime_type()), data.body()))
+}
+
+// https://fetch.spec.whatwg.org/#about-scheme
+// about:blank と、ブラウザのバージョンを表示する about:version を組み込みのページとして返す
+fn load_about(url: &Url) -> Result<HttpResponse, LoadError> {
+    match url.path().as_str() {
+        "blank" => Ok(ok_response(Some("text/html;charset=utf-8"), Vec::new())),
+        "version" => {
+            let html = format!(
+                "<html><head><title>About Version</title></head><body><h1>rust-web-browser</h1><p>Version {}</p></body></html>",
+                env!("CARGO_PKG_VERSION")
+            );
+            Ok(ok_response(
+                Some("text/html;charset=utf-8"),
+                html.into_bytes(),
+            ))
+        }
+        _ => Err(LoadError::NotFound(url.serialize(true))),
+    }
+}
+
+fn ok_response(mime_type: Option<&str>, body: Vec<u8>) -> HttpResponse {
+    let mut headers = HeaderMap::new();
+    if let Some(mime_type) = mime_type {
+        headers.insert("Content-Type", mime_type);
+    }
+    headers.insert("Content-Length", &body.len().to_string());
+    HttpResponse::from_parts(200, "OK", headers, body)
+}
+
+// ファイル名の拡張子からMIMEタイプを推測する
+fn guess_mime_type(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let (_, extension) = name.rsplit_once('.')?;
+    let mime_type = match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html",
+        "txt" => "text/plain",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    Some(mime_type)
+}
+
+// http、https以外のURLを読み込むときのエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    UnsupportedScheme(String), // 読み込み方を知らないスキーム
+    InvalidDataUrl(String),    // , がない、またはBase64としてデコードできない
+    NoFileSystem,              // ファイルシステムが設定されていない
+    RemoteFile(String),        // ほかのホストのファイル
+    NotFound(String),          // ファイルやabout:のページが存在しない
+    PermissionDenied(String),  // ファイルを読む権限がない
+    Read(String),              // それ以外の理由でファイルを読めない
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::UnsupportedScheme(s) => write!(f, "unsupported scheme: {}", s),
+            LoadError::InvalidDataUrl(url) => write!(f, "invalid data url: {}", url),
+            LoadError::NoFileSystem => write!(f, "no file system is configured"),
+            LoadError::RemoteFile(host) => write!(f, "cannot load a file from {}", host),
+            LoadError::NotFound(s) => write!(f, "not found: {}", s),
+            LoadError::PermissionDenied(path) => write!(f, "permission denied: {}", path),
+            LoadError::Read(s) => write!(f, "failed to read: {}", s),
+        }
+    }
+}
+
+impl From<LoadError> for Error {
+    fn from(e: LoadError) -> Self {
+        Error::Load(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::Method;
+    use crate::http::transport::MockTransport;
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest::new(Method::Get, Url::parse(url).expect("failed to parse url"))
+    }
+
+    fn loader() -> Loader {
+        let mut file_system = MemoryFileSystem::new();
+        file_system.add_file("/srv/test page.html", b"<html></html>");
+        file_system.add_file("/srv/data", b"\x00\x01");
+        let mut loader = Loader::new();
+        loader.set_file_system(Rc::new(file_system));
+        loader
+    }
+
+    #[test]
+    fn test_load_file() {
+        let loader = loader();
+        let mut chunks = Vec::new();
+        let response = loader
+            .load_streaming(get("file:///srv/test%20page.html"), &mut |_, chunk| {
+                chunks.push(chunk.to_vec())
+            })
+            .expect("failed to load file");
+        assert_eq!(200, response.status_code());
+        assert_eq!(
+            Some("text/html".to_string()),
+            response.header_value("Content-Type")
+        );
+        assert_eq!(vec![b"<html></html>".to_vec()], chunks);
+        assert_eq!(
+            Some("file:///srv/test%20page.html".to_string()),
+            response.url().map(|url| url.to_string())
+        );
+
+        let response = loader
+            .load(get("file://localhost/srv/data"))
+            .expect("failed to load file");
+        assert_eq!(None, response.header_value("Content-Type"));
+
+        assert_eq!(
+            Some(Error::Load(LoadError::NotFound(
+                "/srv/missing.html".to_string()
+            ))),
+            loader.load(get("file:///srv/missing.html")).err()
+        );
+        assert_eq!(
+            Some(Error::Load(LoadError::RemoteFile(
+                "example.com".to_string()
+            ))),
+            loader.load(get("file://example.com/srv/data")).err()
+        );
+        assert_eq!(
+            Some(Error::Load(LoadError::NoFileSystem)),
+            Loader::new().load(get("file:///srv/data")).err()
+        );
+    }
+
+    #[test]
+    fn test_load_data() {
+        let response = Loader::new()
+            .load(get("data:text/html;base64,PHA+aGk8L3A+"))
+            .expect("failed to load data url");
+        assert_eq!(
+            Some("text/html".to_string()),
+            response.header_value("Content-Type")
+        );
+        assert_eq!("<p>hi</p>", response.body_text());
+    }
+
+    #[test]
+    fn test_load_about() {
+        let loader = Loader::new();
+        let response = loader
+            .load(get("about:blank"))
+            .expect("failed to load about:blank");
+        assert!(response.body().is_empty());
+        assert_eq!(
+            Some("text/html;charset=utf-8".to_string()),
+            response.header_value("Content-Type")
+        );
+
+        let response = loader
+            .load(get("about:version"))
+            .expect("failed to load about:version");
+        assert!(response.body_text().contains(env!("CARGO_PKG_VERSION")));
+
+        assert_eq!(
+            Some(Error::Load(LoadError::NotFound(
+                "about:missing".to_string()
+            ))),
+            loader.load(get("about:missing")).err()
+        );
+    }
+
+    #[test]
+    fn test_dispatch() {
+        // http、httpsはFetcherに任せる
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi",
+        );
+        let transport = Rc::new(transport);
+        let mut loader = Loader::new();
+        assert_eq!(
+            Some(Error::Network(NetworkError::NoTransport)),
+            loader.load(get("http://example.com/")).err()
+        );
+        loader.set_fetcher(Fetcher::new(transport.clone()));
+        let response = loader
+            .load(get("http://example.com/"))
+            .expect("failed to load");
+        assert_eq!("hi", response.body_text());
+        assert_eq!(1, transport.requests().len());
+
+        assert_eq!(
+            Some(Error::Load(LoadError::UnsupportedScheme("ftp".to_string()))),
+            loader.load(get("ftp://example.com/")).err()
+        );
+    }
+}
@@ -0,0 +1,325 @@
+pub mod sniff;
+
+use crate::http::parser::{is_http_whitespace, is_token_char};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+// https://mimesniff.spec.whatwg.org/#mime-type-representation
+// type/subtype;name=value の形式のMIMEタイプ。typeとsubtype、パラメーターの名前は小文字にしている
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeType {
+    top_level_type: String,
+    subtype: String,
+    parameters: Vec<(String, String)>, // 同じ名前のパラメーターは最初のものだけを残す
+}
+
+impl MimeType {
+    pub fn new(top_level_type: &str, subtype: &str) -> Self {
+        Self {
+            top_level_type: top_level_type.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            parameters: Vec::new(),
+        }
+    }
+
+    // https://mimesniff.spec.whatwg.org/#parse-a-mime-type
+    // 解釈できない場合はNoneを返す。不正なパラメーターは無視する
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim_matches(is_http_whitespace);
+        let (top_level_type, rest) = input.split_once('/')?;
+        if top_level_type.is_empty() || !top_level_type.bytes().all(is_token_char) {
+            return None;
+        }
+        let (subtype, mut rest) = match rest.find(';') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let subtype = subtype.trim_end_matches(is_http_whitespace);
+        if subtype.is_empty() || !subtype.bytes().all(is_token_char) {
+            return None;
+        }
+
+        let mut mime_type = Self::new(top_level_type, subtype);
+        while let Some(next) = rest.strip_prefix(';') {
+            rest = next.trim_start_matches(is_http_whitespace);
+
+            let end = rest.find([';', '=']).unwrap_or(rest.len());
+            let name = rest[..end].to_ascii_lowercase();
+            rest = &rest[end..];
+            rest = match rest.strip_prefix('=') {
+                Some(rest) => rest,
+                // 値のないパラメーター
+                None => continue,
+            };
+
+            let value = if rest.starts_with('"') {
+                let (value, after) = collect_quoted_string(rest);
+                // 引用符の後ろの ; までは捨てる
+                rest = &after[after.find(';').unwrap_or(after.len())..];
+                value
+            } else {
+                let end = rest.find(';').unwrap_or(rest.len());
+                let value = rest[..end].trim_end_matches(is_http_whitespace).to_string();
+                rest = &rest[end..];
+                if value.is_empty() {
+                    continue;
+                }
+                value
+            };
+
+            if !name.is_empty()
+                && name.bytes().all(is_token_char)
+                && value.chars().all(is_quoted_string_token_char)
+                && mime_type.parameter(&name).is_none()
+            {
+                mime_type.parameters.push((name, value));
+            }
+        }
+
+        Some(mime_type)
+    }
+
+    pub fn top_level_type(&self) -> String {
+        self.top_level_type.clone()
+    }
+
+    pub fn subtype(&self) -> String {
+        self.subtype.clone()
+    }
+
+    // https://mimesniff.spec.whatwg.org/#mime-type-essence
+    // パラメーターを除いた type/subtype
+    pub fn essence(&self) -> String {
+        let mut essence = self.top_level_type.clone();
+        essence.push('/');
+        essence.push_str(&self.subtype);
+        essence
+    }
+
+    // 名前の大文字小文字は区別しない
+    pub fn parameter(&self, name: &str) -> Option<String> {
+        self.parameters
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    }
+
+    pub fn parameters(&self) -> Vec<(String, String)> {
+        self.parameters.clone()
+    }
+
+    pub fn charset(&self) -> Option<String> {
+        self.parameter("charset")
+    }
+
+    // https://mimesniff.spec.whatwg.org/#image-mime-type
+    pub fn is_image(&self) -> bool {
+        self.top_level_type == "image"
+    }
+
+    // https://mimesniff.spec.whatwg.org/#audio-or-video-mime-type
+    pub fn is_audio_or_video(&self) -> bool {
+        self.top_level_type == "audio"
+            || self.top_level_type == "video"
+            || self.essence() == "application/ogg"
+    }
+
+    // https://mimesniff.spec.whatwg.org/#xml-mime-type
+    pub fn is_xml(&self) -> bool {
+        self.subtype.ends_with("+xml")
+            || matches!(self.essence().as_str(), "text/xml" | "application/xml")
+    }
+
+    // https://mimesniff.spec.whatwg.org/#html-mime-type
+    pub fn is_html(&self) -> bool {
+        self.essence() == "text/html"
+    }
+
+    // https://mimesniff.spec.whatwg.org/#javascript-mime-type
+    pub fn is_javascript(&self) -> bool {
+        matches!(
+            self.essence().as_str(),
+            "application/ecmascript"
+                | "application/javascript"
+                | "application/x-ecmascript"
+                | "application/x-javascript"
+                | "text/ecmascript"
+                | "text/javascript"
+                | "text/javascript1.0"
+                | "text/javascript1.1"
+                | "text/javascript1.2"
+                | "text/javascript1.3"
+                | "text/javascript1.4"
+                | "text/javascript1.5"
+                | "text/jscript"
+                | "text/livescript"
+                | "text/x-ecmascript"
+                | "text/x-javascript"
+        )
+    }
+
+    // https://mimesniff.spec.whatwg.org/#json-mime-type
+    pub fn is_json(&self) -> bool {
+        self.subtype.ends_with("+json")
+            || matches!(self.essence().as_str(), "application/json" | "text/json")
+    }
+}
+
+// https://mimesniff.spec.whatwg.org/#serializing-a-mime-type
+// トークンだけでできていない値は引用符で囲み、" と \ をエスケープする
+impl Display for MimeType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.essence())?;
+        for (name, value) in &self.parameters {
+            write!(f, ";{}=", name)?;
+            if !value.is_empty() && value.bytes().all(is_token_char) {
+                write!(f, "{}", value)?;
+                continue;
+            }
+            write!(f, "\"")?;
+            for c in value.chars() {
+                if c == '"' || c == '\\' {
+                    write!(f, "\\")?;
+                }
+                write!(f, "{}", c)?;
+            }
+            write!(f, "\"")?;
+        }
+        Ok(())
+    }
+}
+
+// レスポンスをどのように表示するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentHandler {
+    Html,     // HTMLとしてパースしてドキュメントを作る
+    Text,     // テキストをそのまま表示する
+    Image,    // 画像を表示するドキュメントを作る
+    Download, // 表示せずにダウンロードする
+}
+
+impl ContentHandler {
+    // https://html.spec.whatwg.org/multipage/browsing-the-web.html#loading-a-document
+    pub fn for_mime_type(mime_type: &MimeType) -> Self {
+        if mime_type.is_html() || mime_type.essence() == "application/xhtml+xml" {
+            ContentHandler::Html
+        } else if mime_type.is_image() {
+            ContentHandler::Image
+        } else if mime_type.top_level_type == "text"
+            || mime_type.is_json()
+            || mime_type.is_javascript()
+            || mime_type.is_xml()
+        {
+            ContentHandler::Text
+        } else {
+            ContentHandler::Download
+        }
+    }
+}
+
+impl Display for ContentHandler {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            ContentHandler::Html => "html",
+            ContentHandler::Text => "text",
+            ContentHandler::Image => "image",
+            ContentHandler::Download => "download",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// https://mimesniff.spec.whatwg.org/#http-quoted-string-token-code-point
+fn is_quoted_string_token_char(c: char) -> bool {
+    c == '\t' || (' '..='~').contains(&c) || ('\u{80}'..='\u{ff}').contains(&c)
+}
+
+// https://fetch.spec.whatwg.org/#collect-an-http-quoted-string
+// " で始まる入力から値を取り出し、値と閉じる " の後ろの残りを返す
+fn collect_quoted_string(input: &str) -> (String, &str) {
+    let mut value = String::new();
+    let mut chars = input.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, &input[i + 1..]),
+            '\\' => match chars.next() {
+                Some((_, escaped)) => value.push(escaped),
+                // 末尾の \ はそのまま値にする
+                None => {
+                    value.push('\\');
+                    return (value, "");
+                }
+            },
+            _ => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn parse(input: &str) -> MimeType {
+        MimeType::parse(input).expect("failed to parse mime type")
+    }
+
+    #[test]
+    fn test_parse() {
+        let mime_type = parse(" Text/HTML ; Charset=\"UTF-8\" ; foo=bar");
+        assert_eq!("text", mime_type.top_level_type());
+        assert_eq!("html", mime_type.subtype());
+        assert_eq!("text/html", mime_type.essence());
+        assert_eq!(Some("UTF-8".to_string()), mime_type.charset());
+        assert_eq!(Some("bar".to_string()), mime_type.parameter("FOO"));
+        assert_eq!("text/html;charset=UTF-8;foo=bar", mime_type.to_string());
+
+        assert_eq!(None, MimeType::parse("text"));
+        assert_eq!(None, MimeType::parse("/html"));
+        assert_eq!(None, MimeType::parse("text/"));
+        assert_eq!(None, MimeType::parse("te xt/html"));
+        assert_eq!(None, MimeType::parse("text/html x"));
+    }
+
+    #[test]
+    fn test_parameters() {
+        // 値のないもの、空のもの、重複したものは無視する
+        let mime_type = parse("text/plain;a;b=;charset=utf-8;charset=latin1;c=\"x;y\"z;d=1");
+        assert_eq!(
+            vec![
+                ("charset".to_string(), "utf-8".to_string()),
+                ("c".to_string(), "x;y".to_string()),
+                ("d".to_string(), "1".to_string()),
+            ],
+            mime_type.parameters()
+        );
+        assert_eq!(
+            "text/plain;charset=utf-8;c=\"x;y\";d=1",
+            mime_type.to_string()
+        );
+
+        let mime_type = parse("text/plain;name=\"a\\\"b\\\\\"");
+        assert_eq!(Some("a\"b\\".to_string()), mime_type.parameter("name"));
+        assert_eq!("text/plain;name=\"a\\\"b\\\\\"", mime_type.to_string());
+    }
+
+    #[test]
+    fn test_content_handler() {
+        let handler = |s: &str| ContentHandler::for_mime_type(&parse(s));
+        assert_eq!(ContentHandler::Html, handler("text/html; charset=utf-8"));
+        assert_eq!(ContentHandler::Html, handler("application/xhtml+xml"));
+        assert_eq!(ContentHandler::Text, handler("text/plain"));
+        assert_eq!(ContentHandler::Text, handler("application/json"));
+        assert_eq!(ContentHandler::Text, handler("application/ld+json"));
+        assert_eq!(ContentHandler::Text, handler("application/javascript"));
+        assert_eq!(ContentHandler::Image, handler("image/png"));
+        assert_eq!(ContentHandler::Image, handler("image/svg+xml"));
+        assert_eq!(
+            ContentHandler::Download,
+            handler("application/octet-stream")
+        );
+        assert_eq!(ContentHandler::Download, handler("application/pdf"));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::http::header::HeaderMap;
+use crate::mime::MimeType;
+
+// https://mimesniff.spec.whatwg.org/#reading-the-resource-header
+// MIMEタイプの判定に使うボディの先頭のバイト数
+pub const RESOURCE_HEADER_SIZE: usize = 1445;
+
+// https://mimesniff.spec.whatwg.org/#determining-the-computed-mime-type-of-a-resource
+// Content-Typeとボディの先頭のバイト列からMIMEタイプを決める
+// 仕様ではapplication/octet-streamは判定しないが、ファイル名の拡張子がわからない場合などに表示できるように、
+// スクリプトを実行できる形式（HTML、XML、PDF）を除いて判定する
+pub fn compute_mime_type(headers: &HeaderMap, resource_header: &[u8]) -> MimeType {
+    let header = &resource_header[..resource_header.len().min(RESOURCE_HEADER_SIZE)];
+    let content_type = headers.get("Content-Type");
+    // https://fetch.spec.whatwg.org/#x-content-type-options-header
+    let no_sniff = headers.get("X-Content-Type-Options").is_some_and(|value| {
+        value
+            .split(',')
+            .next()
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("nosniff"))
+    });
+
+    let supplied = match content_type.as_deref().and_then(MimeType::parse) {
+        Some(supplied)
+            if !matches!(
+                supplied.essence().as_str(),
+                "unknown/unknown" | "application/unknown" | "*/*"
+            ) =>
+        {
+            supplied
+        }
+        _ => return identify_unknown(header, !no_sniff),
+    };
+    if no_sniff {
+        return supplied;
+    }
+    if supplied.essence() == "application/octet-stream" {
+        return identify_unknown(header, false);
+    }
+
+    // https://mimesniff.spec.whatwg.org/#check-for-apache-bug-flag
+    // 古いApacheはファイルの種類に関係なくこれらの値を送るので、テキストかどうかを確かめる
+    if content_type.as_deref().is_some_and(|value| {
+        matches!(
+            value,
+            "text/plain"
+                | "text/plain; charset=ISO-8859-1"
+                | "text/plain; charset=iso-8859-1"
+                | "text/plain; charset=UTF-8"
+        )
+    }) {
+        return distinguish_text_or_binary(header);
+    }
+
+    if supplied.is_xml() || supplied.is_html() {
+        return supplied;
+    }
+    if supplied.is_image() {
+        if let Some(image) = match_image_type(header) {
+            return image;
+        }
+    }
+    supplied
+}
+
+// https://mimesniff.spec.whatwg.org/#rules-for-identifying-an-unknown-mime-type
+fn identify_unknown(header: &[u8], sniff_scriptable: bool) -> MimeType {
+    if sniff_scriptable {
+        // https://mimesniff.spec.whatwg.org/#terminology 空白を読み飛ばしてからタグの名前を探す
+        const HTML_TAGS: [&[u8]; 17] = [
+            b"<!DOCTYPE HTML",
+            b"<HTML",
+            b"<HEAD",
+            b"<SCRIPT",
+            b"<IFRAME",
+            b"<H1",
+            b"<DIV",
+            b"<FONT",
+            b"<TABLE",
+            b"<A",
+            b"<STYLE",
+            b"<TITLE",
+            b"<B",
+            b"<BODY",
+            b"<BR",
+            b"<P",
+            b"<!--",
+        ];
+        let body = skip_whitespace(header);
+        if HTML_TAGS.iter().any(|tag| match_tag(body, tag)) {
+            return MimeType::new("text", "html");
+        }
+        if body.starts_with(b"<?xml") {
+            return MimeType::new("text", "xml");
+        }
+        if header.starts_with(b"%PDF-") {
+            return MimeType::new("application", "pdf");
+        }
+    }
+
+    if header.starts_with(b"%!PS-Adobe-") {
+        return MimeType::new("application", "postscript");
+    }
+    if starts_with_bom(header) {
+        return MimeType::new("text", "plain");
+    }
+    if let Some(mime_type) = match_image_type(header).or_else(|| match_archive_type(header)) {
+        return mime_type;
+    }
+    if !header.iter().any(|b| is_binary_data_byte(*b)) {
+        return MimeType::new("text", "plain");
+    }
+    MimeType::new("application", "octet-stream")
+}
+
+// https://mimesniff.spec.whatwg.org/#rules-for-text-or-binary
+fn distinguish_text_or_binary(header: &[u8]) -> MimeType {
+    if starts_with_bom(header) || !header.iter().any(|b| is_binary_data_byte(*b)) {
+        return MimeType::new("text", "plain");
+    }
+    identify_unknown(header, false)
+}
+
+// https://mimesniff.spec.whatwg.org/#matching-an-image-type-pattern
+fn match_image_type(header: &[u8]) -> Option<MimeType> {
+    let subtype = if header.starts_with(&[0x00, 0x00, 0x01, 0x00])
+        || header.starts_with(&[0x00, 0x00, 0x02, 0x00])
+    {
+        "x-icon"
+    } else if header.starts_with(b"BM") {
+        "bmp"
+    } else if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        "gif"
+    } else if header.len() >= 14 && header.starts_with(b"RIFF") && &header[8..14] == b"WEBPVP" {
+        "webp"
+    } else if header.starts_with(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]) {
+        "png"
+    } else if header.starts_with(&[0xff, 0xd8, 0xff]) {
+        "jpeg"
+    } else {
+        return None;
+    };
+    Some(MimeType::new("image", subtype))
+}
+
+// https://mimesniff.spec.whatwg.org/#matching-an-archive-type-pattern
+fn match_archive_type(header: &[u8]) -> Option<MimeType> {
+    let subtype = if header.starts_with(&[0x1f, 0x8b, 0x08]) {
+        "x-gzip"
+    } else if header.starts_with(&[b'P', b'K', 0x03, 0x04]) {
+        "zip"
+    } else if header.starts_with(&[b'R', b'a', b'r', b' ', 0x1a, 0x07, 0x00]) {
+        "x-rar-compressed"
+    } else {
+        return None;
+    };
+    Some(MimeType::new("application", subtype))
+}
+
+// UTF-16BE、UTF-16LE、UTF-8のBOM
+fn starts_with_bom(header: &[u8]) -> bool {
+    header.starts_with(&[0xfe, 0xff])
+        || header.starts_with(&[0xff, 0xfe])
+        || header.starts_with(&[0xef, 0xbb, 0xbf])
+}
+
+// 大文字小文字を区別せずにタグの名前と比べ、続くバイトが空白か > であることを確かめる
+fn match_tag(body: &[u8], tag: &[u8]) -> bool {
+    body.len() > tag.len()
+        && body[..tag.len()].eq_ignore_ascii_case(tag)
+        && matches!(body[tag.len()], b' ' | b'>')
+}
+
+// https://mimesniff.spec.whatwg.org/#whitespace-byte
+fn skip_whitespace(header: &[u8]) -> &[u8] {
+    let start = header
+        .iter()
+        .position(|b| !matches!(b, 0x09 | 0x0a | 0x0c | 0x0d | 0x20))
+        .unwrap_or(header.len());
+    &header[start..]
+}
+
+// https://mimesniff.spec.whatwg.org/#binary-data-byte
+fn is_binary_data_byte(b: u8) -> bool {
+    matches!(b, 0x00..=0x08 | 0x0b | 0x0e..=0x1a | 0x1c..=0x1f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+
+    fn sniff(headers: &[(&str, &str)], body: &[u8]) -> String {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(name, value);
+        }
+        compute_mime_type(&map, body).to_string()
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        assert_eq!("text/html", sniff(&[], b"\n  <!doctype html><html>"));
+        assert_eq!("text/html", sniff(&[], b"<p>hello</p>"));
+        assert_eq!(
+            "text/html",
+            sniff(&[("Content-Type", "*/*")], b"<!-- x -->")
+        );
+        // タグの名前の後ろが空白か > でない場合はHTMLとみなさない
+        assert_eq!("text/plain", sniff(&[], b"<pre>hello</pre>"));
+        assert_eq!("text/xml", sniff(&[], b"<?xml version=\"1.0\"?>"));
+        assert_eq!("application/pdf", sniff(&[], b"%PDF-1.7"));
+        assert_eq!("image/png", sniff(&[], b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert_eq!("image/gif", sniff(&[], b"GIF89a\x01\0\x01\0"));
+        assert_eq!("image/webp", sniff(&[], b"RIFF\0\0\0\0WEBPVP8 "));
+        assert_eq!("application/zip", sniff(&[], b"PK\x03\x04\x14\0"));
+        assert_eq!("text/plain", sniff(&[], "こんにちは".as_bytes()));
+        assert_eq!("text/plain", sniff(&[], b"\xff\xfeh\0i\0"));
+        assert_eq!("application/octet-stream", sniff(&[], b"\0\x01\x02\x03"));
+
+        // nosniffの場合もContent-Typeがなければ判定するが、HTMLとはみなさない
+        assert_eq!(
+            "text/plain",
+            sniff(&[("X-Content-Type-Options", "nosniff")], b"<html>")
+        );
+    }
+
+    #[test]
+    fn test_supplied_type() {
+        assert_eq!(
+            "text/html;charset=utf-8",
+            sniff(&[("Content-Type", "text/html; charset=utf-8")], b"\0\0\0")
+        );
+        assert_eq!(
+            "application/json",
+            sniff(&[("Content-Type", "application/json")], b"<html>")
+        );
+        // 画像の場合は中身の形式を優先する
+        assert_eq!(
+            "image/jpeg",
+            sniff(&[("Content-Type", "image/png")], b"\xff\xd8\xff\xe0")
+        );
+        assert_eq!(
+            "image/png",
+            sniff(&[("Content-Type", "image/png")], b"unknown image")
+        );
+    }
+
+    #[test]
+    fn test_octet_stream() {
+        let octet_stream = [("Content-Type", "application/octet-stream")];
+        assert_eq!("image/png", sniff(&octet_stream, b"\x89PNG\r\n\x1a\n"));
+        assert_eq!("text/plain", sniff(&octet_stream, b"plain text"));
+        // スクリプトを実行できる形式にはしない
+        assert_eq!("text/plain", sniff(&octet_stream, b"<html><body>"));
+        assert_eq!(
+            "application/octet-stream",
+            sniff(&octet_stream, b"%PDF-1.7\n\x01\x02")
+        );
+        assert_eq!(
+            "application/octet-stream",
+            sniff(
+                &[
+                    ("Content-Type", "application/octet-stream"),
+                    ("X-Content-Type-Options", "nosniff")
+                ],
+                b"plain text"
+            )
+        );
+    }
+
+    #[test]
+    fn test_apache_bug() {
+        let apache = [("Content-Type", "text/plain; charset=ISO-8859-1")];
+        assert_eq!("text/plain", sniff(&apache, b"hello"));
+        assert_eq!("image/gif", sniff(&apache, b"GIF87a\x01\0\x01\0"));
+        // 値が少しでも違う場合はそのまま使う
+        assert_eq!(
+            "text/plain;charset=utf-8",
+            sniff(
+                &[("Content-Type", "text/plain;charset=utf-8")],
+                b"GIF87a\x01\0"
+            )
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use core::fmt::Debug;
+
+// 予測できない乱数を返す。no_stdの環境では乱数を得る方法が決まっていないので、外から差し替えられるようにする
+pub trait RandomSource: Debug {
+    // bufを乱数で埋める
+    fn fill(&self, buf: &mut [u8]);
+
+    // 予測できない乱数を返せない場合はfalseを返す
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+// 乱数を得られない環境で使う。bufは0で埋める
+// Digest認証のcnonceのように予測できないことが求められる値は、nonceや時刻から作るしかなくなる
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoRandom;
+
+impl NoRandom {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl RandomSource for NoRandom {
+    fn fill(&self, buf: &mut [u8]) {
+        buf.fill(0);
+    }
+
+    fn is_available(&self) -> bool {
+        false
+    }
+}
@@ -1,16 +1,54 @@
-use alloc::{string::{String, ToString}, vec::Vec};
+use alloc::{format, string::{String, ToString}, vec::Vec};
 
 use crate::renderer::css::token::{CssToken, CssTokenizer};
-use core::iter::Peekable;
+use crate::renderer::css::CssError;
 
 #[derive(Debug, Clone)]
 pub struct CssParser {
-    t: Peekable<CssTokenizer>,
+    t: CssTokenizer,
+    peeked: Option<(Option<CssToken>, usize)>, // 先読みしたトークンとその開始位置
+    offset: usize, // 最後に取り出したトークンの開始位置
+    errors: Vec<CssError>,
 }
 
 impl CssParser {
     pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+        Self { t, peeked: None, offset: 0, errors: Vec::new() }
+    }
+
+    // これまでに見つかった解析エラー
+    pub fn errors(&self) -> Vec<CssError> {
+        self.errors.clone()
+    }
+
+    // 次のトークンを消費せずに返す
+    fn peek(&mut self) -> Option<&CssToken> {
+        if self.peeked.is_none() {
+            let token = self.t.next();
+            self.peeked = Some((token, self.t.token_offset()));
+        }
+        self.peeked.as_ref().and_then(|(token, _)| token.as_ref())
+    }
+
+    fn next_token(&mut self) -> Option<CssToken> {
+        let (token, offset) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => {
+                let token = self.t.next();
+                (token, self.t.token_offset())
+            }
+        };
+        self.offset = offset;
+        token
+    }
+
+    // 最後に取り出したトークンが期待したものではなかったことを記録する
+    fn unexpected(&mut self, token: Option<&CssToken>) {
+        let error = match token {
+            Some(token) => CssError::UnexpectedToken { token: format!("{:?}", token), offset: self.offset },
+            None => CssError::UnexpectedEof { offset: self.offset },
+        };
+        self.errors.push(error);
     }
 
     pub fn parse_stylesheet(&mut self) -> StyleSheet {
@@ -22,7 +60,7 @@ impl CssParser {
     fn consume_list_of_rules(&mut self) -> Vec<QualifiedRule> {
         let mut rules = Vec::new();
         loop {
-            let token = match self.t.peek() {
+            let token = match self.peek() {
                 Some(t) => t,
                 None => return rules,
             };
@@ -49,16 +87,21 @@ impl CssParser {
     fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
         let mut rule = QualifiedRule::new();
         loop {
-            let token = match self.t.peek() {
+            let token = match self.peek() {
                 Some(t) => t,
-                None => return None,
+                None => {
+                    // 宣言ブロックが始まる前に入力が終わった
+                    self.next_token();
+                    self.unexpected(None);
+                    return None;
+                }
             };
 
             match token {
                 CssToken::OpenCurly => {
                     // 次のトークンが { のとき、宣言ブロックの開始を表す
                     // 宣言ブロックの解釈を行い、ルールのdeclarationsフィールドに設定する
-                    assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
+                    assert_eq!(self.next_token(), Some(CssToken::OpenCurly));
                     rule.set_declarations(self.consume_list_of_declarations());
                     return Some(rule);
                 }
@@ -72,9 +115,12 @@ impl CssParser {
     }
 
     fn consume_selector(&mut self) -> Selector {
-        let token = match self.t.next() {
+        let token = match self.next_token() {
             Some(t) => t,
-            None => panic!("should have a token but got None"),
+            None => {
+                self.unexpected(None);
+                return Selector::UnknownSelector;
+            }
         };
 
         match token {
@@ -83,53 +129,60 @@ impl CssParser {
             CssToken::Delim(delim) => {
                 // ピリオドのときクラスセレクタを作成して返す
                 if delim == '.' {
-                    return Selector::ClassSelector(self.consume_ident());
+                    return match self.consume_ident() {
+                        Some(ident) => Selector::ClassSelector(ident),
+                        None => Selector::UnknownSelector,
+                    };
                 }
-                panic!("Parse error: {:?} is an unexpected token.", token);
+                self.unexpected(Some(&token));
+                Selector::UnknownSelector
             }
             CssToken::Ident(ident) => {
                 // 識別子のときタイプセレクタを作成して返す
                 // a:hoverのようなセレクタはタイプセレクタとして扱う
-                if self.t.peek() == Some(&CssToken::Colon) {
+                if self.peek() == Some(&CssToken::Colon) {
                     // コロンが出てきた場合は宣言ブロックの開始直前までトークンを進める
-                    while self.t.peek() != Some(&CssToken::OpenCurly) {
-                        self.t.next();
-                    }
+                    self.skip_to_block();
                 }
                 Selector::TypeSelector(ident.to_string())
             }
             CssToken::AtKeyword(_keyword) => {
                 // @から始まるルールを無視するための宣言ブロックの開始直前までトークンを進める
-                while self.t.peek() != Some(&CssToken::OpenCurly) {
-                    self.t.next();
-                }
+                self.skip_to_block();
                 Selector::UnknownSelector
             }
             _ => {
-                self.t.next();
+                self.next_token();
                 Selector::UnknownSelector
             }
         }
     }
 
+    // 宣言ブロックの開始直前までトークンを進める
+    fn skip_to_block(&mut self) {
+        while matches!(self.peek(), Some(t) if *t != CssToken::OpenCurly) {
+            self.next_token();
+        }
+    }
+
     fn consume_list_of_declarations(&mut self) -> Vec<Declaration> {
         let mut declarations = Vec::new();
         loop {
-            let token = match self.t.peek() {
+            let token = match self.peek() {
                 Some(t) => t,
                 None => return declarations,
             };
 
             match token {
                 CssToken::CloseCurly => {
-                    assert_eq!(self.t.next(), Some(CssToken::CloseCurly));
+                    assert_eq!(self.next_token(), Some(CssToken::CloseCurly));
                     // 閉じ並み括弧が現れたら、今まで作成した宣言を返す
                     return declarations;
                 }
                 CssToken::SemiColon => {
                     // 次のトークンがセミコロンの場合、1つの宣言が終了したことを表す
                     // 単にセミコロンのトークンを消費し何もしない
-                    assert_eq!(self.t.next(), Some(CssToken::SemiColon));
+                    assert_eq!(self.next_token(), Some(CssToken::SemiColon));
                 }
                 CssToken::Ident(ref _ident) => {
                     // 次のトークンが識別子のとき、1つの宣言を解釈し追加する
@@ -138,50 +191,58 @@ impl CssParser {
                     }
                 }
                 _ => {
-                    self.t.next();
+                    self.next_token();
                 }
             }
         }
     }
 
     fn consume_declaration(&mut self) -> Option<Declaration> {
-        self.t.peek()?;
+        self.peek()?;
 
         let mut declaration = Declaration::new();
         // プロパティを処理する
-        declaration.set_property(self.consume_ident());
-        match self.t.next() {
+        declaration.set_property(self.consume_ident()?);
+        match self.next_token() {
             Some(token) => match token {
                 CssToken::Colon => {}
                 // トークンが転んでない場合はパースエラーなのでNoneを返す
-                _ => return None, 
+                _ => {
+                    self.unexpected(Some(&token));
+                    return None;
+                }
             },
-            None => return None,
+            None => {
+                self.unexpected(None);
+                return None;
+            }
         }
 
-        declaration.set_value(self.consume_component_value());
+        declaration.set_value(self.consume_component_value()?);
         Some(declaration)
     }
 
-    fn consume_ident(&mut self) -> String {
+    fn consume_ident(&mut self) -> Option<String> {
         // 識別子トークンを消費し文字列を取得する
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
+        let token = self.next_token();
 
         match token {
-            CssToken::Ident(ref ident) => ident.to_string(),
+            Some(CssToken::Ident(ref ident)) => Some(ident.to_string()),
             _ => {
-                panic!("Parse error: {:?} is an unexpected token.", token);
+                self.unexpected(token.as_ref());
+                None
             }
         }
     }
 
     // https://www.w3.org/TR/css-syntax-3/#consume-component-value
-    fn consume_component_value(&mut self) -> ComponentValue {
+    fn consume_component_value(&mut self) -> Option<ComponentValue> {
         // コンポーネント値はCSSのトークンと同等なので、トークンが存在することを確認してそのまま帰す
-        self.t.next().expect("should have a token in consume_component_value")
+        let token = self.next_token();
+        if token.is_none() {
+            self.unexpected(None);
+        }
+        token
     }
 }
 
@@ -191,6 +252,12 @@ pub struct StyleSheet {
     pub rules: Vec<QualifiedRule>,
 }
 
+impl Default for StyleSheet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StyleSheet {
     pub fn new() -> Self {
         Self { rules: Vec::new() }
@@ -209,6 +276,12 @@ pub struct QualifiedRule {
     pub declarations: Vec<Declaration>,
 }
 
+impl Default for QualifiedRule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl QualifiedRule {
     pub fn new() -> Self {
         Self {
@@ -240,6 +313,12 @@ pub struct Declaration {
     pub value: ComponentValue,
 }
 
+impl Default for Declaration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Declaration {
     pub fn new() -> Self {
         Self {
@@ -339,6 +418,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_errors() {
+        let style = "p > a { color: red; } h1 { color blue; } div { color:".to_string();
+        let t = CssTokenizer::new(style);
+        let mut parser = CssParser::new(t);
+        let cssom = parser.parse_stylesheet();
+
+        assert_eq!(cssom.rules.len(), 3);
+        assert_eq!(
+            parser.errors(),
+            vec![
+                CssError::UnexpectedToken { token: "Delim('>')".to_string(), offset: 2 },
+                CssError::UnexpectedToken { token: "Ident(\"blue\")".to_string(), offset: 33 },
+                CssError::UnexpectedEof { offset: 53 },
+            ]
+        );
+    }
+
     #[test]
     fn test_multiple_rules() {
         let style = "p { content: \"Hey\"; } h1 { font-size: 40; color: blue; }".to_string();
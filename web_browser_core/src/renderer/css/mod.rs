@@ -1,2 +1,33 @@
 pub mod token;
 pub mod cssom;
+
+use crate::error::Error;
+use alloc::string::String;
+use core::fmt::Display;
+
+// https://www.w3.org/TR/css-syntax-3/#parse-error
+// 解析は止めずに記録だけする。offsetはトークンが始まる入力のバイト位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CssError {
+    UnexpectedToken { token: String, offset: usize },
+    UnexpectedEof { offset: usize },
+}
+
+impl Display for CssError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CssError::UnexpectedToken { token, offset } => {
+                write!(f, "unexpected token {} at offset {}", token, offset)
+            }
+            CssError::UnexpectedEof { offset } => {
+                write!(f, "unexpected end of input at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl From<CssError> for Error {
+    fn from(e: CssError) -> Self {
+        Error::Css(e)
+    }
+}
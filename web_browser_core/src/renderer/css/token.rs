@@ -20,11 +20,28 @@ pub enum CssToken {
 pub struct CssTokenizer {
     pos: usize,
     input: Vec<char>,
+    counted: usize,       // バイト数を数え終わった文字の数
+    counted_bytes: usize, // counted文字分のバイト数
+    token_offset: usize,  // 最後に返したトークンの開始位置（バイト）
 }
 
 impl CssTokenizer {
     pub fn new(css: String) -> Self {
-        Self { pos: 0, input: css.chars().collect() }
+        Self { pos: 0, input: css.chars().collect(), counted: 0, counted_bytes: 0, token_offset: 0 }
+    }
+
+    // 最後に返したトークンが始まるバイト位置。入力の最後に到達した後は入力のバイト数を返す
+    pub fn token_offset(&self) -> usize {
+        self.token_offset
+    }
+
+    // posまでの入力のバイト数。トークンの開始位置は増えていくだけなので前回の続きから数える
+    fn byte_offset(&mut self, pos: usize) -> usize {
+        while self.counted < pos && self.counted < self.input.len() {
+            self.counted_bytes += self.input[self.counted].len_utf8();
+            self.counted += 1;
+        }
+        self.counted_bytes
     }
 
     // https://www.w3.org/TR/css-syntax-3/#consume-a-string-token
@@ -32,13 +49,12 @@ impl CssTokenizer {
     fn consume_string_token(&mut self) -> String {
         let mut s = String::new();
         loop {
-            // 現在のトークンの位置が入力の長さを超えたら文字列を返す
-            if self.pos >= self.input.len() {
-                return s;
-            }
             self.pos += 1;
-            // 現在のトークンを取得
-            let c = self.input[self.pos];
+            // 現在のトークンを取得。閉じられずに入力の最後に到達したら文字列を返す
+            let c = match self.input.get(self.pos) {
+                Some(c) => *c,
+                None => return s,
+            };
             match c {
                 '"' | '\'' => break, // ダブルクォーテーション、シングルクォーテーションが出たので文字列を返す
                 _ => s.push(c),
@@ -90,7 +106,10 @@ impl CssTokenizer {
         s.push(self.input[self.pos]);
         loop {
             self.pos += 1;
-            let c = self.input[self.pos];
+            let c = match self.input.get(self.pos) {
+                Some(c) => *c,
+                None => break,
+            };
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
                     s.push(c);
@@ -109,9 +128,11 @@ impl Iterator for CssTokenizer {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             if self.pos >= self.input.len() {
+                self.token_offset = self.byte_offset(self.input.len());
                 return None;
             }
 
+            self.token_offset = self.byte_offset(self.pos);
             let c = self.input[self.pos];
             let token = match c {
                 // 次のトークンを決定する
@@ -151,7 +172,8 @@ impl Iterator for CssTokenizer {
                 '@' => {
                     // 次の3文字が識別子として有効な文字の場合、<at-keyword-token>トークンを作成して返す
                     // それ以外の場合、<delim-token>を返す
-                    if self.input[self.pos + 1].is_ascii_alphabetic() && self.input[self.pos + 2].is_alphanumeric() && self.input[self.pos + 3].is_alphanumeric() {
+                    let at = |i: usize| self.input.get(self.pos + i).copied().unwrap_or('\0');
+                    if at(1).is_ascii_alphabetic() && at(2).is_alphanumeric() && at(3).is_alphanumeric() {
                         // skip '@'
                         self.pos += 1;
                         let t = CssToken::AtKeyword(self.consume_ident_token());
@@ -167,9 +189,8 @@ impl Iterator for CssTokenizer {
                     self.pos -= 1;
                     t
                 }
-                _ => {
-                    unimplemented!("char {} is not supported yet", c);
-                }
+                // それ以外の文字は1文字のdelimトークンとして返し、パーサーでエラーにする
+                _ => CssToken::Delim(c),
             };
 
             self.pos += 1;
@@ -248,6 +269,19 @@ mod tests {
         assert!(t.next().is_none());
     }
 
+    #[test]
+    fn test_unknown_char() {
+        let style = "p > a".to_string();
+        let mut t = CssTokenizer::new(style);
+        assert_eq!(Some(CssToken::Ident("p".to_string())), t.next());
+        assert_eq!(0, t.token_offset());
+        assert_eq!(Some(CssToken::Delim('>')), t.next());
+        assert_eq!(2, t.token_offset());
+        assert_eq!(Some(CssToken::Ident("a".to_string())), t.next());
+        assert!(t.next().is_none());
+        assert_eq!(5, t.token_offset());
+    }
+
     #[test]
     fn test_multiple_rules() {
         let style = "p { content: \"Hey\"; } h1 { font-size: 40; color: blue; }".to_string();
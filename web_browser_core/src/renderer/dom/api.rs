@@ -1,6 +1,6 @@
 use core::cell::RefCell;
 
-use alloc::{rc::Rc, vec::Vec};
+use alloc::{rc::Rc, string::ToString, vec::Vec};
 
 use crate::renderer::dom::node::{Element, ElementKind, Node, NodeKind};
 
@@ -28,4 +28,36 @@ pub fn get_target_element_node(
         }
         None => None,
     }
-}
\ No newline at end of file
+}
+
+// nodeとその子孫、兄弟ノードのうち、element_kindの要素をドキュメントの順にすべて返す
+pub fn get_element_nodes(
+    node: Option<Rc<RefCell<Node>>>,
+    element_kind: ElementKind,
+) -> Vec<Rc<RefCell<Node>>> {
+    let mut nodes = Vec::new();
+    let mut current = node;
+    while let Some(n) = current {
+        if n.borrow().get_element().is_some_and(|e| e.kind() == element_kind) {
+            nodes.push(n.clone());
+        }
+        nodes.extend(get_element_nodes(n.borrow().first_child(), element_kind));
+        current = n.borrow().next_sibling();
+    }
+    nodes
+}
+
+// https://dom.spec.whatwg.org/#concept-node-append
+// childをparentの最後の子ノードとして追加する
+pub fn append_child(parent: &Rc<RefCell<Node>>, child: Rc<RefCell<Node>>) {
+    let last_child = parent.borrow().last_child().upgrade();
+    match last_child {
+        Some(last) => {
+            last.borrow_mut().set_next_sibling(Some(child.clone()));
+            child.borrow_mut().set_previous_sibling(Rc::downgrade(&last));
+        }
+        None => parent.borrow_mut().set_first_child(Some(child.clone())),
+    }
+    parent.borrow_mut().set_last_child(Rc::downgrade(&child));
+    child.borrow_mut().set_parent(Rc::downgrade(parent));
+}
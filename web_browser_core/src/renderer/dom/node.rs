@@ -133,6 +133,12 @@ pub struct Window {
     document: Rc<RefCell<Node>>,
 }
 
+impl Default for Window {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Window {
     pub fn new() -> Self {
         let window = Self {
@@ -172,15 +178,23 @@ impl Element {
     }
 
     pub fn is_block_element(&self) -> bool {
-        match self.kind {
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P => true,
-            _ => false,
-        }
+        matches!(
+            self.kind,
+            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P
+        )
     }
 
     pub fn attributes(&self) -> Vec<Attribute> {
         self.attributes.clone()
     }
+
+    // 指定した名前の属性の値を返す
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name() == name)
+            .map(|attr| attr.value())
+    }
 }
 
 // https://dom.spec.whatwg.org/#interface-element
@@ -188,6 +202,8 @@ impl Element {
 pub enum ElementKind {
     Html,   // https://html.spec.whatwg.org/multipage/semantics.html#the-html-element
     Head,   // https://html.spec.whatwg.org/multipage/semantics.html#the-head-element
+    Base,   // https://html.spec.whatwg.org/multipage/semantics.html#the-base-element
+    Title,  // https://html.spec.whatwg.org/multipage/semantics.html#the-title-element
     Style,  // https://html.spec.whatwg.org/multipage/semantics.html#the-style-element
     Script, // https://html.spec.whatwg.org/multipage/scripting.html#the-script-element
     Body,   // https://html.spec.whatwg.org/multipage/sections.html#the-body-element
@@ -195,6 +211,7 @@ pub enum ElementKind {
     H1,     // https://html.spec.whatwg.org/multipage/sections.html#the-h1,-h2,-h3,-h4,-h5,-and-h6-elements
     H2,     // https://html.spec.whatwg.org/multipage/sections.html#the-h1,-h2,-h3,-h4,-h5,-and-h6-elements
     A,      // https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-a-element
+    Img,    // https://html.spec.whatwg.org/multipage/embedded-content.html#the-img-element
 }
 
 impl FromStr for ElementKind {
@@ -204,6 +221,8 @@ impl FromStr for ElementKind {
         match s {
             "html" => Ok(Self::Html),
             "head" => Ok(Self::Head),
+            "base" => Ok(Self::Base),
+            "title" => Ok(Self::Title),
             "style" => Ok(Self::Style),
             "script" => Ok(Self::Script),
             "body" => Ok(Self::Body),
@@ -211,16 +230,19 @@ impl FromStr for ElementKind {
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
             "a" => Ok(ElementKind::A),
+            "img" => Ok(ElementKind::Img),
             _ => Err(format!("unimplemented element name {:?}", s)),
         }
     }
 }
 
-impl Display for Element {
+impl Display for ElementKind {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let s = match self {
             ElementKind::Html => "html",
             ElementKind::Head => "head",
+            ElementKind::Base => "base",
+            ElementKind::Title => "title",
             ElementKind::Style => "style",
             ElementKind::Script => "script",
             ElementKind::Body => "body",
@@ -228,6 +250,7 @@ impl Display for Element {
             ElementKind::H2 => "h2",
             ElementKind::P => "p",
             ElementKind::A => "a",
+            ElementKind::Img => "img",
         };
         write!(f, "{}", s)
     }
@@ -0,0 +1,351 @@
+use crate::encoding::Encoding;
+use crate::mime::MimeType;
+use crate::renderer::dom::node::Window;
+use crate::url::Url;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// 戻る・進むで読み込み直さずに表示できるように、DOMツリーを残しておく項目の数
+// 今の項目から遠いものから捨てる
+pub const MAX_CACHED_DOCUMENTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScrollPosition {
+    x: i64,
+    y: i64,
+}
+
+impl ScrollPosition {
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn x(&self) -> i64 {
+        self.x
+    }
+
+    pub fn y(&self) -> i64 {
+        self.y
+    }
+}
+
+// 戻る・進むで表示し直すためのドキュメント
+#[derive(Debug, Clone)]
+pub struct CachedDocument {
+    frame: Rc<RefCell<Window>>,
+    content_type: Option<MimeType>,
+    character_set: Option<Encoding>,
+}
+
+impl CachedDocument {
+    pub fn new(
+        frame: Rc<RefCell<Window>>,
+        content_type: Option<MimeType>,
+        character_set: Option<Encoding>,
+    ) -> Self {
+        Self {
+            frame,
+            content_type,
+            character_set,
+        }
+    }
+
+    pub fn frame(&self) -> Rc<RefCell<Window>> {
+        self.frame.clone()
+    }
+
+    pub fn content_type(&self) -> Option<MimeType> {
+        self.content_type.clone()
+    }
+
+    pub fn character_set(&self) -> Option<Encoding> {
+        self.character_set
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/browsing-the-web.html#session-history-entry
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    url: Url,
+    title: String,
+    scroll_position: ScrollPosition,
+    document: Option<CachedDocument>, // Noneの場合は表示するときにURLを読み込み直す
+}
+
+impl HistoryEntry {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            title: String::new(),
+            scroll_position: ScrollPosition::default(),
+            document: None,
+        }
+    }
+
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    pub fn scroll_position(&self) -> ScrollPosition {
+        self.scroll_position
+    }
+
+    pub fn set_scroll_position(&mut self, scroll_position: ScrollPosition) {
+        self.scroll_position = scroll_position;
+    }
+
+    pub fn document(&self) -> Option<CachedDocument> {
+        self.document.clone()
+    }
+
+    pub fn set_document(&mut self, document: Option<CachedDocument>) {
+        self.document = document;
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/history.html#joint-session-history
+// ページごとの履歴。今の項目より後ろの項目は、新しく移動したときに取り除く
+#[derive(Debug, Clone, Default)]
+pub struct SessionHistory {
+    entries: Vec<HistoryEntry>,
+    current: usize, // 今の項目の位置。entriesが空の場合は使わない
+}
+
+impl SessionHistory {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.entries.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        Some(self.current)
+    }
+
+    pub fn current(&self) -> Option<HistoryEntry> {
+        self.entries.get(self.current).cloned()
+    }
+
+    pub fn get(&self, index: usize) -> Option<HistoryEntry> {
+        self.entries.get(index).cloned()
+    }
+
+    // https://html.spec.whatwg.org/multipage/browsing-the-web.html#finalize-a-cross-document-navigation
+    // 今の項目より後ろを取り除いてから追加し、追加した項目を今の項目にする
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.current + 1);
+        }
+        self.entries.push(entry);
+        self.current = self.entries.len() - 1;
+        self.evict_documents();
+    }
+
+    // 今の項目を置き換える。項目がない場合は追加する
+    pub fn replace_current(&mut self, entry: HistoryEntry) {
+        match self.entries.get_mut(self.current) {
+            Some(current) => *current = entry,
+            None => self.push(entry),
+        }
+        self.evict_documents();
+    }
+
+    // 今の項目からdelta個離れた項目の位置を返す。範囲外の場合はNoneを返す
+    pub fn index_by_delta(&self, delta: isize) -> Option<usize> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let index = self.current.checked_add_signed(delta)?;
+        if index < self.entries.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_current_index(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.current = index;
+            self.evict_documents();
+        }
+    }
+
+    // 項目と今の位置だけを写した履歴。DOMツリーは写さないので、表示するときにURLを読み込み直す
+    pub fn without_documents(&self) -> Self {
+        let mut history = self.clone();
+        for entry in history.entries.iter_mut() {
+            entry.document = None;
+        }
+        history
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.index_by_delta(-1).is_some()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.index_by_delta(1).is_some()
+    }
+
+    // 残しておくDOMツリーがMAX_CACHED_DOCUMENTSを超えたら、今の項目から遠いものから捨てる
+    // フラグメントだけが違う項目は同じフレームを共有しているので、1つとして数える
+    fn evict_documents(&mut self) {
+        let mut cached: Vec<usize> = (0..self.entries.len())
+            .filter(|i| self.entries[*i].document.is_some())
+            .collect();
+        cached.sort_by_key(|i| i.abs_diff(self.current));
+
+        let mut kept: Vec<Rc<RefCell<Window>>> = Vec::new();
+        for i in cached {
+            let frame = match &self.entries[i].document {
+                Some(document) => document.frame(),
+                None => continue,
+            };
+            if kept.iter().any(|f| Rc::ptr_eq(f, &frame)) {
+                continue;
+            }
+            if kept.len() < MAX_CACHED_DOCUMENTS {
+                kept.push(frame);
+            } else {
+                self.entries[i].document = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn entry(path: &str) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(
+            Url::parse(&format!("http://example.com/{}", path)).expect("failed to parse url"),
+        );
+        entry.set_document(Some(CachedDocument::new(
+            Rc::new(RefCell::new(Window::new())),
+            None,
+            None,
+        )));
+        entry
+    }
+
+    fn paths(history: &SessionHistory) -> Vec<String> {
+        history
+            .entries()
+            .iter()
+            .map(|entry| entry.url().path())
+            .collect()
+    }
+
+    #[test]
+    fn test_push_truncates_forward_entries() {
+        let mut history = SessionHistory::new();
+        assert_eq!(None, history.current_index());
+        assert!(!history.can_go_back());
+
+        history.push(entry("a"));
+        history.push(entry("b"));
+        history.push(entry("c"));
+        assert_eq!(Some(2), history.current_index());
+        assert_eq!(Some(0), history.index_by_delta(-2));
+        assert_eq!(None, history.index_by_delta(-3));
+        assert_eq!(None, history.index_by_delta(1));
+
+        history.set_current_index(0);
+        assert!(history.can_go_forward());
+        history.push(entry("d"));
+        assert_eq!(vec!["/a".to_string(), "/d".to_string()], paths(&history));
+        assert_eq!(Some(1), history.current_index());
+        assert!(!history.can_go_forward());
+    }
+
+    #[test]
+    fn test_evict_documents() {
+        let mut history = SessionHistory::new();
+        for i in 0..MAX_CACHED_DOCUMENTS + 2 {
+            history.push(entry(&i.to_string()));
+        }
+        // 今の項目から遠い、古い項目のDOMツリーから捨てる
+        let cached: Vec<bool> = history
+            .entries()
+            .iter()
+            .map(|entry| entry.document().is_some())
+            .collect();
+        assert_eq!(MAX_CACHED_DOCUMENTS, cached.iter().filter(|c| **c).count());
+        assert!(!cached[0] && !cached[1] && cached[2]);
+    }
+
+    #[test]
+    fn test_evict_documents_shared_frame() {
+        let mut history = SessionHistory::new();
+        history.push(entry("0"));
+        // フラグメントへの移動で増えた項目は、同じフレームを共有する
+        for i in 1..MAX_CACHED_DOCUMENTS + 2 {
+            let mut fragment = HistoryEntry::new(
+                Url::parse(&format!("http://example.com/0#{}", i)).expect("failed to parse url"),
+            );
+            fragment.set_document(history.current().and_then(|entry| entry.document()));
+            history.push(fragment);
+        }
+        history.push(entry("1"));
+        assert!(history
+            .entries()
+            .iter()
+            .all(|entry| entry.document().is_some()));
+    }
+
+    #[test]
+    fn test_without_documents() {
+        let mut history = SessionHistory::new();
+        history.push(entry("a"));
+        let mut b = entry("b");
+        b.set_title("B".to_string());
+        b.set_scroll_position(ScrollPosition::new(0, 10));
+        history.push(b);
+        history.set_current_index(0);
+
+        let copied = history.without_documents();
+        assert_eq!(paths(&history), paths(&copied));
+        assert_eq!(Some(0), copied.current_index());
+        let b = copied.get(1).expect("no entry");
+        assert_eq!("B", b.title());
+        assert_eq!(ScrollPosition::new(0, 10), b.scroll_position());
+        assert!(copied
+            .entries()
+            .iter()
+            .all(|entry| entry.document().is_none()));
+        assert!(history
+            .entries()
+            .iter()
+            .all(|entry| entry.document().is_some()));
+    }
+}
@@ -1,3 +1,38 @@
 pub mod attribute;
 pub mod parser;
 pub mod token;
+
+use crate::error::Error;
+use core::fmt::Display;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#parse-errors
+// 解析は止めずに記録だけする。lineとcolumnはエラーを見つけた文字の位置で、1から数える
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlError {
+    EofInTag { line: usize, column: usize },
+    InvalidFirstCharacterOfTagName { line: usize, column: usize },
+    MissingEndTagName { line: usize, column: usize },
+    UnexpectedSolidusInTag { line: usize, column: usize },
+}
+
+impl Display for HtmlError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (code, line, column) = match self {
+            HtmlError::EofInTag { line, column } => ("eof-in-tag", line, column),
+            HtmlError::InvalidFirstCharacterOfTagName { line, column } => {
+                ("invalid-first-character-of-tag-name", line, column)
+            }
+            HtmlError::MissingEndTagName { line, column } => ("missing-end-tag-name", line, column),
+            HtmlError::UnexpectedSolidusInTag { line, column } => {
+                ("unexpected-solidus-in-tag", line, column)
+            }
+        };
+        write!(f, "{} at line {}, column {}", code, line, column)
+    }
+}
+
+impl From<HtmlError> for Error {
+    fn from(e: HtmlError) -> Self {
+        Error::Html(e)
+    }
+}
@@ -5,6 +5,7 @@ use crate::renderer::dom::node::NodeKind;
 use crate::renderer::dom::node::Window;
 use crate::renderer::html::attribute::Attribute;
 use crate::renderer::html::token::{HtmlTokenizer, HtmlToken};
+use crate::renderer::html::HtmlError;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::vec::Vec;
@@ -33,6 +34,26 @@ impl HtmlParser {
         }
     }
 
+    // 入力を追加して、そこまでで作れるDOMツリーを返す
+    // トークナイザーはHtmlTokenizer::streaming()で作っておく
+    pub fn feed(&mut self, chunk: &str) -> Rc<RefCell<Window>> {
+        self.t.feed(chunk);
+        self.construct_tree()
+    }
+
+    // 入力の終わりを伝えて、完成したDOMツリーを返す
+    pub fn finish(&mut self) -> Rc<RefCell<Window>> {
+        self.t.finish();
+        self.construct_tree()
+    }
+
+    // トークナイザーが見つけた解析エラー
+    pub fn errors(&self) -> Vec<HtmlError> {
+        self.t.errors()
+    }
+
+    // トークナイザーから取り出せるトークンをすべて処理してDOMツリーを返す
+    // 入力が足りない場合は途中の状態を保っておき、feedされた後に続きから処理する
     pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
         let mut token = self.t.next();
         while token.is_some() {
@@ -126,8 +147,8 @@ impl HtmlParser {
                             self_closing: _,
                             ref attributes,
                         }) => {
-                            if tag == "style" || tag == "script" {
-                                // タグの名前がstyle、scriptだったとき新しいノードを追加して、Text状態に遷移する
+                            if tag == "style" || tag == "script" || tag == "title" {
+                                // タグの名前がstyle、script、titleだったとき新しいノードを追加して、Text状態に遷移する
                                 self.insert_element(tag, attributes.to_vec());
                                 self.original_insertion_mode = self.mode;
                                 self.mode = InsertionMode::Text;
@@ -135,6 +156,14 @@ impl HtmlParser {
                                 continue;
                             }
                             
+                            if tag == "base" {
+                                // baseは子要素を持たない要素なので、追加したらすぐにスタックから取り出す
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Base);
+                                token = self.t.next();
+                                continue;
+                            }
+
                             if tag == "body" {
                                 // このブラウザがすべての仕様を実装していないので、headが省略されているHTMLを扱うのに必要
                                 // これがないとheadが省略されているHTMLで無限ループが発生
@@ -165,7 +194,7 @@ impl HtmlParser {
                             return self.window.clone();
                         }
                     }
-                    // meta、titleなどのサポートしていないタグは無視する
+                    // metaなどのサポートしていないタグは無視する
                     token = self.t.next();
                     continue;
 
@@ -230,6 +259,13 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            "img" => {
+                                // imgは子要素を持たない要素なので、追加したらすぐにスタックから取り出す
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Img);
+                                token = self.t.next();
+                                continue;
+                            }
                             _ => {
                                 token = self.t.next();
                             }
@@ -304,7 +340,7 @@ impl HtmlParser {
                         Some(HtmlToken::EndTag { 
                             ref tag
                         }) => {
-                            // style終了タグ、script終了タグが出てきたら下の状態に戻る
+                            // style、script、titleの終了タグが出てきたら元の状態に戻る
                             if tag == "style" {
                                 self.pop_until(ElementKind::Style);
                                 self.mode = self.original_insertion_mode;
@@ -317,6 +353,12 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            if tag == "title" {
+                                self.pop_until(ElementKind::Title);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
                         }
                         Some(HtmlToken::Char(c)) => {
                             // 終了タグが出てくるまで文字をテキストノードとしてDOMツリーに追加します
@@ -559,6 +601,8 @@ pub enum InsertionMode {
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::utils::convert_dom_to_string;
     use alloc::vec;
 
     #[test]
@@ -592,6 +636,19 @@ mod tests {
         assert_eq!(Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new("body", Vec::new()))))), body);
     }
 
+    #[test]
+    fn test_title() {
+        let html = "<html><head><title>a <b></title></head><body><p>text</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        // titleの中身はタグも含めてテキストとして扱い、bodyの要素には影響しない
+        let title = get_target_element_node(Some(document.clone()), ElementKind::Title).expect("failed to get a title element");
+        let text = title.borrow().first_child().expect("failed to get a first child of title");
+        assert_eq!(NodeKind::Text("a <b>".to_string()), text.borrow().kind());
+        assert!(get_target_element_node(Some(document), ElementKind::P).is_some());
+    }
+
     #[test]
     fn test_text() {
         let html = "<html><head></head><body>text</body></html>".to_string();
@@ -644,4 +701,23 @@ mod tests {
         let text = a.borrow().first_child().expect("failed to get a first child of a");
         assert_eq!(Rc::new(RefCell::new(Node::new(NodeKind::Text("text".to_string())))), text);
     }
+
+    #[test]
+    fn test_streaming() {
+        let html = "<html><head><style>p{}</style></head><body><h1>title</h1><p>te<a href=\"x\">xt</a></p></body></html>";
+        let expected = convert_dom_to_string(&Some(HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree().borrow().document()));
+
+        let mut parser = HtmlParser::new(HtmlTokenizer::streaming());
+        // 途中まででもDOMツリーを作れる
+        let split = html.find("<h1>").expect("failed to find h1") + 2;
+        let window = parser.feed(&html[..split]);
+        let body = get_target_element_node(Some(window.borrow().document()), ElementKind::Body);
+        assert!(body.is_some());
+
+        for chunk in html.as_bytes()[split..].chunks(3) {
+            parser.feed(core::str::from_utf8(chunk).expect("html should be ascii"));
+        }
+        let window = parser.finish();
+        assert_eq!(expected, convert_dom_to_string(&Some(window.borrow().document())));
+    }
 }
@@ -1,4 +1,5 @@
 use crate::renderer::html::attribute::Attribute;
+use crate::renderer::html::HtmlError;
 use alloc::{string::String, vec::Vec};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -9,9 +10,14 @@ pub struct HtmlTokenizer {
     latest_token: Option<HtmlToken>,
     input: Vec<char>,
     buf: String,
+    finished: bool, // これ以上入力が追加されない
+    line: usize,    // 最後に読んだ文字の行
+    column: usize,  // 最後に読んだ文字の列
+    errors: Vec<HtmlError>,
 }
 
 impl HtmlTokenizer {
+    // すべての入力がそろっている場合に使う
     pub fn new(html: String) -> Self {
         Self {
             state: State::Data,
@@ -20,11 +26,84 @@ impl HtmlTokenizer {
             latest_token: None,
             input: html.chars().collect(),
             buf: String::new(),
+            finished: true,
+            line: 1,
+            column: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    // 入力をfeedで少しずつ追加する場合に使う。最後にfinishを呼び出す
+    pub fn streaming() -> Self {
+        let mut tokenizer = Self::new(String::new());
+        tokenizer.finished = false;
+        tokenizer
+    }
+
+    // 入力を追加する。途中まで読んだトークンは、続きの入力を受け取ってから返す
+    pub fn feed(&mut self, chunk: &str) {
+        // 再消費のために直前の1文字だけを残して、処理済みの入力を捨てる
+        if self.pos > 1 {
+            self.input.drain(..self.pos - 1);
+            self.pos = 1;
+        }
+        self.input.extend(chunk.chars());
+    }
+
+    // 入力の終わりを伝える
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    // これまでに見つかった解析エラー
+    pub fn errors(&self) -> Vec<HtmlError> {
+        self.errors.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#tokenization
+    // 入力の最後で、途中まで読んだ < を文字として返す
+    fn flush_at_eof(&mut self) -> Option<HtmlToken> {
+        match self.state {
+            State::TagOpen => {
+                self.state = State::Data;
+                Some(HtmlToken::Char('<'))
+            }
+            State::ScriptDataLessThanSign => {
+                self.state = State::ScriptData;
+                Some(HtmlToken::Char('<'))
+            }
+            // 閉じられていないタグは捨てる
+            State::TagName
+            | State::BeforeAttributeName
+            | State::AttributeName
+            | State::AfterAttributeName
+            | State::BeforeAttributeValue
+            | State::AttributeValueDoubleQuoted
+            | State::AttributeValueSingleQuoted
+            | State::AttributeValueUnquoted
+            | State::AfterAttributeValueQuoted
+            | State::SelfClosingStartTag => {
+                self.errors.push(HtmlError::EofInTag {
+                    line: self.line,
+                    column: self.column,
+                });
+                self.latest_token = None;
+                self.state = State::Data;
+                None
+            }
+            _ => None,
         }
     }
 
     // inputの文字列から現在の位置（pos）の文字を返し、posを1つ進める
     fn consume_next_input(&mut self) -> char {
+        // 直前の文字が改行なら次の行に進む
+        if self.pos > 0 && self.input[self.pos - 1] == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         let c = self.input[self.pos];
         self.pos += 1;
         c
@@ -139,12 +218,16 @@ impl Iterator for HtmlTokenizer {
     type Item = HtmlToken;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // 現在の位置が入力文字よりの長さより長い場合はNoneを返す
-        if self.pos >= self.input.len() {
-            return None;
-        }
-
         loop {
+            // 入力を使い切った場合はNoneを返す
+            // 入力が追加される可能性がある場合は、状態を保ったまま続きがfeedされるのを待つ
+            if !self.reconsume && self.pos >= self.input.len() {
+                if self.finished {
+                    return self.flush_at_eof();
+                }
+                return None;
+            }
+
             let c = match self.reconsume {
                 true => self.reconsume_input(),
                 false => self.consume_next_input(),
@@ -193,6 +276,10 @@ impl Iterator for HtmlTokenizer {
                     }
 
                     // 上記以外の場合は現在の文字をサイト取り扱う
+                    self.errors.push(HtmlError::InvalidFirstCharacterOfTagName {
+                        line: self.line,
+                        column: self.column,
+                    });
                     self.reconsume = true;
                     self.state = State::Data;
                 }
@@ -210,6 +297,20 @@ impl Iterator for HtmlTokenizer {
                         self.create_tag(false);
                         continue;
                     }
+
+                    // </> は何も出力せずに読み飛ばす
+                    if c == '>' {
+                        self.errors.push(HtmlError::MissingEndTagName {
+                            line: self.line,
+                            column: self.column,
+                        });
+                    } else {
+                        self.errors.push(HtmlError::InvalidFirstCharacterOfTagName {
+                            line: self.line,
+                            column: self.column,
+                        });
+                    }
+                    self.state = State::Data;
                 }
 
                 // TagOpenのときに文字がアルファベットならTagNameに遷移する
@@ -432,6 +533,14 @@ impl Iterator for HtmlTokenizer {
                         // エラー処理
                         return Some(HtmlToken::Eof);
                     }
+
+                    // <br / class="a"> のように / の後に > 以外が続く場合は属性の続きとして扱う
+                    self.errors.push(HtmlError::UnexpectedSolidusInTag {
+                        line: self.line,
+                        column: self.column,
+                    });
+                    self.reconsume = true;
+                    self.state = State::BeforeAttributeName;
                 }
 
                 // <script>タグの中のJavaScriptを処理する状態
@@ -645,6 +754,66 @@ mod tests {
         assert_eq!(tokenizer.next(), Some(expected));
     }
 
+    #[test]
+    fn test_streaming() {
+        let mut tokenizer = HtmlTokenizer::streaming();
+        tokenizer.feed("<p cla");
+        assert!(tokenizer.next().is_none());
+        tokenizer.feed("ss=\"A\">x</");
+        let mut attr = Attribute::new();
+        for c in "class".chars() {
+            attr.add_char(c, true);
+        }
+        attr.add_char('A', false);
+        assert_eq!(
+            tokenizer.next(),
+            Some(HtmlToken::StartTag {
+                tag: "p".to_string(),
+                self_closing: false,
+                attributes: vec![attr],
+            })
+        );
+        assert_eq!(tokenizer.next(), Some(HtmlToken::Char('x')));
+        assert!(tokenizer.next().is_none());
+
+        tokenizer.feed("p> <");
+        assert_eq!(
+            tokenizer.next(),
+            Some(HtmlToken::EndTag {
+                tag: "p".to_string(),
+            })
+        );
+        assert_eq!(tokenizer.next(), Some(HtmlToken::Char(' ')));
+        assert!(tokenizer.next().is_none());
+
+        // 入力の終わりでは、途中の < を文字として返す
+        tokenizer.finish();
+        assert_eq!(tokenizer.next(), Some(HtmlToken::Char('<')));
+        assert!(tokenizer.next().is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let html = "<p>a < b</>\n<br / x>\n<p class=\"a".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        while tokenizer.next().is_some() {}
+        assert_eq!(
+            vec![
+                HtmlError::InvalidFirstCharacterOfTagName { line: 1, column: 7 },
+                HtmlError::MissingEndTagName {
+                    line: 1,
+                    column: 11
+                },
+                HtmlError::UnexpectedSolidusInTag { line: 2, column: 6 },
+                HtmlError::EofInTag {
+                    line: 3,
+                    column: 11
+                },
+            ],
+            tokenizer.errors()
+        );
+    }
+
     #[test]
     fn test_script_tag() {
         let html = "<script>js code;</script>".to_string();
@@ -1,7 +1,7 @@
-use core::cell::RefCell;
+use core::{cell::RefCell, str::FromStr};
 
-use alloc::{format, rc::Rc, string::{String, ToString}};
-use crate::{error::Error, renderer::dom::node::{ElementKind, Node, NodeKind}};
+use alloc::{rc::Rc, string::{String, ToString}};
+use crate::renderer::{dom::node::{ElementKind, Node, NodeKind}, layout::LayoutError};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ComputedStyle {
@@ -14,6 +14,12 @@ pub struct ComputedStyle {
     width: Option<f64>,
 }
 
+impl Default for ComputedStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ComputedStyle {
     pub fn new() -> Self {
         Self {
@@ -127,7 +133,7 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn from_name(name: &str) -> Result<Self, Error> {
+    pub fn from_name(name: &str) -> Result<Self, LayoutError> {
         let code = match name {
             "black" => "#000000".to_string(),
             "silver" => "#c0c0c0".to_string(),
@@ -148,7 +154,7 @@ impl Color {
             "orange" => "#ffa500".to_string(),
             "lightgray" => "#d3d3d3".to_string(),
             _ => {
-                return Err(Error::UnexpectedInput(format!("color name {:?} is not supported yet", name)));
+                return Err(LayoutError::UnsupportedColorName(name.to_string()));
             }
         };
         Ok(Self {
@@ -157,10 +163,10 @@ impl Color {
         })
     }
 
-    pub fn from_code(code: &str) -> Result<Self, Error> {
+    pub fn from_code(code: &str) -> Result<Self, LayoutError> {
         if code.chars().nth(0) != Some('#') || code.len() != 7 {
             // #fffなどの省略された形式のカラーコードは使用できない
-            return Err(Error::UnexpectedInput(format!("invalid color code {}", code)));
+            return Err(LayoutError::InvalidColorCode(code.to_string()));
         }
 
         let name = match code {
@@ -183,7 +189,7 @@ impl Color {
             "#ffa500" => "orange".to_string(),
             "#d3d3d3" => "lightgray".to_string(),
             _ => {
-                return Err(Error::UnexpectedInput(format!("color code {:?} is not supported yet", code)));
+                return Err(LayoutError::UnsupportedColorCode(code.to_string()));
             }
         };
         Ok(Self {
@@ -201,7 +207,7 @@ impl Color {
     }
 
     pub fn code_u32(&self) ->u32 {
-        u32::from_str_radix(&self.code.trim_start_matches('#'), 16).unwrap()
+        u32::from_str_radix(self.code.trim_start_matches('#'), 16).unwrap()
     }
 }
 
@@ -246,13 +252,17 @@ impl DisplayType {
             NodeKind::Text(_) => DisplayType::Inline,
         }
     }
+}
+
+impl FromStr for DisplayType {
+    type Err = LayoutError;
 
-    pub fn from_str(s: &str) -> Result<Self, Error> {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "block" => Ok(Self::Block),
             "inline" => Ok(Self::Inline),
             "none" => Ok(Self::DisplayNone),
-            _ => Err(Error::UnexpectedInput(format!("display {:?} is not supported yet", s))),
+            _ => Err(LayoutError::UnsupportedDisplay(s.to_string())),
         }
     }
 }
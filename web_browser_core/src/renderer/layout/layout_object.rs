@@ -1,6 +1,6 @@
-use core::{alloc::Layout, cell::{Ref, RefCell}, ops::RangeInclusive};
+use core::{cell::RefCell, str::FromStr};
 
-use alloc::{rc::{Rc, Weak}, vec::Vec};
+use alloc::{rc::{Rc, Weak}, string::ToString, vec::Vec};
 
 use crate::{constants::{CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH, CONTENT_AREA_WIDTH}, renderer::{css::cssom::{ComponentValue, Declaration, Selector, StyleSheet}, dom::node::{Node, NodeKind}, layout::computed_style::{Color, ComputedStyle, DisplayType, FontSize}}};
 
@@ -113,7 +113,7 @@ impl LayoutObject {
             match declaration.property.as_str() {
                 "background-color" => {
                     if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
+                        let color = match Color::from_name(value) {
                             Ok(color) => color,
                             Err(_) => Color::white(),
                         };
@@ -122,7 +122,7 @@ impl LayoutObject {
                     }
 
                     if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(&color_code) {
+                        let color = match Color::from_code(color_code) {
                             Ok(color) => color,
                             Err(_) => Color::white(),
                         };
@@ -132,7 +132,7 @@ impl LayoutObject {
                 }
                 "color" => {
                     if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
+                        let color = match Color::from_name(value) {
                             Ok(color) => color,
                             Err(_) => Color::black(),
                         };
@@ -140,7 +140,7 @@ impl LayoutObject {
                     }
 
                     if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(&color_code) {
+                        let color = match Color::from_code(color_code) {
                             Ok(color) => color,
                             Err(_) => Color::black(),
                         };
@@ -265,6 +265,44 @@ impl LayoutObject {
         }
         self.size = size;
     }
+
+    pub fn compute_position(
+        &mut self,
+        parent_point: LayoutPoint,
+        previous_sibling_kind: LayoutObjectKind,
+        previous_sibling_point: Option<LayoutPoint>,
+        previous_sibling_size: Option<LayoutSize>,
+    ) {
+        let mut point = LayoutPoint::new(0, 0);
+
+        match (self.kind(), previous_sibling_kind) {
+            // ブロック要素が兄弟ノードの場合、Y軸方向に進む
+            (LayoutObjectKind::Block, _) | (_, LayoutObjectKind::Block) => {
+                if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
+                    point.set_y(pos.y() + size.height());
+                } else {
+                    point.set_y(parent_point.y());
+                }
+                point.set_x(parent_point.x());
+            }
+            // インライン要素が並ぶ場合、X軸方向に進む
+            (LayoutObjectKind::Inline, LayoutObjectKind::Inline) => {
+                if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
+                    point.set_x(pos.x() + size.width());
+                    point.set_y(pos.y());
+                } else {
+                    point.set_x(parent_point.x());
+                    point.set_y(parent_point.y());
+                }
+            }
+            _ => {
+                point.set_x(parent_point.x());
+                point.set_y(parent_point.y());
+            }
+        }
+
+        self.point = point;
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -293,11 +331,11 @@ impl LayoutPoint {
         self.y
     }
 
-    pub fn set_x(&self, x: i64) {
+    pub fn set_x(&mut self, x: i64) {
         self.x = x;
     }
 
-    pub fn set_y(&self, y: i64) {
+    pub fn set_y(&mut self, y: i64) {
         self.y = y;
     }
 }
@@ -347,11 +385,7 @@ pub fn create_layout_object(
         }
 
         // CSSでスタイルが指定されていない場合デフォルトの値または親のノードから継承した阿智を使用する
-        let parent_style = if let Some(parent) = parent_obj {
-            Some(parent.borrow().style())
-        } else {
-            None
-        };
+        let parent_style = parent_obj.as_ref().map(|parent| parent.borrow().style());
         // 指定値の決定（デフォルティング）
         layout_object.borrow_mut().defaulting_style(n, parent_style);
 
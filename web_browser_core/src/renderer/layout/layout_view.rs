@@ -2,7 +2,8 @@ use core::cell::RefCell;
 
 use alloc::rc::Rc;
 
-use crate::renderer::{css::{self, cssom::StyleSheet}, dom::node::{ElementKind, Node}, layout::layout_object::{LayoutObject, LayoutObjectKind, LayoutPoint, LayoutSize}};
+use crate::constants::CONTENT_AREA_WIDTH;
+use crate::renderer::{css::cssom::StyleSheet, dom::{api::get_target_element_node, node::{ElementKind, Node}}, layout::layout_object::{create_layout_object, LayoutObject, LayoutObjectKind, LayoutPoint, LayoutSize}};
 
 #[derive(Debug, Clone)]
 pub struct LayoutView {
@@ -60,6 +61,43 @@ impl LayoutView {
             n.borrow_mut().compute_size(parent_size);
         }
     }
+
+    fn calculate_node_position(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        parent_point: LayoutPoint,
+        previous_sibling_kind: LayoutObjectKind,
+        previous_sibling_point: Option<LayoutPoint>,
+        previous_sibling_size: Option<LayoutSize>,
+    ) {
+        if let Some(n) = node {
+            n.borrow_mut().compute_position(
+                parent_point,
+                previous_sibling_kind,
+                previous_sibling_point,
+                previous_sibling_size,
+            );
+
+            // 子ノードの位置は、このノードの位置を起点に計算する
+            let first_child = n.borrow().first_child();
+            Self::calculate_node_position(
+                &first_child,
+                n.borrow().point(),
+                LayoutObjectKind::Block,
+                None,
+                None,
+            );
+
+            // 兄弟ノードの位置は、このノードの位置とサイズから計算する
+            let next_sibling = n.borrow().next_sibling();
+            Self::calculate_node_position(
+                &next_sibling,
+                parent_point,
+                n.borrow().kind(),
+                Some(n.borrow().point()),
+                Some(n.borrow().size()),
+            );
+        }
+    }
 }
 
 fn build_layout_tree(
@@ -1,3 +1,37 @@
 pub mod computed_style;
 pub mod layout_object;
 pub mod layout_view;
+
+use crate::error::Error;
+use alloc::string::String;
+use core::fmt::Display;
+
+// スタイルの値をレイアウトで使える値に変換できなかった理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    UnsupportedColorName(String),
+    InvalidColorCode(String),     // #rrggbbの形式でない
+    UnsupportedColorCode(String), // 名前のある色以外のカラーコード
+    UnsupportedDisplay(String),
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LayoutError::UnsupportedColorName(name) => {
+                write!(f, "color name {:?} is not supported yet", name)
+            }
+            LayoutError::InvalidColorCode(code) => write!(f, "invalid color code {}", code),
+            LayoutError::UnsupportedColorCode(code) => {
+                write!(f, "color code {:?} is not supported yet", code)
+            }
+            LayoutError::UnsupportedDisplay(s) => write!(f, "display {:?} is not supported yet", s),
+        }
+    }
+}
+
+impl From<LayoutError> for Error {
+    fn from(e: LayoutError) -> Self {
+        Error::Layout(e)
+    }
+}
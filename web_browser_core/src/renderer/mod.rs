@@ -1,5 +1,7 @@
 pub mod dom;
+pub mod history;
 pub mod html;
 pub mod page;
 pub mod css;
 pub mod layout;
+pub mod viewer;
@@ -1,35 +1,469 @@
 use crate::browser::Browser;
+use crate::download::Download;
+use crate::encoding::decoder::Decoder;
+use crate::encoding::{determine_encoding, Encoding};
+use crate::error::Error;
+use crate::http::request::{HttpRequest, Method};
 use crate::http::HttpResponse;
-use crate::renderer::dom::node::Window;
+use crate::loader::Loader;
+use crate::mime::sniff::{compute_mime_type, RESOURCE_HEADER_SIZE};
+use crate::mime::{ContentHandler, MimeType};
+use crate::renderer::dom::api::{get_element_nodes, get_target_element_node};
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::{NodeKind, Window};
+use crate::renderer::history::{CachedDocument, HistoryEntry, ScrollPosition, SessionHistory};
 use crate::renderer::html::parser::HtmlParser;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::viewer::{image_document, text_document};
+use crate::url::{Url, UrlError};
 use crate::utils::convert_dom_to_string;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::fmt::Display;
 
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
     frame: Option<Rc<RefCell<Window>>>,
+    url: Option<Url>,                // 表示しているドキュメントのURL
+    base_url: Option<Url>, // 相対URLを解決するための基準URL。<base href>があればその値になる
+    content_type: Option<MimeType>, // 表示しているドキュメントのMIMEタイプ
+    character_set: Option<Encoding>, // 表示しているドキュメントの文字エンコーディング。画像の場合はNone
+    load_state: LoadState,
+    scroll_position: ScrollPosition,
+    history: SessionHistory, // このページで表示したドキュメントの履歴
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Page {
     pub fn new() -> Self {
-        Self { browser: Weak::new(), frame: None }
+        Self {
+            browser: Weak::new(),
+            frame: None,
+            url: None,
+            base_url: None,
+            content_type: None,
+            character_set: None,
+            load_state: LoadState::Initial,
+            scroll_position: ScrollPosition::default(),
+            history: SessionHistory::new(),
+        }
     }
 
     pub fn set_browser(&mut self, browser: Weak<RefCell<Browser>>) {
         self.browser = browser;
     }
 
+    // ドキュメントのURLを設定する。基準URLもドキュメントのURLにリセットされる
+    pub fn set_url(&mut self, url: Url) {
+        self.base_url = Some(url.clone());
+        self.url = Some(url);
+    }
+
+    pub fn url(&self) -> Option<Url> {
+        self.url.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    pub fn base_url(&self) -> Option<Url> {
+        self.base_url.clone()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-document-contenttype
+    pub fn content_type(&self) -> Option<MimeType> {
+        self.content_type.clone()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-document-characterset
+    pub fn character_set(&self) -> Option<Encoding> {
+        self.character_set
+    }
+
+    pub fn load_state(&self) -> LoadState {
+        self.load_state.clone()
+    }
+
+    // 戻る・進むで表示し直したときに、このスクロール位置に戻す
+    pub fn set_scroll_position(&mut self, scroll_position: ScrollPosition) {
+        self.scroll_position = scroll_position;
+    }
+
+    pub fn scroll_position(&self) -> ScrollPosition {
+        self.scroll_position
+    }
+
+    pub fn history(&self) -> SessionHistory {
+        self.history.clone()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.history.can_go_back()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.history.can_go_forward()
+    }
+
+    // https://html.spec.whatwg.org/multipage/dom.html#document.title
+    // <title>のテキストの前後の空白を取り除き、連続する空白を1つにまとめる。<title>がない場合は空文字列を返す
+    pub fn title(&self) -> String {
+        let document = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return String::new(),
+        };
+        let title = match get_target_element_node(Some(document), ElementKind::Title) {
+            Some(title) => title,
+            None => return String::new(),
+        };
+
+        let mut text = String::new();
+        let mut child = title.borrow().first_child();
+        while let Some(node) = child {
+            if let NodeKind::Text(s) = node.borrow().kind() {
+                text.push_str(&s);
+            }
+            child = node.borrow().next_sibling();
+        }
+        text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    // https://html.spec.whatwg.org/multipage/urls-and-fetching.html#encoding-parsing-a-url
+    // リンクやサブリソースのURLをドキュメントの基準URLをもとに絶対URLに変換する
+    pub fn resolve_url(&self, href: &str) -> Result<Url, UrlError> {
+        match &self.base_url {
+            Some(base) => base.join(href),
+            None => Url::parse(href),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/links.html#following-hyperlinks
+    // <a href>のリンク先を、ドキュメントの順に基準URLをもとに解決して返す。解決できないものは含めない
+    pub fn links(&self) -> Vec<Url> {
+        self.resolve_attribute_urls(&[(ElementKind::A, "href")])
+    }
+
+    // <img src>や<script src>のサブリソースのURLを、ドキュメントの順に基準URLをもとに解決して返す
+    pub fn subresources(&self) -> Vec<Url> {
+        self.resolve_attribute_urls(&[(ElementKind::Img, "src"), (ElementKind::Script, "src")])
+    }
+
+    // URLのドキュメントを読み込んで表示し、DOMツリーをデバック用に文字列として返す
+    // http、httpsはブラウザのトランスポートで取得し、data:、file:、about:はネットワークを使わずに読み込む
+    // 相対URLの場合は今のドキュメントの基準URLをもとに解決する
+    // ダウンロードした場合は、表示しているドキュメントと読み込みの状態をそのままにする
+    // 表示したドキュメントは履歴に追加し、今の項目より後ろの項目は取り除く
+    // # 以降だけが違うURLの場合は、読み込み直さずに履歴だけを追加する
+    pub fn load(&mut self, url: &str) -> Result<String, Error> {
+        let url = match self.resolve_url(url) {
+            Ok(url) => url,
+            Err(e) => {
+                let e = Error::from(e);
+                self.load_state = LoadState::Failed(e.clone());
+                return Err(e);
+            }
+        };
+
+        // 今と同じURLの場合は、履歴に追加せずに今の項目を置き換える
+        let replace = self
+            .url
+            .as_ref()
+            .is_some_and(|current| current.serialize(false) == url.serialize(false));
+        self.save_current_entry();
+        if self.is_fragment_navigation(&url) {
+            self.set_url(url);
+            self.update_base_url();
+            self.add_entry(replace);
+            return Ok(self.dom_string());
+        }
+        if self.fetch(navigation_request(url))? {
+            self.scroll_position = ScrollPosition::default();
+            self.add_entry(replace);
+        }
+        Ok(self.dom_string())
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-history-back
+    pub fn back(&mut self) -> Result<String, Error> {
+        self.go(-1)
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-history-forward
+    pub fn forward(&mut self) -> Result<String, Error> {
+        self.go(1)
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-history-go
+    // 履歴の中でdelta個離れた項目を表示する。範囲外の場合は何もせず、0の場合は読み込み直す
+    // DOMツリーを残している項目はそれを表示し、残していない項目はURLを読み込み直す
+    pub fn go(&mut self, delta: isize) -> Result<String, Error> {
+        if delta == 0 {
+            return self.reload();
+        }
+        let (index, entry) = match self
+            .history
+            .index_by_delta(delta)
+            .and_then(|index| Some((index, self.history.get(index)?)))
+        {
+            Some(target) => target,
+            None => return Ok(self.dom_string()),
+        };
+
+        self.save_current_entry();
+        match entry.document() {
+            Some(document) => {
+                self.frame = Some(document.frame());
+                self.content_type = document.content_type();
+                self.character_set = document.character_set();
+                self.load_state = LoadState::Complete;
+                self.set_url(entry.url());
+                self.update_base_url();
+            }
+            // ダウンロードになった場合は移動しない
+            None => {
+                if !self.fetch(navigation_request(entry.url()))? {
+                    return Ok(self.dom_string());
+                }
+            }
+        }
+        self.scroll_position = entry.scroll_position();
+        self.history.set_current_index(index);
+        self.save_current_entry();
+        Ok(self.dom_string())
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-reload
+    // 今のURLを読み込み直して今の履歴の項目を置き換える。スクロール位置はそのままにする
+    // https://www.rfc-editor.org/rfc/rfc9111#section-5.2.1.1
+    // キャッシュしているレスポンスが新鮮でも、max-age=0を付けてサーバーに検証させる
+    pub fn reload(&mut self) -> Result<String, Error> {
+        let url = match &self.url {
+            Some(url) => url.clone(),
+            None => return Ok(self.dom_string()),
+        };
+        let mut request = navigation_request(url);
+        request.headers_mut().insert("Cache-Control", "max-age=0");
+        if self.fetch(request)? {
+            self.save_current_entry();
+        }
+        Ok(self.dom_string())
+    }
+
+    // 別のページの履歴の項目と今の位置を引き継ぎ、今の項目を表示する
+    // DOMツリーは共有しないので、今の項目のURLを読み込み直す
+    pub fn restore_history(&mut self, history: SessionHistory) -> Result<String, Error> {
+        let entry = match history.current() {
+            Some(entry) => entry,
+            None => return Ok(self.dom_string()),
+        };
+        self.history = history.without_documents();
+        if self.fetch(navigation_request(entry.url()))? {
+            self.scroll_position = entry.scroll_position();
+            self.save_current_entry();
+        }
+        Ok(self.dom_string())
+    }
+
+    // URLを読み込み、ドキュメントを表示した場合はtrue、ダウンロードした場合はfalseを返す
+    // 失敗した場合は、受信中に表示していた途中までのドキュメントを前のドキュメントに戻す
+    fn fetch(&mut self, request: HttpRequest) -> Result<bool, Error> {
+        let previous = core::mem::replace(&mut self.load_state, LoadState::Loading);
+        let previous_frame = self.frame.clone();
+        match self.load_document(request) {
+            Ok(()) if self.load_state == LoadState::Loading => {
+                self.load_state = previous;
+                Ok(false)
+            }
+            Ok(()) => Ok(true),
+            Err(e) => {
+                self.frame = previous_frame;
+                self.load_state = LoadState::Failed(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn load_document(&mut self, request: HttpRequest) -> Result<(), Error> {
+        let loader = match self.browser.upgrade() {
+            Some(browser) => browser.borrow().loader(),
+            None => Loader::new(),
+        };
+
+        let url = request.url();
+
+        // MIMEタイプと文字エンコーディングを判定できるだけのボディを受け取るまではためておく
+        // HTMLの場合は、それ以降はボディを受け取るたびにデコードしてトークナイズし、途中までのDOMツリーをフレームに設定する
+        let mut sniffed: Option<MimeType> = None;
+        let mut resource_header = Vec::new();
+        let mut parser: Option<(HtmlParser, Decoder)> = None;
+        let response = loader.load_streaming(request, &mut |response, chunk| {
+            let chunk = match sniffed {
+                Some(_) => chunk.to_vec(),
+                None => {
+                    resource_header.extend_from_slice(chunk);
+                    if resource_header.len() < RESOURCE_HEADER_SIZE {
+                        return;
+                    }
+                    let mime_type = compute_mime_type(&response.headers(), &resource_header);
+                    if content_handler(response, &mime_type) == ContentHandler::Html {
+                        let encoding = determine_encoding(&mime_type, &resource_header);
+                        parser = Some((
+                            HtmlParser::new(HtmlTokenizer::streaming()),
+                            encoding.new_decoder(),
+                        ));
+                    }
+                    sniffed = Some(mime_type);
+                    core::mem::take(&mut resource_header)
+                }
+            };
+            if let Some((parser, decoder)) = &mut parser {
+                let html = decoder.decode(&chunk, false);
+                self.frame = Some(parser.feed(&html));
+            }
+        })?;
+
+        // ボディが短い場合は、すべて受け取ってから判定する
+        let mime_type = match sniffed {
+            Some(mime_type) => mime_type,
+            None => compute_mime_type(&response.headers(), &resource_header),
+        };
+        // リダイレクトした場合はリダイレクト先のURLがドキュメントのURLになる
+        let url = response.url().unwrap_or(url);
+        match content_handler(&response, &mime_type) {
+            ContentHandler::Html => {
+                let (mut parser, mut decoder) = parser.unwrap_or_else(|| {
+                    let encoding = determine_encoding(&mime_type, &resource_header);
+                    (
+                        HtmlParser::new(HtmlTokenizer::streaming()),
+                        encoding.new_decoder(),
+                    )
+                });
+                parser.feed(&decoder.decode(&resource_header, true));
+                self.frame = Some(parser.finish());
+                self.character_set = Some(decoder.encoding());
+            }
+            ContentHandler::Text => self.create_text_frame(&response.body(), &mime_type),
+            ContentHandler::Image => {
+                self.frame = Some(image_document(&url.to_string()));
+                self.character_set = None;
+            }
+            ContentHandler::Download => {
+                self.download(&response, mime_type);
+                return Ok(());
+            }
+        }
+
+        self.content_type = Some(mime_type);
+        self.load_state = LoadState::Complete;
+        self.set_url(url);
+        self.update_base_url();
+        Ok(())
+    }
+
     // HttpResponseを受取り、DOMツリーをデバック用に文字列として返す関数
+    // Content-Typeとボディの先頭からMIMEタイプを判定し、HTML以外はそれぞれの形式に合わせて表示する
+    // HTMLとテキストは、判定した文字エンコーディングでデコードする
+    // レスポンスのURLがわかる場合は、そのURLのドキュメントとして履歴に追加する
     pub fn receive_response(&mut self, response: HttpResponse) -> String {
-        self.create_frame(response.body());
+        self.save_current_entry();
+        let body = response.body();
+        let mime_type = compute_mime_type(&response.headers(), &body);
+        match content_handler(&response, &mime_type) {
+            ContentHandler::Html => {
+                let encoding = determine_encoding(&mime_type, &body);
+                self.create_frame(encoding.decode(&body));
+                self.character_set = Some(encoding);
+            }
+            ContentHandler::Text => self.create_text_frame(&body, &mime_type),
+            ContentHandler::Image => {
+                let src = response.url().or(self.url.clone());
+                self.frame = Some(image_document(
+                    &src.map(|url| url.to_string()).unwrap_or_default(),
+                ));
+                self.character_set = None;
+            }
+            ContentHandler::Download => {
+                self.download(&response, mime_type);
+                return self.dom_string();
+            }
+        }
+        self.content_type = Some(mime_type);
+        self.load_state = LoadState::Complete;
+        self.scroll_position = ScrollPosition::default();
+        match response.url() {
+            Some(url) => {
+                self.set_url(url);
+                self.update_base_url();
+                self.add_entry(false);
+            }
+            None => self.update_base_url(),
+        }
+        self.dom_string()
+    }
+
+    // https://html.spec.whatwg.org/multipage/browsing-the-web.html#navigate-fragid
+    // # 以降だけが違い、移動先のURLに # がある場合は、同じドキュメントの中での移動になる
+    fn is_fragment_navigation(&self, url: &Url) -> bool {
+        match &self.url {
+            Some(current) => {
+                self.frame.is_some()
+                    && url.fragment().is_some()
+                    && current.serialize(true) == url.serialize(true)
+            }
+            None => false,
+        }
+    }
+
+    // 表示しているドキュメントから履歴の項目を作る
+    fn history_entry(&self, url: Url) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(url);
+        entry.set_title(self.title());
+        entry.set_scroll_position(self.scroll_position);
+        if let Some(frame) = &self.frame {
+            entry.set_document(Some(CachedDocument::new(
+                frame.clone(),
+                self.content_type.clone(),
+                self.character_set,
+            )));
+        }
+        entry
+    }
+
+    // 別の項目に移る前に、今の項目のタイトルやスクロール位置を表示している状態に合わせる
+    fn save_current_entry(&mut self) {
+        if let (Some(url), Some(_)) = (self.url.clone(), self.history.current()) {
+            self.history.replace_current(self.history_entry(url));
+        }
+    }
+
+    // 表示しているドキュメントを履歴に追加する。replaceがtrueの場合は今の項目を置き換える
+    fn add_entry(&mut self, replace: bool) {
+        if replace {
+            self.save_current_entry();
+        } else if let Some(url) = self.url.clone() {
+            self.history.push(self.history_entry(url));
+        }
+    }
+
+    // ブラウザのダウンロードの一覧に加える
+    fn download(&self, response: &HttpResponse, mime_type: MimeType) {
+        if let Some(browser) = self.browser.upgrade() {
+            browser
+                .borrow_mut()
+                .add_download(Download::new(response, mime_type));
+        }
+    }
 
-        // デバック用にDOMツリーを文字列として返す
+    // デバック用にDOMツリーを文字列として返す
+    fn dom_string(&self) -> String {
         if let Some(frame) = &self.frame {
             let dom = frame.borrow().document().clone();
             let debug = convert_dom_to_string(&Some(dom));
@@ -44,4 +478,635 @@ impl Page {
         let frame = HtmlParser::new(html_tokenizer).construct_tree();
         self.frame = Some(frame);
     }
+
+    // https://html.spec.whatwg.org/multipage/document-lifecycle.html#read-text
+    fn create_text_frame(&mut self, body: &[u8], mime_type: &MimeType) {
+        let encoding = determine_encoding(mime_type, body);
+        self.frame = Some(text_document(&encoding.decode(body)));
+        self.character_set = Some(encoding);
+    }
+
+    // 要素の種類ごとに指定した属性の値を基準URLをもとに解決する
+    fn resolve_attribute_urls(&self, targets: &[(ElementKind, &str)]) -> Vec<Url> {
+        let document = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return Vec::new(),
+        };
+        let mut urls = Vec::new();
+        for (element_kind, name) in targets {
+            for node in get_element_nodes(Some(document.clone()), *element_kind) {
+                if let Some(url) = node
+                    .borrow()
+                    .get_element()
+                    .and_then(|element| element.get_attribute(name))
+                    .and_then(|value| self.resolve_url(&value).ok())
+                {
+                    urls.push(url);
+                }
+            }
+        }
+        urls
+    }
+
+    // https://html.spec.whatwg.org/multipage/semantics.html#set-the-frozen-base-url
+    // <base href>がある場合はドキュメントのURLをもとに解決した値を基準URLにする
+    fn update_base_url(&mut self) {
+        self.base_url = self.url.clone();
+
+        let document = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+        let href = match get_target_element_node(Some(document), ElementKind::Base)
+            .and_then(|node| node.borrow().get_element())
+            .and_then(|element| element.get_attribute("href"))
+        {
+            Some(href) => href,
+            None => return,
+        };
+
+        // 解決に失敗した場合はドキュメントのURLをそのまま使う
+        if let Ok(base) = self.resolve_url(&href) {
+            self.base_url = Some(base);
+        }
+    }
+}
+
+// ページの読み込みの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadState {
+    Initial,       // まだ何も読み込んでいない
+    Loading,       // 読み込んでいる途中
+    Complete,      // 読み込みが終わった
+    Failed(Error), // 読み込みに失敗した。表示しているドキュメントは前のまま
+}
+
+impl Display for LoadState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadState::Initial => write!(f, "initial"),
+            LoadState::Loading => write!(f, "loading"),
+            LoadState::Complete => write!(f, "complete"),
+            LoadState::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+// ドキュメントを表示するためのリクエストを作る
+fn navigation_request(url: Url) -> HttpRequest {
+    let mut request = HttpRequest::new(Method::Get, url);
+    request.headers_mut().insert("Accept", "text/html");
+    request
+}
+
+// https://www.rfc-editor.org/rfc/rfc6266#section-4.2
+// Content-Disposition: attachmentの場合は、MIMEタイプに関係なくダウンロードする
+fn content_handler(response: &HttpResponse, mime_type: &MimeType) -> ContentHandler {
+    let attachment = response
+        .headers()
+        .content_disposition()
+        .is_some_and(|disposition| disposition.is_attachment());
+    if attachment {
+        return ContentHandler::Download;
+    }
+    ContentHandler::for_mime_type(mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::http::transport::{MockTransport, NetworkError};
+    use crate::loader::MemoryFileSystem;
+    use crate::renderer::history::MAX_CACHED_DOCUMENTS;
+    use alloc::format;
+    use alloc::vec;
+
+    fn create_page(url: &str, html: &str) -> Page {
+        let mut page = Page::new();
+        page.set_url(Url::parse(url).expect("failed to parse url"));
+        page.create_frame(html.to_string());
+        page.update_base_url();
+        page
+    }
+
+    #[test]
+    fn test_resolve_url_with_document_url() {
+        let page = create_page(
+            "http://example.com/dir/index.html",
+            "<html><body></body></html>",
+        );
+        assert_eq!(
+            Ok("http://example.com/dir/example.com".to_string()),
+            page.resolve_url("example.com").map(|url| url.to_string())
+        );
+        assert_eq!(
+            Ok("http://example.com/style.css".to_string()),
+            page.resolve_url("../style.css").map(|url| url.to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_url_with_base_element() {
+        let page = create_page(
+            "http://example.com/dir/index.html",
+            "<html><head><base href=\"/assets/\"></head><body></body></html>",
+        );
+        assert_eq!(
+            Some("http://example.com/assets/".to_string()),
+            page.base_url().map(|url| url.to_string())
+        );
+        assert_eq!(
+            Ok("http://example.com/assets/style.css".to_string()),
+            page.resolve_url("style.css").map(|url| url.to_string())
+        );
+        assert_eq!(
+            Ok("http://cdn.example.com/app.js".to_string()),
+            page.resolve_url("//cdn.example.com/app.js")
+                .map(|url| url.to_string())
+        );
+    }
+
+    #[test]
+    fn test_links_and_subresources_use_base_element() {
+        let page = create_page(
+            "http://example.com/dir/index.html",
+            "<html><head><base href=\"/assets/\"><script src=\"//cdn.example.com/app.js\"></script></head><body><a href=\"next.html\">next</a><p><a href=\"../top.html#a\">top</a></p><img src=\"logo.png\"></body></html>",
+        );
+        let to_strings = |urls: Vec<Url>| -> Vec<String> {
+            urls.iter().map(|url| url.to_string()).collect()
+        };
+        assert_eq!(
+            vec![
+                "http://example.com/assets/next.html".to_string(),
+                "http://example.com/top.html#a".to_string(),
+            ],
+            to_strings(page.links())
+        );
+        assert_eq!(
+            vec![
+                "http://example.com/assets/logo.png".to_string(),
+                "http://cdn.example.com/app.js".to_string(),
+            ],
+            to_strings(page.subresources())
+        );
+    }
+
+    #[test]
+    fn test_load_with_mock_transport() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/dir/index.html",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 56\r\n\r\n<html><head><base href=\"/a/\"></head><body></body></html>",
+        );
+        transport.add_response(
+            "http://example.com/a/next.html",
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n<html\r\n1\r\n>\r\n0\r\n\r\n",
+        );
+        let transport = Rc::new(transport);
+
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        let page = browser.borrow().current_page();
+
+        page.borrow_mut()
+            .load("http://example.com/dir/index.html")
+            .expect("failed to load page");
+        assert_eq!(
+            Some("http://example.com/a/".to_string()),
+            page.borrow().base_url().map(|url| url.to_string())
+        );
+
+        // 相対URLは<base href>をもとに解決される
+        page.borrow_mut()
+            .load("next.html")
+            .expect("failed to load page");
+        assert_eq!(
+            Some("http://example.com/a/next.html".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+        assert_eq!(2, transport.requests().len());
+        assert_eq!(
+            Some("text/html".to_string()),
+            transport.requests()[1].headers().get("Accept")
+        );
+
+        assert!(page.borrow_mut().load("/missing.html").is_err());
+    }
+
+    #[test]
+    fn test_load_follows_redirect() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 301 Moved Permanently\r\nLocation: /dir/index.html\r\nContent-Length: 0\r\n\r\n",
+        );
+        transport.add_response(
+            "http://example.com/dir/index.html",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\r\n<html></html>",
+        );
+
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(Rc::new(transport));
+        let page = browser.borrow().current_page();
+        page.borrow_mut()
+            .load("http://example.com/")
+            .expect("failed to load page");
+        assert_eq!(
+            Some("http://example.com/dir/index.html".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+        assert_eq!(
+            Ok("http://example.com/dir/style.css".to_string()),
+            page.borrow()
+                .resolve_url("style.css")
+                .map(|url| url.to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_local_urls() {
+        let mut file_system = MemoryFileSystem::new();
+        file_system.add_file(
+            "/pages/index.html",
+            b"<html><body><a href=\"next.html\">next</a></body></html>",
+        );
+        let browser = Browser::new();
+        browser.borrow_mut().set_file_system(Rc::new(file_system));
+        let page = browser.borrow().current_page();
+
+        // トランスポートがなくてもファイルは読み込める
+        let dom = page
+            .borrow_mut()
+            .load("file:///pages/index.html")
+            .expect("failed to load page");
+        assert!(dom.contains("kind: A,"));
+        assert_eq!(
+            Ok("file:///pages/next.html".to_string()),
+            page.borrow()
+                .resolve_url("next.html")
+                .map(|url| url.to_string())
+        );
+        assert!(page.borrow_mut().load("next.html").is_err());
+
+        let dom = page
+            .borrow_mut()
+            .load("data:text/html,%3Cp%3Ehi%3C%2Fp%3E")
+            .expect("failed to load page");
+        assert!(dom.contains("kind: P,"));
+
+        page.borrow_mut()
+            .load("about:blank")
+            .expect("failed to load page");
+        assert_eq!(
+            Some("about:blank".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_dispatches_by_mime_type() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/a.txt",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 9\r\n\r\n<p>hi</p>",
+        );
+        transport.add_response(
+            "http://example.com/a.png",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\n\x89PNG\r\n\x1a\n",
+        );
+        transport.add_response(
+            "http://example.com/a.bin",
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 3\r\n\r\n\x00\x01\x02",
+        );
+        transport.add_response(
+            "http://example.com/report",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Disposition: attachment; filename=report.html\r\nContent-Length: 13\r\n\r\n<html></html>",
+        );
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(Rc::new(transport));
+        let page = browser.borrow().current_page();
+
+        // text/plainはHTMLとしてパースしない
+        let dom = page
+            .borrow_mut()
+            .load("http://example.com/a.txt")
+            .expect("failed to load page");
+        assert!(dom.contains("Text(\"<p>hi</p>\")"));
+        assert!(!dom.contains("kind: P,"));
+        assert_eq!(
+            Some("text/plain".to_string()),
+            page.borrow().content_type().map(|m| m.to_string())
+        );
+
+        // Content-Typeがない場合はボディから判定する
+        let dom = page
+            .borrow_mut()
+            .load("http://example.com/a.png")
+            .expect("failed to load page");
+        assert!(dom.contains("kind: Img,"));
+        assert_eq!(
+            Some("image/png".to_string()),
+            page.borrow().content_type().map(|m| m.to_string())
+        );
+
+        // ダウンロードした場合は表示しているドキュメントを変えない
+        let dom = page
+            .borrow_mut()
+            .load("http://example.com/a.bin")
+            .expect("failed to load page");
+        assert!(dom.contains("kind: Img,"));
+        page.borrow_mut()
+            .load("http://example.com/report")
+            .expect("failed to load page");
+        assert_eq!(
+            Some("http://example.com/a.png".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+
+        let downloads = browser.borrow().downloads();
+        assert_eq!(2, downloads.len());
+        assert_eq!("a.bin", downloads[0].file_name());
+        assert_eq!(vec![0, 1, 2], downloads[0].body());
+        assert_eq!("report.html", downloads[1].file_name());
+        assert_eq!("text/html", downloads[1].mime_type().to_string());
+    }
+
+    #[test]
+    fn test_load_streams_long_html() {
+        // 判定に使う長さを超えるHTMLも、チャンクごとにパースする
+        let mut html = String::from("<html><body><p>");
+        html.push_str(&"a".repeat(RESOURCE_HEADER_SIZE));
+        html.push_str("</p></body></html>");
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            format!(
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n10\r\n<p>second</p>   \r\n0\r\n\r\n",
+                html.len(),
+                html
+            )
+            .as_bytes(),
+        );
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(Rc::new(transport));
+        let page = browser.borrow().current_page();
+        let dom = page
+            .borrow_mut()
+            .load("http://example.com/")
+            .expect("failed to load page");
+        assert_eq!(2, dom.matches("kind: P,").count());
+        assert_eq!(
+            Some("text/html".to_string()),
+            page.borrow().content_type().map(|m| m.to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_keeps_document_when_stream_fails() {
+        // ボディの途中で接続が切れた場合は、途中までパースしたドキュメントではなく前のドキュメントを表示する
+        let body = format!("<html><body><p>{}", "b".repeat(RESOURCE_HEADER_SIZE));
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/a",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 16\r\n\r\n<p>old page</p>\n",
+        );
+        transport.add_response(
+            "http://example.com/b",
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                body.len() + 100,
+                body
+            )
+            .as_bytes(),
+        );
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(Rc::new(transport));
+        let page = browser.borrow().current_page();
+        let old_dom = page
+            .borrow_mut()
+            .load("http://example.com/a")
+            .expect("failed to load page");
+
+        assert!(page.borrow_mut().load("http://example.com/b").is_err());
+        assert_eq!(old_dom, page.borrow().dom_string());
+        assert!(page.borrow().dom_string().contains("old page"));
+        assert_eq!(
+            Some("http://example.com/a".to_string()),
+            page.borrow().url().map(|url| url.to_string())
+        );
+        assert!(matches!(page.borrow().load_state(), LoadState::Failed(_)));
+        assert_eq!(1, page.borrow().history().len());
+    }
+
+    #[test]
+    fn test_load_decodes_legacy_encodings() {
+        let mut transport = MockTransport::new();
+        // 「こんにちは」をShift_JIS、EUC-JPで表したもの
+        transport.add_response(
+            "http://example.com/sjis.html",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 43\r\n\r\n<meta charset=\"Shift_JIS\"><p>\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd</p>",
+        );
+        transport.add_response(
+            "http://example.com/euc.txt",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=EUC-JP\r\nContent-Length: 10\r\n\r\n\xa4\xb3\xa4\xf3\xa4\xcb\xa4\xc1\xa4\xcf",
+        );
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(Rc::new(transport));
+        let page = browser.borrow().current_page();
+
+        let dom = page
+            .borrow_mut()
+            .load("http://example.com/sjis.html")
+            .expect("failed to load page");
+        assert!(dom.contains("Text(\"こんにちは\")"));
+        assert_eq!(Some(Encoding::ShiftJis), page.borrow().character_set());
+
+        let dom = page
+            .borrow_mut()
+            .load("http://example.com/euc.txt")
+            .expect("failed to load page");
+        assert!(dom.contains("Text(\"こんにちは\")"));
+        assert_eq!(Some(Encoding::EucJp), page.borrow().character_set());
+    }
+
+    #[test]
+    fn test_history() {
+        let mut transport = MockTransport::new();
+        for (path, title) in [("a", "A"), ("b", "B"), ("c", "C")] {
+            let html = format!("<html><head><title>{}</title></head></html>", title);
+            transport.add_response(
+                &format!("http://example.com/{}", path),
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    html.len(),
+                    html
+                )
+                .as_bytes(),
+            );
+        }
+        let transport = Rc::new(transport);
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        let page = browser.borrow().current_page();
+        let url = |page: &Rc<RefCell<Page>>| page.borrow().url().map(|url| url.to_string());
+
+        page.borrow_mut()
+            .load("http://example.com/a")
+            .expect("failed to load page");
+        page.borrow_mut()
+            .set_scroll_position(ScrollPosition::new(0, 120));
+        page.borrow_mut().load("b").expect("failed to load page");
+        assert_eq!(ScrollPosition::default(), page.borrow().scroll_position());
+        // # 以降だけが違う場合は読み込み直さない
+        page.borrow_mut()
+            .load("#section")
+            .expect("failed to load page");
+        assert_eq!(2, transport.requests().len());
+        assert_eq!(3, page.borrow().history().len());
+        assert_eq!(Some("http://example.com/b#section".to_string()), url(&page));
+
+        // 残しているDOMツリーを表示し、スクロール位置を戻す
+        page.borrow_mut().back().expect("failed to go back");
+        assert_eq!(Some("http://example.com/b".to_string()), url(&page));
+        page.borrow_mut().back().expect("failed to go back");
+        assert_eq!(Some("http://example.com/a".to_string()), url(&page));
+        assert_eq!("A", page.borrow().title());
+        assert_eq!(ScrollPosition::new(0, 120), page.borrow().scroll_position());
+        assert_eq!(2, transport.requests().len());
+        assert!(!page.borrow().can_go_back());
+        // 範囲外の場合は何もしない
+        page.borrow_mut().go(-1).expect("failed to go");
+        assert_eq!(Some("http://example.com/a".to_string()), url(&page));
+
+        page.borrow_mut().forward().expect("failed to go forward");
+        assert_eq!("B", page.borrow().title());
+        assert!(page.borrow().can_go_forward());
+
+        // 新しく移動すると、今の項目より後ろの項目は取り除かれる
+        page.borrow_mut().load("c").expect("failed to load page");
+        assert!(!page.borrow().can_go_forward());
+        let titles: Vec<String> = page
+            .borrow()
+            .history()
+            .entries()
+            .iter()
+            .map(|entry| entry.title())
+            .collect();
+        assert_eq!(vec!["A", "B", "C"], titles);
+
+        // 読み込み直しても履歴の項目は増えない
+        page.borrow_mut().go(-2).expect("failed to go");
+        page.borrow_mut().reload().expect("failed to reload");
+        assert_eq!(4, transport.requests().len());
+        assert_eq!(3, page.borrow().history().len());
+        assert_eq!(Some(0), page.borrow().history().current_index());
+        assert_eq!(ScrollPosition::new(0, 120), page.borrow().scroll_position());
+        page.borrow_mut()
+            .load("http://example.com/a")
+            .expect("failed to load page");
+        assert_eq!(3, page.borrow().history().len());
+    }
+
+    #[test]
+    fn test_reload_revalidates_fresh_cache() {
+        let mut transport = MockTransport::new();
+        transport.add_response(
+            "http://example.com/",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nCache-Control: max-age=3600\r\nContent-Length: 9\r\n\r\n<p>hi</p>",
+        );
+        let transport = Rc::new(transport);
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        browser
+            .borrow_mut()
+            .set_clock(Rc::new(ManualClock::new(1445412480)));
+        let page = browser.borrow().current_page();
+        page.borrow_mut()
+            .load("http://example.com/")
+            .expect("failed to load page");
+        page.borrow_mut()
+            .load("http://example.com/")
+            .expect("failed to load page");
+        assert_eq!(1, transport.requests().len());
+
+        // 新鮮なレスポンスをキャッシュしていても、読み込み直す場合はサーバーに送る
+        page.borrow_mut().reload().expect("failed to reload");
+        let requests = transport.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(
+            Some("max-age=0".to_string()),
+            requests[1].headers().get("Cache-Control")
+        );
+        assert_eq!(LoadState::Complete, page.borrow().load_state());
+    }
+
+    #[test]
+    fn test_reload_fragment_and_go_to_evicted_entry() {
+        let mut transport = MockTransport::new();
+        for i in 0..MAX_CACHED_DOCUMENTS + 2 {
+            let html = format!("<html><head><title>{}</title></head></html>", i);
+            transport.add_response(
+                &format!("http://example.com/{}", i),
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    html.len(),
+                    html
+                )
+                .as_bytes(),
+            );
+        }
+        let transport = Rc::new(transport);
+        let browser = Browser::new();
+        browser.borrow_mut().set_transport(transport.clone());
+        let page = browser.borrow().current_page();
+        let url = |page: &Rc<RefCell<Page>>| page.borrow().url().map(|url| url.to_string());
+
+        page.borrow_mut()
+            .load("http://example.com/0")
+            .expect("failed to load page");
+        page.borrow_mut().load("#top").expect("failed to load page");
+        // フラグメントのあるURLを読み込み直しても、URLと履歴の項目はそのまま
+        page.borrow_mut().reload().expect("failed to reload");
+        assert_eq!(2, transport.requests().len());
+        assert_eq!(Some("http://example.com/0#top".to_string()), url(&page));
+        assert_eq!(2, page.borrow().history().len());
+        assert_eq!(Some(1), page.borrow().history().current_index());
+        assert_eq!("0", page.borrow().title());
+
+        for i in 1..MAX_CACHED_DOCUMENTS + 2 {
+            page.borrow_mut()
+                .load(&i.to_string())
+                .expect("failed to load page");
+        }
+        let history = page.borrow().history();
+        assert!(history.get(0).and_then(|entry| entry.document()).is_none());
+
+        // DOMツリーを捨てた項目に戻る場合はURLを読み込み直す
+        let requests = transport.requests().len();
+        page.borrow_mut()
+            .go(-(history.len() as isize - 1))
+            .expect("failed to go");
+        assert_eq!(requests + 1, transport.requests().len());
+        assert_eq!(Some("http://example.com/0".to_string()), url(&page));
+        assert_eq!("0", page.borrow().title());
+        assert_eq!(Some(0), page.borrow().history().current_index());
+        assert_eq!(history.len(), page.borrow().history().len());
+    }
+
+    #[test]
+    fn test_load_without_transport() {
+        let mut page = Page::new();
+        assert_eq!(
+            Err(Error::Network(NetworkError::NoTransport)),
+            page.load("http://example.com/")
+        );
+    }
+
+    #[test]
+    fn test_resolve_url_without_document_url() {
+        let page = Page::new();
+        assert!(page.resolve_url("style.css").is_err());
+        assert!(page.resolve_url("http://example.com/style.css").is_ok());
+    }
 }
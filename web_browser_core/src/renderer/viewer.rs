@@ -0,0 +1,80 @@
+use crate::renderer::dom::api::append_child;
+use crate::renderer::dom::node::{Element, Node, NodeKind, Window};
+use crate::renderer::html::attribute::Attribute;
+use alloc::rc::Rc;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// HTML以外のレスポンスを表示するためのドキュメントを作る
+
+// https://html.spec.whatwg.org/multipage/document-lifecycle.html#read-text
+// テキスト全体を1つのテキストノードとしてbodyに入れる
+pub fn text_document(text: &str) -> Rc<RefCell<Window>> {
+    let (window, body) = empty_document();
+    if !text.is_empty() {
+        let node = Node::new(NodeKind::Text(text.to_string()));
+        append_child(&body, Rc::new(RefCell::new(node)));
+    }
+    window
+}
+
+// https://html.spec.whatwg.org/multipage/document-lifecycle.html#read-media
+// srcの画像を表示する<img>だけをbodyに入れる
+pub fn image_document(src: &str) -> Rc<RefCell<Window>> {
+    let (window, body) = empty_document();
+    let mut attribute = Attribute::new();
+    "src".chars().for_each(|c| attribute.add_char(c, true));
+    src.chars().for_each(|c| attribute.add_char(c, false));
+    append_child(&body, element("img", vec![attribute]));
+    window
+}
+
+// html、head、bodyだけのドキュメントと、そのbodyを返す
+fn empty_document() -> (Rc<RefCell<Window>>, Rc<RefCell<Node>>) {
+    let window = Rc::new(RefCell::new(Window::new()));
+    let document = window.borrow().document();
+    let html = element("html", Vec::new());
+    append_child(&document, html.clone());
+    append_child(&html, element("head", Vec::new()));
+    let body = element("body", Vec::new());
+    append_child(&html, body.clone());
+    (window, body)
+}
+
+fn element(tag: &str, attributes: Vec<Attribute>) -> Rc<RefCell<Node>> {
+    Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+        tag, attributes,
+    )))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::utils::convert_dom_to_string;
+
+    #[test]
+    fn test_text_document() {
+        let window = text_document("<p>not html</p>\n");
+        let document = window.borrow().document();
+        assert_eq!(
+            "\nDocument\n  Element(Element { kind: Html, attributes: [] })\n    Element(Element { kind: Head, attributes: [] })\n    Element(Element { kind: Body, attributes: [] })\n      Text(\"<p>not html</p>\\n\")\n",
+            convert_dom_to_string(&Some(document))
+        );
+    }
+
+    #[test]
+    fn test_image_document() {
+        let window = image_document("http://example.com/a.png");
+        let img = get_target_element_node(Some(window.borrow().document()), ElementKind::Img)
+            .and_then(|node| node.borrow().get_element())
+            .expect("no img element");
+        assert_eq!(
+            Some("http://example.com/a.png".to_string()),
+            img.get_attribute("src")
+        );
+    }
+}
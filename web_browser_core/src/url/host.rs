@@ -0,0 +1,541 @@
+use crate::url::percent_encoding::{percent_decode, utf8_percent_encode_str, PercentEncodeSet};
+use crate::url::UrlError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+// https://url.spec.whatwg.org/#concept-host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Host {
+    Domain(String), // https://url.spec.whatwg.org/#concept-domain
+    Ipv4([u8; 4]),  // https://url.spec.whatwg.org/#concept-ipv4
+    Ipv6([u16; 8]), // https://url.spec.whatwg.org/#concept-ipv6
+    Opaque(String), // https://url.spec.whatwg.org/#opaque-host
+    Empty,          // https://url.spec.whatwg.org/#empty-host
+}
+
+impl Host {
+    // https://url.spec.whatwg.org/#concept-host-parser
+    // is_opaqueは特別なスキーム（http、httpsなど）以外のURLのときにtrueになる
+    pub fn parse(input: &str, is_opaque: bool) -> Result<Self, UrlError> {
+        // [ から始まる場合はIPv6アドレスとして解釈する
+        if let Some(rest) = input.strip_prefix('[') {
+            return match rest.strip_suffix(']') {
+                Some(address) => Ok(Host::Ipv6(parse_ipv6(address)?)),
+                None => Err(UrlError::InvalidIpv6Address(input.to_string())),
+            };
+        }
+
+        if is_opaque {
+            return parse_opaque_host(input);
+        }
+
+        // パーセントエンコードを戻してからドメインとして解釈する
+        let domain = String::from_utf8_lossy(&percent_decode(input.as_bytes())).to_string();
+        let ascii_domain = domain_to_ascii(&domain)?;
+
+        if ascii_domain.chars().any(is_forbidden_domain_code_point) {
+            return Err(UrlError::InvalidDomain(input.to_string()));
+        }
+
+        // 最後のラベルが数字の場合はIPv4アドレスとして解釈する
+        if ends_in_a_number(&ascii_domain) {
+            return Ok(Host::Ipv4(parse_ipv4(&ascii_domain)?));
+        }
+
+        Ok(Host::Domain(ascii_domain))
+    }
+}
+
+// https://url.spec.whatwg.org/#concept-host-serializer
+impl Display for Host {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Host::Domain(s) | Host::Opaque(s) => write!(f, "{}", s),
+            Host::Ipv4(a) => write!(f, "{}.{}.{}.{}", a[0], a[1], a[2], a[3]),
+            Host::Ipv6(a) => write!(f, "[{}]", serialize_ipv6(a)),
+            Host::Empty => Ok(()),
+        }
+    }
+}
+
+// https://url.spec.whatwg.org/#forbidden-host-code-point
+fn is_forbidden_host_code_point(c: char) -> bool {
+    matches!(
+        c,
+        '\0' | '\t'
+            | '\n'
+            | '\r'
+            | ' '
+            | '#'
+            | '/'
+            | ':'
+            | '<'
+            | '>'
+            | '?'
+            | '@'
+            | '['
+            | '\\'
+            | ']'
+            | '^'
+            | '|'
+    )
+}
+
+// https://url.spec.whatwg.org/#forbidden-domain-code-point
+fn is_forbidden_domain_code_point(c: char) -> bool {
+    is_forbidden_host_code_point(c) || c.is_ascii_control() || c == '%'
+}
+
+// https://url.spec.whatwg.org/#concept-opaque-host-parser
+fn parse_opaque_host(input: &str) -> Result<Host, UrlError> {
+    if input.chars().any(is_forbidden_host_code_point) {
+        return Err(UrlError::InvalidOpaqueHost(input.to_string()));
+    }
+    Ok(Host::Opaque(utf8_percent_encode_str(
+        input,
+        PercentEncodeSet::C0Control,
+    )))
+}
+
+// https://url.spec.whatwg.org/#concept-domain-to-ascii
+// UTS #46のマッピングテーブルは持たないので、小文字化とPunycodeへの変換のみを行う
+fn domain_to_ascii(domain: &str) -> Result<String, UrlError> {
+    let mut labels = Vec::new();
+    for label in domain.split('.') {
+        if label.is_ascii() {
+            labels.push(label.to_ascii_lowercase());
+            continue;
+        }
+        let lowered: String = label.chars().flat_map(|c| c.to_lowercase()).collect();
+        labels.push(format!("xn--{}", punycode_encode(&lowered)?));
+    }
+
+    let ascii = labels.join(".");
+    if ascii.is_empty() {
+        return Err(UrlError::EmptyHost);
+    }
+    Ok(ascii)
+}
+
+// https://datatracker.ietf.org/doc/html/rfc3492#section-6.3
+fn punycode_encode(input: &str) -> Result<String, UrlError> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+    const INITIAL_BIAS: u32 = 72;
+    const INITIAL_N: u32 = 128;
+
+    fn adapt(mut delta: u32, num_points: u32, first_time: bool) -> u32 {
+        delta /= if first_time { DAMP } else { 2 };
+        delta += delta / num_points;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+    }
+
+    fn encode_digit(d: u32) -> char {
+        if d < 26 {
+            (b'a' + d as u8) as char
+        } else {
+            (b'0' + (d - 26) as u8) as char
+        }
+    }
+
+    let code_points: Vec<u32> = input.chars().map(|c| c as u32).collect();
+    let mut output: String = input.chars().filter(|c| c.is_ascii()).collect();
+    let basic_len = output.len() as u32;
+    let mut handled = basic_len;
+    if basic_len > 0 {
+        output.push('-');
+    }
+
+    let overflow = || UrlError::PunycodeOverflow;
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    while (handled as usize) < code_points.len() {
+        let m = code_points
+            .iter()
+            .copied()
+            .filter(|&c| c >= n)
+            .min()
+            .ok_or_else(overflow)?;
+        delta = (m - n)
+            .checked_mul(handled + 1)
+            .and_then(|d| delta.checked_add(d))
+            .ok_or_else(overflow)?;
+        n = m;
+        for &c in &code_points {
+            if c < n {
+                delta = delta.checked_add(1).ok_or_else(overflow)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        T_MIN
+                    } else if k >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, handled + 1, handled == basic_len);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+
+    Ok(output)
+}
+
+// https://url.spec.whatwg.org/#ends-in-a-number-checker
+fn ends_in_a_number(input: &str) -> bool {
+    let mut parts: Vec<&str> = input.split('.').collect();
+    if parts.last() == Some(&"") {
+        if parts.len() == 1 {
+            return false;
+        }
+        parts.pop();
+    }
+
+    let last = parts.last().copied().unwrap_or("");
+    if !last.is_empty() && last.chars().all(|c| c.is_ascii_digit()) {
+        return true;
+    }
+
+    parse_ipv4_number(last).is_ok()
+}
+
+// https://url.spec.whatwg.org/#concept-ipv4-parser
+fn parse_ipv4(input: &str) -> Result<[u8; 4], UrlError> {
+    let mut parts: Vec<&str> = input.split('.').collect();
+    if parts.last() == Some(&"") && parts.len() > 1 {
+        parts.pop();
+    }
+
+    if parts.len() > 4 {
+        return Err(UrlError::InvalidIpv4Address(input.to_string()));
+    }
+
+    let mut numbers = Vec::new();
+    for part in parts {
+        numbers.push(
+            parse_ipv4_number(part).map_err(|_| UrlError::InvalidIpv4Address(input.to_string()))?,
+        );
+    }
+
+    // 最後以外の数値は255以下でなければならない
+    let (last, rest) = numbers
+        .split_last()
+        .expect("numbers should have at least 1 item");
+    if rest.iter().any(|&n| n > 255) {
+        return Err(UrlError::InvalidIpv4Address(input.to_string()));
+    }
+    // 最後の数値は残りのバイトすべてを表すことができる（例: 127.1 は 127.0.0.1）
+    if *last >= 256u64.pow(5 - numbers.len() as u32) {
+        return Err(UrlError::InvalidIpv4Address(input.to_string()));
+    }
+
+    let mut ipv4 = *last;
+    for (i, n) in rest.iter().enumerate() {
+        ipv4 += n * 256u64.pow(3 - i as u32);
+    }
+
+    Ok((ipv4 as u32).to_be_bytes())
+}
+
+// https://url.spec.whatwg.org/#ipv4-number-parser
+// 0xから始まる場合は16進数、0から始まる場合は8進数として解釈する
+fn parse_ipv4_number(input: &str) -> Result<u64, ()> {
+    if input.is_empty() {
+        return Err(());
+    }
+
+    let (digits, radix) = if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if input.len() >= 2 && input.starts_with('0') {
+        (&input[1..], 8)
+    } else {
+        (input, 10)
+    };
+
+    if digits.is_empty() {
+        return Ok(0);
+    }
+
+    let mut value: u64 = 0;
+    for c in digits.chars() {
+        let d = c.to_digit(radix).ok_or(())?;
+        value = value
+            .checked_mul(radix as u64)
+            .and_then(|v| v.checked_add(d as u64))
+            .ok_or(())?;
+    }
+    Ok(value)
+}
+
+// https://url.spec.whatwg.org/#concept-ipv6-parser
+fn parse_ipv6(input: &str) -> Result<[u16; 8], UrlError> {
+    let err = || UrlError::InvalidIpv6Address(input.to_string());
+    let chars: Vec<char> = input.chars().collect();
+    let at = |i: usize| chars.get(i).copied();
+
+    let mut address = [0u16; 8];
+    let mut piece_index = 0;
+    let mut compress: Option<usize> = None;
+    let mut pointer = 0;
+
+    // :: から始まる場合は先頭を圧縮する
+    if at(pointer) == Some(':') {
+        if at(pointer + 1) != Some(':') {
+            return Err(err());
+        }
+        pointer += 2;
+        piece_index += 1;
+        compress = Some(piece_index);
+    }
+
+    while let Some(c) = at(pointer) {
+        if piece_index == 8 {
+            return Err(err());
+        }
+
+        if c == ':' {
+            if compress.is_some() {
+                return Err(err());
+            }
+            pointer += 1;
+            piece_index += 1;
+            compress = Some(piece_index);
+            continue;
+        }
+
+        let mut value: u16 = 0;
+        let mut length = 0;
+        while length < 4 {
+            match at(pointer).and_then(|c| c.to_digit(16)) {
+                Some(d) => {
+                    value = value * 0x10 + d as u16;
+                    pointer += 1;
+                    length += 1;
+                }
+                None => break,
+            }
+        }
+
+        match at(pointer) {
+            // IPv4アドレスが埋め込まれている場合（例: ::ffff:192.168.0.1）
+            Some('.') => {
+                if length == 0 {
+                    return Err(err());
+                }
+                pointer -= length;
+                if piece_index > 6 {
+                    return Err(err());
+                }
+
+                let mut numbers_seen = 0;
+                while at(pointer).is_some() {
+                    let mut ipv4_piece: Option<u16> = None;
+                    if numbers_seen > 0 {
+                        if at(pointer) == Some('.') && numbers_seen < 4 {
+                            pointer += 1;
+                        } else {
+                            return Err(err());
+                        }
+                    }
+
+                    if !at(pointer).is_some_and(|c| c.is_ascii_digit()) {
+                        return Err(err());
+                    }
+
+                    while let Some(d) = at(pointer).and_then(|c| c.to_digit(10)) {
+                        ipv4_piece = match ipv4_piece {
+                            None => Some(d as u16),
+                            Some(0) => return Err(err()),
+                            Some(p) => Some(p * 10 + d as u16),
+                        };
+                        if ipv4_piece > Some(255) {
+                            return Err(err());
+                        }
+                        pointer += 1;
+                    }
+
+                    address[piece_index] =
+                        address[piece_index] * 0x100 + ipv4_piece.expect("ipv4 piece should exist");
+                    numbers_seen += 1;
+                    if numbers_seen == 2 || numbers_seen == 4 {
+                        piece_index += 1;
+                    }
+                }
+
+                if numbers_seen != 4 {
+                    return Err(err());
+                }
+                break;
+            }
+            Some(':') => {
+                pointer += 1;
+                if at(pointer).is_none() {
+                    return Err(err());
+                }
+            }
+            Some(_) => return Err(err()),
+            None => {}
+        }
+
+        address[piece_index] = value;
+        piece_index += 1;
+    }
+
+    match compress {
+        // 圧縮された部分を0で埋めるため、圧縮位置以降の値を後ろに移動する
+        Some(compress) => {
+            let mut swaps = piece_index - compress;
+            piece_index = 7;
+            while piece_index != 0 && swaps > 0 {
+                address.swap(piece_index, compress + swaps - 1);
+                piece_index -= 1;
+                swaps -= 1;
+            }
+        }
+        None => {
+            if piece_index != 8 {
+                return Err(err());
+            }
+        }
+    }
+
+    Ok(address)
+}
+
+// https://url.spec.whatwg.org/#concept-ipv6-serializer
+// 最も長い0の連続（2つ以上）を :: に圧縮する
+fn serialize_ipv6(address: &[u16; 8]) -> String {
+    let mut compress: Option<usize> = None;
+    let mut longest = 1;
+    let mut i = 0;
+    while i < 8 {
+        if address[i] != 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < 8 && address[i] == 0 {
+            i += 1;
+        }
+        if i - start > longest {
+            longest = i - start;
+            compress = Some(start);
+        }
+    }
+
+    let mut output = String::new();
+    let mut ignore0 = false;
+    for (index, piece) in address.iter().enumerate() {
+        if ignore0 && *piece == 0 {
+            continue;
+        }
+        ignore0 = false;
+
+        if compress == Some(index) {
+            output.push_str(if index == 0 { "::" } else { ":" });
+            ignore0 = true;
+            continue;
+        }
+
+        output.push_str(&format!("{:x}", piece));
+        if index != 7 {
+            output.push(':');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(
+            Ok(Host::Domain("example.com".to_string())),
+            Host::parse("EXAMPLE.com", false)
+        );
+    }
+
+    #[test]
+    fn test_forbidden_domain() {
+        assert!(Host::parse("exa mple.com", false).is_err());
+        assert!(Host::parse("exa%25mple.com", false).is_err());
+    }
+
+    #[test]
+    fn test_punycode() {
+        assert_eq!(
+            Ok(Host::Domain("xn--r8jz45g.jp".to_string())),
+            Host::parse("例え.jp", false)
+        );
+    }
+
+    #[test]
+    fn test_ipv4() {
+        assert_eq!(
+            Ok(Host::Ipv4([127, 0, 0, 1])),
+            Host::parse("127.0.0.1", false)
+        );
+        assert_eq!(Ok(Host::Ipv4([127, 0, 0, 1])), Host::parse("127.1", false));
+        assert_eq!(
+            Ok(Host::Ipv4([192, 168, 0, 1])),
+            Host::parse("0xc0.0250.0.1", false)
+        );
+        assert!(Host::parse("1.2.3.256", false).is_err());
+        assert!(Host::parse("1.2.3.4.5", false).is_err());
+    }
+
+    #[test]
+    fn test_ipv6() {
+        let host = Host::parse("[2001:DB8::1]", false).expect("failed to parse IPv6 address");
+        assert_eq!(Host::Ipv6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]), host);
+        assert_eq!("[2001:db8::1]", host.to_string());
+
+        let host =
+            Host::parse("[::ffff:192.168.0.1]", false).expect("failed to parse IPv6 address");
+        assert_eq!("[::ffff:c0a8:1]", host.to_string());
+
+        assert_eq!("[::]", Host::parse("[::]", false).unwrap().to_string());
+        assert!(Host::parse("[1:2:3]", false).is_err());
+        assert!(Host::parse("[::1", false).is_err());
+    }
+
+    #[test]
+    fn test_opaque_host() {
+        assert_eq!(
+            Ok(Host::Opaque("EXAMPLE.com".to_string())),
+            Host::parse("EXAMPLE.com", true)
+        );
+        assert!(Host::parse("a<b", true).is_err());
+    }
+}
@@ -0,0 +1,652 @@
+use crate::url::host::Host;
+use crate::url::percent_encoding::{
+    utf8_percent_encode, utf8_percent_encode_str, PercentEncodeSet,
+};
+use crate::url::{default_port, Url, UrlError, UrlPath};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// https://url.spec.whatwg.org/#concept-basic-url-parser
+#[derive(Debug, Clone)]
+pub struct UrlParser {
+    state: State,
+    pos: usize,
+    input: Vec<char>,
+    buf: String,
+    at_sign_seen: bool,        // authorityに @ が出現したかどうか
+    inside_brackets: bool,     // ホスト名の [ ] の内側かどうか（IPv6アドレス）
+    password_token_seen: bool, // ユーザー情報に : が出現したかどうか
+    base: Option<Url>,         // 相対URLを解決するときの基準となるURL
+    url: Url,
+}
+
+impl UrlParser {
+    pub fn new(input: &str, base: Option<&Url>) -> Self {
+        // 先頭と末尾のC0制御文字、空白を取り除き、タブと改行をすべて取り除く
+        let input = input
+            .trim_matches(|c: char| c <= ' ')
+            .chars()
+            .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+            .collect();
+
+        Self {
+            state: State::SchemeStart,
+            pos: 0,
+            input,
+            buf: String::new(),
+            at_sign_seen: false,
+            inside_brackets: false,
+            password_token_seen: false,
+            base: base.cloned(),
+            url: Url::empty(),
+        }
+    }
+
+    // 現在の位置以降の入力（cの次の文字から）がsで始まるかどうか
+    fn remaining_starts_with(&self, s: &str) -> bool {
+        let rest = self.input.iter().skip(self.pos + 1);
+        s.chars().count() <= rest.len() && s.chars().zip(rest).all(|(a, b)| a == *b)
+    }
+
+    // 現在の位置の入力がWindowsのドライブ文字（C: や C|）から始まるかどうか
+    // https://url.spec.whatwg.org/#start-with-a-windows-drive-letter
+    fn starts_with_windows_drive_letter(&self) -> bool {
+        let rest = &self.input[self.pos.min(self.input.len())..];
+        if rest.len() < 2 || !is_windows_drive_letter(&rest[..2].iter().collect::<String>()) {
+            return false;
+        }
+        rest.len() == 2 || matches!(rest[2], '/' | '\\' | '?' | '#')
+    }
+
+    // 基準URLのユーザー情報、ホスト、ポートをコピーする
+    fn copy_authority_from_base(&mut self, base: &Url) {
+        self.url.username = base.username.clone();
+        self.url.password = base.password.clone();
+        self.url.host = base.host.clone();
+        self.url.port = base.port;
+    }
+
+    // https://url.spec.whatwg.org/#shorten-a-urls-path
+    fn shorten_path(&mut self) {
+        let is_file = self.url.scheme == "file";
+        if let UrlPath::Segments(ref mut segments) = self.url.path {
+            if is_file && segments.len() == 1 && is_normalized_windows_drive_letter(&segments[0]) {
+                return;
+            }
+            segments.pop();
+        }
+    }
+
+    fn push_path_segment(&mut self, segment: String) {
+        if let UrlPath::Segments(ref mut segments) = self.url.path {
+            segments.push(segment);
+        }
+    }
+
+    fn path_is_empty(&self) -> bool {
+        match self.url.path {
+            UrlPath::Segments(ref segments) => segments.is_empty(),
+            UrlPath::Opaque(ref s) => s.is_empty(),
+        }
+    }
+
+    // posまでの入力のバイト数。エラーの位置を示すのに使う
+    fn byte_offset(&self, pos: usize) -> usize {
+        self.input[..pos.min(self.input.len())]
+            .iter()
+            .map(|c| c.len_utf8())
+            .sum()
+    }
+
+    pub fn parse(mut self) -> Result<Url, UrlError> {
+        loop {
+            // 入力の最後に到達した場合はNone（EOF）として扱う
+            let c = self.input.get(self.pos).copied();
+
+            match self.state {
+                // https://url.spec.whatwg.org/#scheme-start-state
+                State::SchemeStart => {
+                    match c {
+                        Some(c) if c.is_ascii_alphabetic() => {
+                            self.buf.push(c.to_ascii_lowercase());
+                            self.state = State::Scheme;
+                        }
+                        _ => {
+                            // スキームから始まらない場合は同じ文字をNoSchemeで再度扱う
+                            self.state = State::NoScheme;
+                            continue;
+                        }
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#scheme-state
+                State::Scheme => match c {
+                    Some(c) if c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.' => {
+                        self.buf.push(c.to_ascii_lowercase());
+                    }
+                    Some(':') => {
+                        self.url.scheme = core::mem::take(&mut self.buf);
+                        let base_has_same_scheme = self
+                            .base
+                            .as_ref()
+                            .is_some_and(|base| base.scheme == self.url.scheme);
+                        if self.url.scheme == "file" {
+                            self.state = State::File;
+                        } else if self.url.is_special() && base_has_same_scheme {
+                            // http:foo のように基準URLと同じスキームの場合は相対URLとして扱う
+                            self.state = State::SpecialRelativeOrAuthority;
+                        } else if self.url.is_special() {
+                            self.state = State::SpecialAuthoritySlashes;
+                        } else if self.remaining_starts_with("/") {
+                            self.state = State::PathOrAuthority;
+                            self.pos += 1;
+                        } else {
+                            // mailto:、data:などの / が続かないURLは不透明なパスを持つ
+                            self.url.path = UrlPath::Opaque(String::new());
+                            self.state = State::OpaquePath;
+                        }
+                    }
+                    _ => {
+                        // スキームとして不正な文字が出てきた場合は最初から読み直す
+                        self.buf = String::new();
+                        self.state = State::NoScheme;
+                        self.pos = 0;
+                        continue;
+                    }
+                },
+
+                // https://url.spec.whatwg.org/#no-scheme-state
+                State::NoScheme => {
+                    let base = match self.base {
+                        Some(ref base) if !base.has_opaque_path() || c == Some('#') => base.clone(),
+                        _ => {
+                            return Err(UrlError::MissingScheme(
+                                self.input.iter().collect::<String>(),
+                            ))
+                        }
+                    };
+
+                    if base.has_opaque_path() {
+                        // about:blank#top のように不透明なパスに対してはフラグメントのみ変更できる
+                        self.url.scheme = base.scheme;
+                        self.url.path = base.path;
+                        self.url.query = base.query;
+                        self.url.fragment = Some(String::new());
+                        self.state = State::Fragment;
+                    } else if base.scheme != "file" {
+                        self.state = State::Relative;
+                        continue;
+                    } else {
+                        self.state = State::File;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#special-relative-or-authority-state
+                State::SpecialRelativeOrAuthority => {
+                    if c == Some('/') && self.remaining_starts_with("/") {
+                        self.state = State::SpecialAuthorityIgnoreSlashes;
+                        self.pos += 1;
+                    } else {
+                        self.state = State::Relative;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#path-or-authority-state
+                State::PathOrAuthority => {
+                    if c == Some('/') {
+                        self.state = State::Authority;
+                    } else {
+                        self.state = State::Path;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#relative-state
+                State::Relative => {
+                    let base = self.base.clone().expect("base should exist in relative state");
+                    self.url.scheme = base.scheme.clone();
+                    if c == Some('/') || (self.url.is_special() && c == Some('\\')) {
+                        self.state = State::RelativeSlash;
+                    } else {
+                        // パスのみ、クエリのみ、フラグメントのみの相対URLは基準URLの値を引き継ぐ
+                        self.copy_authority_from_base(&base);
+                        self.url.path = base.path;
+                        self.url.query = base.query;
+                        match c {
+                            Some('?') => {
+                                self.url.query = Some(String::new());
+                                self.state = State::Query;
+                            }
+                            Some('#') => {
+                                self.url.fragment = Some(String::new());
+                                self.state = State::Fragment;
+                            }
+                            Some(_) => {
+                                // 基準URLの最後のセグメントを取り除いてからパスとして解釈する
+                                self.url.query = None;
+                                self.shorten_path();
+                                self.state = State::Path;
+                                continue;
+                            }
+                            None => {}
+                        }
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#relative-slash-state
+                State::RelativeSlash => {
+                    if self.url.is_special() && (c == Some('/') || c == Some('\\')) {
+                        // //example.com のようなスキーム相対URL
+                        self.state = State::SpecialAuthorityIgnoreSlashes;
+                    } else if c == Some('/') {
+                        self.state = State::Authority;
+                    } else {
+                        // /index.html のような絶対パス
+                        let base = self.base.clone().expect("base should exist in relative slash state");
+                        self.copy_authority_from_base(&base);
+                        self.state = State::Path;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#special-authority-slashes-state
+                State::SpecialAuthoritySlashes => {
+                    self.state = State::SpecialAuthorityIgnoreSlashes;
+                    if c == Some('/') && self.remaining_starts_with("/") {
+                        self.pos += 1;
+                    } else {
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#special-authority-ignore-slashes-state
+                // http:///example.com のような余分なスラッシュは無視する
+                State::SpecialAuthorityIgnoreSlashes => {
+                    if c != Some('/') && c != Some('\\') {
+                        self.state = State::Authority;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#authority-state
+                State::Authority => {
+                    if c == Some('@') {
+                        // user:pass@host のユーザー情報を処理する
+                        if self.at_sign_seen {
+                            self.buf.insert_str(0, "%40");
+                        }
+                        self.at_sign_seen = true;
+
+                        for code_point in self.buf.chars() {
+                            if code_point == ':' && !self.password_token_seen {
+                                self.password_token_seen = true;
+                                continue;
+                            }
+                            if self.password_token_seen {
+                                utf8_percent_encode(
+                                    code_point,
+                                    PercentEncodeSet::Userinfo,
+                                    &mut self.url.password,
+                                );
+                            } else {
+                                utf8_percent_encode(
+                                    code_point,
+                                    PercentEncodeSet::Userinfo,
+                                    &mut self.url.username,
+                                );
+                            }
+                        }
+                        self.buf = String::new();
+                    } else if matches!(c, None | Some('/') | Some('?') | Some('#'))
+                        || (self.url.is_special() && c == Some('\\'))
+                    {
+                        if self.at_sign_seen && self.buf.is_empty() {
+                            return Err(UrlError::MissingHost {
+                                offset: self.byte_offset(self.pos),
+                            });
+                        }
+                        // バッファに溜めた文字をホストとして読み直す
+                        self.pos -= self.buf.chars().count();
+                        self.buf = String::new();
+                        self.state = State::Host;
+                        continue;
+                    } else {
+                        self.buf.push(c.expect("c should be Some"));
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#host-state
+                State::Host => {
+                    if c == Some(':') && !self.inside_brackets {
+                        if self.buf.is_empty() {
+                            return Err(UrlError::MissingHost {
+                                offset: self.byte_offset(self.pos),
+                            });
+                        }
+                        self.url.host = Some(Host::parse(&self.buf, !self.url.is_special())?);
+                        self.buf = String::new();
+                        self.state = State::Port;
+                    } else if matches!(c, None | Some('/') | Some('?') | Some('#'))
+                        || (self.url.is_special() && c == Some('\\'))
+                    {
+                        if self.url.is_special() && self.buf.is_empty() {
+                            return Err(UrlError::MissingHost {
+                                offset: self.byte_offset(self.pos),
+                            });
+                        }
+                        self.url.host = Some(if self.buf.is_empty() {
+                            Host::Empty
+                        } else {
+                            Host::parse(&self.buf, !self.url.is_special())?
+                        });
+                        self.buf = String::new();
+                        self.state = State::PathStart;
+                        continue;
+                    } else {
+                        let c = c.expect("c should be Some");
+                        if c == '[' {
+                            self.inside_brackets = true;
+                        }
+                        if c == ']' {
+                            self.inside_brackets = false;
+                        }
+                        self.buf.push(c);
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#port-state
+                State::Port => match c {
+                    Some(c) if c.is_ascii_digit() => self.buf.push(c),
+                    _ if matches!(c, None | Some('/') | Some('?') | Some('#'))
+                        || (self.url.is_special() && c == Some('\\')) =>
+                    {
+                        if !self.buf.is_empty() {
+                            let port: u16 = match self.buf.parse() {
+                                Ok(port) => port,
+                                Err(_) => {
+                                    return Err(UrlError::InvalidPort {
+                                        port: self.buf.clone(),
+                                        offset: self
+                                            .byte_offset(self.pos - self.buf.chars().count()),
+                                    })
+                                }
+                            };
+                            // スキームのデフォルトポートと同じ場合は省略する
+                            self.url.port = if default_port(&self.url.scheme) == Some(port) {
+                                None
+                            } else {
+                                Some(port)
+                            };
+                            self.buf = String::new();
+                        }
+                        self.state = State::PathStart;
+                        continue;
+                    }
+                    _ => {
+                        // ポートの開始位置から区切り文字までをエラーに含める
+                        let start = self.pos - self.buf.chars().count();
+                        return Err(UrlError::InvalidPort {
+                            port: self.input[start..]
+                                .iter()
+                                .take_while(|c| !matches!(c, '/' | '?' | '#' | '\\'))
+                                .collect(),
+                            offset: self.byte_offset(start),
+                        });
+                    }
+                },
+
+                // https://url.spec.whatwg.org/#file-state
+                State::File => {
+                    self.url.scheme = "file".to_string();
+                    self.url.host = Some(Host::Empty);
+                    if c == Some('/') || c == Some('\\') {
+                        self.state = State::FileSlash;
+                    } else if let Some(base) = self.base.clone().filter(|base| base.scheme == "file") {
+                        self.url.host = base.host;
+                        self.url.path = base.path;
+                        self.url.query = base.query;
+                        match c {
+                            Some('?') => {
+                                self.url.query = Some(String::new());
+                                self.state = State::Query;
+                            }
+                            Some('#') => {
+                                self.url.fragment = Some(String::new());
+                                self.state = State::Fragment;
+                            }
+                            Some(_) => {
+                                self.url.query = None;
+                                if self.starts_with_windows_drive_letter() {
+                                    self.url.path = UrlPath::Segments(Vec::new());
+                                } else {
+                                    self.shorten_path();
+                                }
+                                self.state = State::Path;
+                                continue;
+                            }
+                            None => {}
+                        }
+                    } else {
+                        self.state = State::Path;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#file-slash-state
+                State::FileSlash => {
+                    if c == Some('/') || c == Some('\\') {
+                        self.state = State::FileHost;
+                    } else {
+                        if let Some(base) = self.base.clone().filter(|base| base.scheme == "file") {
+                            self.url.host = base.host;
+                            // 基準URLのドライブ文字（C:）を引き継ぐ
+                            if !self.starts_with_windows_drive_letter() {
+                                if let UrlPath::Segments(ref segments) = base.path {
+                                    if segments.first().is_some_and(|s| is_normalized_windows_drive_letter(s)) {
+                                        self.push_path_segment(segments[0].clone());
+                                    }
+                                }
+                            }
+                        }
+                        self.state = State::Path;
+                        continue;
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#file-host-state
+                State::FileHost => {
+                    if matches!(c, None | Some('/') | Some('\\') | Some('?') | Some('#')) {
+                        if is_windows_drive_letter(&self.buf) {
+                            // file://C:/ のような場合はホストではなくパスの一部として扱う
+                            self.state = State::Path;
+                        } else if self.buf.is_empty() {
+                            self.url.host = Some(Host::Empty);
+                            self.state = State::PathStart;
+                        } else {
+                            let mut host = Host::parse(&self.buf, !self.url.is_special())?;
+                            if host == Host::Domain("localhost".to_string()) {
+                                host = Host::Empty;
+                            }
+                            self.url.host = Some(host);
+                            self.buf = String::new();
+                            self.state = State::PathStart;
+                        }
+                        continue;
+                    }
+                    self.buf.push(c.expect("c should be Some"));
+                }
+
+                // https://url.spec.whatwg.org/#path-start-state
+                State::PathStart => {
+                    if self.url.is_special() {
+                        self.state = State::Path;
+                        if c != Some('/') && c != Some('\\') {
+                            continue;
+                        }
+                    } else if c == Some('?') {
+                        self.url.query = Some(String::new());
+                        self.state = State::Query;
+                    } else if c == Some('#') {
+                        self.url.fragment = Some(String::new());
+                        self.state = State::Fragment;
+                    } else if c.is_some() {
+                        self.state = State::Path;
+                        if c != Some('/') {
+                            continue;
+                        }
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#path-state
+                State::Path => {
+                    let is_separator = c == Some('/') || (self.url.is_special() && c == Some('\\'));
+                    if c.is_none() || is_separator || c == Some('?') || c == Some('#') {
+                        if is_double_dot_segment(&self.buf) {
+                            // .. のときは1つ上の階層に戻る
+                            self.shorten_path();
+                            if !is_separator {
+                                self.push_path_segment(String::new());
+                            }
+                        } else if is_single_dot_segment(&self.buf) {
+                            if !is_separator {
+                                self.push_path_segment(String::new());
+                            }
+                        } else {
+                            let mut segment = core::mem::take(&mut self.buf);
+                            if self.url.scheme == "file"
+                                && self.path_is_empty()
+                                && is_windows_drive_letter(&segment)
+                            {
+                                // C| を C: に正規化する
+                                segment.replace_range(1..2, ":");
+                            }
+                            self.push_path_segment(segment);
+                        }
+                        self.buf = String::new();
+
+                        if c == Some('?') {
+                            self.url.query = Some(String::new());
+                            self.state = State::Query;
+                        }
+                        if c == Some('#') {
+                            self.url.fragment = Some(String::new());
+                            self.state = State::Fragment;
+                        }
+                    } else if let Some(c) = c {
+                        utf8_percent_encode(c, PercentEncodeSet::Path, &mut self.buf);
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#cannot-be-a-base-url-path-state
+                State::OpaquePath => match c {
+                    Some('?') => {
+                        self.url.query = Some(String::new());
+                        self.state = State::Query;
+                    }
+                    Some('#') => {
+                        self.url.fragment = Some(String::new());
+                        self.state = State::Fragment;
+                    }
+                    Some(c) => {
+                        if let UrlPath::Opaque(ref mut path) = self.url.path {
+                            utf8_percent_encode(c, PercentEncodeSet::C0Control, path);
+                        }
+                    }
+                    None => {}
+                },
+
+                // https://url.spec.whatwg.org/#query-state
+                State::Query => {
+                    if c.is_none() || c == Some('#') {
+                        let set = if self.url.is_special() {
+                            PercentEncodeSet::SpecialQuery
+                        } else {
+                            PercentEncodeSet::Query
+                        };
+                        let encoded = utf8_percent_encode_str(&self.buf, set);
+                        self.url
+                            .query
+                            .get_or_insert_with(String::new)
+                            .push_str(&encoded);
+                        self.buf = String::new();
+
+                        if c == Some('#') {
+                            self.url.fragment = Some(String::new());
+                            self.state = State::Fragment;
+                        }
+                    } else if let Some(c) = c {
+                        self.buf.push(c);
+                    }
+                }
+
+                // https://url.spec.whatwg.org/#fragment-state
+                State::Fragment => {
+                    if let Some(c) = c {
+                        let fragment = self.url.fragment.get_or_insert_with(String::new);
+                        utf8_percent_encode(c, PercentEncodeSet::Fragment, fragment);
+                    }
+                }
+            }
+
+            // EOFを処理し終えたら終了する
+            if c.is_none() {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        Ok(self.url)
+    }
+}
+
+// https://url.spec.whatwg.org/#windows-drive-letter
+fn is_windows_drive_letter(s: &str) -> bool {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(a), Some(b), None) => a.is_ascii_alphabetic() && (b == ':' || b == '|'),
+        _ => false,
+    }
+}
+
+// https://url.spec.whatwg.org/#normalized-windows-drive-letter
+fn is_normalized_windows_drive_letter(s: &str) -> bool {
+    is_windows_drive_letter(s) && s.ends_with(':')
+}
+
+// https://url.spec.whatwg.org/#single-dot-path-segment
+fn is_single_dot_segment(s: &str) -> bool {
+    s == "." || s.eq_ignore_ascii_case("%2e")
+}
+
+// https://url.spec.whatwg.org/#double-dot-path-segment
+fn is_double_dot_segment(s: &str) -> bool {
+    let s = s.to_ascii_lowercase();
+    matches!(s.as_str(), ".." | ".%2e" | "%2e." | "%2e%2e")
+}
+
+/// https://url.spec.whatwg.org/#url-parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    SchemeStart,
+    Scheme,
+    NoScheme,
+    SpecialRelativeOrAuthority,
+    PathOrAuthority,
+    Relative,
+    RelativeSlash,
+    SpecialAuthoritySlashes,
+    SpecialAuthorityIgnoreSlashes,
+    Authority,
+    Host,
+    Port,
+    File,
+    FileSlash,
+    FileHost,
+    PathStart,
+    Path,
+    OpaquePath,
+    Query,
+    Fragment,
+}
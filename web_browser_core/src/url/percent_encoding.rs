@@ -0,0 +1,108 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://url.spec.whatwg.org/#percent-encoded-bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentEncodeSet {
+    C0Control,      // https://url.spec.whatwg.org/#c0-control-percent-encode-set
+    Fragment,       // https://url.spec.whatwg.org/#fragment-percent-encode-set
+    Query,          // https://url.spec.whatwg.org/#query-percent-encode-set
+    SpecialQuery,   // https://url.spec.whatwg.org/#special-query-percent-encode-set
+    Path,           // https://url.spec.whatwg.org/#path-percent-encode-set
+    Userinfo,       // https://url.spec.whatwg.org/#userinfo-percent-encode-set
+    Component,      // https://url.spec.whatwg.org/#component-percent-encode-set
+    FormUrlencoded, // https://url.spec.whatwg.org/#application-x-www-form-urlencoded-percent-encode-set
+}
+
+impl PercentEncodeSet {
+    // 各集合は前の集合を包含する形で定義されているので、順番に判定する
+    pub fn contains(&self, b: u8) -> bool {
+        // C0制御文字とASCII範囲外のバイトはすべての集合に含まれる
+        if !(0x20..=0x7e).contains(&b) {
+            return true;
+        }
+
+        match self {
+            PercentEncodeSet::C0Control => false,
+            PercentEncodeSet::Fragment => matches!(b, b' ' | b'"' | b'<' | b'>' | b'`'),
+            PercentEncodeSet::Query => matches!(b, b' ' | b'"' | b'#' | b'<' | b'>'),
+            PercentEncodeSet::SpecialQuery => b == b'\'' || PercentEncodeSet::Query.contains(b),
+            PercentEncodeSet::Path => {
+                matches!(b, b'?' | b'`' | b'{' | b'}') || PercentEncodeSet::Query.contains(b)
+            }
+            PercentEncodeSet::Userinfo => {
+                matches!(
+                    b,
+                    b'/' | b':' | b';' | b'=' | b'@' | b'[' | b'\\' | b']' | b'^' | b'|'
+                ) || PercentEncodeSet::Path.contains(b)
+            }
+            PercentEncodeSet::Component => {
+                matches!(b, b'$' | b'%' | b'&' | b'+' | b',')
+                    || PercentEncodeSet::Userinfo.contains(b)
+            }
+            PercentEncodeSet::FormUrlencoded => {
+                matches!(b, b'!' | b'\'' | b'(' | b')' | b'~')
+                    || PercentEncodeSet::Component.contains(b)
+            }
+        }
+    }
+}
+
+// https://url.spec.whatwg.org/#percent-encode
+fn percent_encode_byte(b: u8, output: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    output.push('%');
+    output.push(HEX[(b >> 4) as usize] as char);
+    output.push(HEX[(b & 0x0f) as usize] as char);
+}
+
+// https://url.spec.whatwg.org/#utf-8-percent-encode
+// 1文字をUTF-8のバイト列に変換し、集合に含まれるバイトを%XXの形式にしてoutputに追加する
+pub fn utf8_percent_encode(c: char, set: PercentEncodeSet, output: &mut String) {
+    let mut bytes = [0u8; 4];
+    for b in c.encode_utf8(&mut bytes).bytes() {
+        if set.contains(b) {
+            percent_encode_byte(b, output);
+        } else {
+            output.push(b as char);
+        }
+    }
+}
+
+// 文字列全体をパーセントエンコードする
+pub fn utf8_percent_encode_str(input: &str, set: PercentEncodeSet) -> String {
+    let mut output = String::new();
+    for c in input.chars() {
+        utf8_percent_encode(c, set, &mut output);
+    }
+    output
+}
+
+// https://url.spec.whatwg.org/#percent-decode
+// %XXの形式を1バイトに戻す。%のあとに16進数が2つ続かない場合はそのまま残す
+pub fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        if b == b'%' && i + 2 < input.len() {
+            if let (Some(high), Some(low)) = (hex_value(input[i + 1]), hex_value(input[i + 2])) {
+                output.push(high << 4 | low);
+                i += 3;
+                continue;
+            }
+        }
+        output.push(b);
+        i += 1;
+    }
+    output
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
@@ -0,0 +1,209 @@
+use crate::url::percent_encoding::{percent_decode, utf8_percent_encode, PercentEncodeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::Display;
+
+// https://url.spec.whatwg.org/#interface-urlsearchparams
+// クエリ文字列を名前と値の組の順序付きリストとして扱う
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchParams {
+    list: Vec<(String, String)>,
+}
+
+impl SearchParams {
+    pub fn new() -> Self {
+        Self { list: Vec::new() }
+    }
+
+    // https://url.spec.whatwg.org/#concept-urlencoded-parser
+    // 先頭の ? は取り除いてから解釈する
+    pub fn parse(input: &str) -> Self {
+        let input = input.strip_prefix('?').unwrap_or(input);
+        let mut list = Vec::new();
+        for sequence in input.split('&') {
+            if sequence.is_empty() {
+                continue;
+            }
+            let (name, value) = sequence.split_once('=').unwrap_or((sequence, ""));
+            list.push((decode(name), decode(value)));
+        }
+        Self { list }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    // 名前と値の組を順番通りに返す
+    pub fn pairs(&self) -> Vec<(String, String)> {
+        self.list.clone()
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-get
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.list
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-getall
+    pub fn get_all(&self, name: &str) -> Vec<String> {
+        self.list
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-has
+    pub fn has(&self, name: &str) -> bool {
+        self.list.iter().any(|(n, _)| n == name)
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-append
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.list.push((String::from(name), String::from(value)));
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-set
+    // 最初に見つかった組の値を置き換え、同じ名前の残りの組は削除する
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.list.iter().position(|(n, _)| n == name) {
+            Some(index) => {
+                self.list[index].1 = String::from(value);
+                let mut i = 0;
+                self.list.retain(|(n, _)| {
+                    let keep = i <= index || n != name;
+                    i += 1;
+                    keep
+                });
+            }
+            None => self.append(name, value),
+        }
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-delete
+    pub fn delete(&mut self, name: &str) {
+        self.list.retain(|(n, _)| n != name);
+    }
+
+    // https://url.spec.whatwg.org/#dom-urlsearchparams-sort
+    // 名前をUTF-16のコードユニットで比較する安定ソート。同じ名前の組の順番は保たれる
+    pub fn sort(&mut self) {
+        self.list.sort_by(|(a, _), (b, _)| compare_utf16(a, b));
+    }
+}
+
+// https://url.spec.whatwg.org/#concept-urlencoded-serializer
+impl Display for SearchParams {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut output = String::new();
+        for (i, (name, value)) in self.list.iter().enumerate() {
+            if i > 0 {
+                output.push('&');
+            }
+            encode(name, &mut output);
+            output.push('=');
+            encode(value, &mut output);
+        }
+        write!(f, "{}", output)
+    }
+}
+
+// + を空白に置き換えてからパーセントエンコードを戻す
+fn decode(input: &str) -> String {
+    let bytes: Vec<u8> = input
+        .bytes()
+        .map(|b| if b == b'+' { b' ' } else { b })
+        .collect();
+    String::from_utf8_lossy(&percent_decode(&bytes)).into_owned()
+}
+
+// 空白は + に、それ以外はapplication/x-www-form-urlencodedの集合でパーセントエンコードする
+fn encode(input: &str, output: &mut String) {
+    for c in input.chars() {
+        if c == ' ' {
+            output.push('+');
+        } else {
+            utf8_percent_encode(c, PercentEncodeSet::FormUrlencoded, output);
+        }
+    }
+}
+
+fn compare_utf16(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse() {
+        let params = SearchParams::parse("?a=1&b=hello+world&&c&a=%E3%81%82");
+        assert_eq!(
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "hello world".to_string()),
+                ("c".to_string(), "".to_string()),
+                ("a".to_string(), "あ".to_string()),
+            ],
+            params.pairs()
+        );
+        assert_eq!(Some("1".to_string()), params.get("a"));
+        assert_eq!(vec!["1".to_string(), "あ".to_string()], params.get_all("a"));
+        assert_eq!(None, params.get("d"));
+        assert!(params.has("c"));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut params = SearchParams::new();
+        params.append("q", "rust & web");
+        params.append("lang", "日本");
+        params.append("sym", "a+b=c~!");
+        assert_eq!(
+            "q=rust+%26+web&lang=%E6%97%A5%E6%9C%AC&sym=a%2Bb%3Dc%7E%21",
+            params.to_string()
+        );
+        assert_eq!(params, SearchParams::parse(&params.to_string()));
+    }
+
+    #[test]
+    fn test_set_and_delete() {
+        let mut params = SearchParams::parse("a=1&b=2&a=3&c=4");
+        params.set("a", "5");
+        assert_eq!("a=5&b=2&c=4", params.to_string());
+        params.set("d", "6");
+        assert_eq!("a=5&b=2&c=4&d=6", params.to_string());
+        params.delete("b");
+        assert_eq!("a=5&c=4&d=6", params.to_string());
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut params = SearchParams::parse("z=1&a=2&z=0&b=3&a=1");
+        params.sort();
+        assert_eq!("a=2&a=1&b=3&z=1&z=0", params.to_string());
+
+        // UTF-16のコードユニットで比較するため、サロゲートペアはU+FFFDより前に並ぶ
+        let mut params = SearchParams::parse("%EF%BF%BD=x&%F0%9F%8C%88=y");
+        params.sort();
+        assert_eq!(
+            vec!["🌈", "\u{FFFD}"],
+            params
+                .pairs()
+                .iter()
+                .map(|(n, _)| n.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::renderer::dom::node::Node;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec::Vec;
 use core::cell::RefCell;
 
 pub fn convert_dom_to_string(root: &Option<Rc<RefCell<Node>>>) -> String {
@@ -19,3 +20,65 @@ fn convert_dom_to_string_internal(node: &Option<Rc<RefCell<Node>>>, depth: usize
         convert_dom_to_string_internal(&n.borrow().next_sibling(), depth, result);
     }
 }
+
+// https://www.rfc-editor.org/rfc/rfc4648#section-4
+// 3バイトずつ6ビットの4文字に変換する。足りない分は = で埋める
+pub fn encode_base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::new();
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+// https://infra.spec.whatwg.org/#forgiving-base64-decode
+// 空白を取り除き、末尾の = を省略したものも受け付ける。不正な入力の場合はNoneを返す
+pub fn decode_base64(input: &[u8]) -> Option<Vec<u8>> {
+    let mut data: Vec<u8> = input.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    if data.len() % 4 == 0 {
+        if data.ends_with(b"==") {
+            data.truncate(data.len() - 2);
+        } else if data.ends_with(b"=") {
+            data.truncate(data.len() - 1);
+        }
+    }
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut output = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for b in data {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(output)
+}